@@ -0,0 +1,57 @@
+use rand_core::{RngCore, OsRng};
+
+use curve25519_dalek::edwards::EdwardsPoint;
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+
+use monero_serai::{Commitment, random_scalar, ringct::bulletproofs::Bulletproofs};
+
+const OUTPUTS: [usize; 5] = [1, 2, 4, 8, 16];
+
+fn commitments(outputs: usize) -> Vec<Commitment> {
+  (0 .. outputs).map(|_| Commitment::new(random_scalar(&mut OsRng), OsRng.next_u64())).collect()
+}
+
+fn name(plus: bool) -> &'static str {
+  if plus {
+    "Bulletproofs+"
+  } else {
+    "Bulletproofs"
+  }
+}
+
+fn prove(c: &mut Criterion) {
+  for plus in [false, true] {
+    let mut group = c.benchmark_group(format!("{} proving", name(plus)));
+    for outputs in OUTPUTS {
+      let commitments = commitments(outputs);
+      group.bench_with_input(
+        BenchmarkId::from_parameter(outputs),
+        &commitments,
+        |b, commitments| b.iter(|| Bulletproofs::prove(&mut OsRng, commitments, plus).unwrap()),
+      );
+    }
+    group.finish();
+  }
+}
+
+fn verify(c: &mut Criterion) {
+  for plus in [false, true] {
+    let mut group = c.benchmark_group(format!("{} verification", name(plus)));
+    for outputs in OUTPUTS {
+      let commitments = commitments(outputs);
+      let bp = Bulletproofs::prove(&mut OsRng, &commitments, plus).unwrap();
+      let commitments =
+        commitments.iter().map(Commitment::calculate).collect::<Vec<EdwardsPoint>>();
+      group.bench_with_input(
+        BenchmarkId::from_parameter(outputs),
+        &commitments,
+        |b, commitments| b.iter(|| assert!(bp.verify(&mut OsRng, commitments))),
+      );
+    }
+    group.finish();
+  }
+}
+
+criterion_group!(benches, prove, verify);
+criterion_main!(benches);
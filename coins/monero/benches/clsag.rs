@@ -0,0 +1,88 @@
+use core::ops::Deref;
+
+use zeroize::Zeroizing;
+use rand_core::{RngCore, OsRng};
+
+use curve25519_dalek::{constants::ED25519_BASEPOINT_TABLE, edwards::EdwardsPoint};
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+
+use monero_serai::{
+  Commitment, random_scalar,
+  wallet::Decoys,
+  ringct::{
+    generate_key_image,
+    clsag::{ClsagInput, Clsag},
+  },
+};
+
+const RING_LEN: u64 = 16;
+const MSG: [u8; 32] = [1; 32];
+
+struct Signed {
+  clsag: Clsag,
+  ring: Vec<[EdwardsPoint; 2]>,
+  image: EdwardsPoint,
+  pseudo_out: EdwardsPoint,
+}
+
+// Create the specified amount of CLSAGs, each with a distinct ring
+fn clsags(amount: usize) -> Vec<Signed> {
+  (0 .. amount)
+    .map(|_| {
+      let real = OsRng.next_u64() % RING_LEN;
+      let mut secrets = None;
+      let mut ring = vec![];
+      for i in 0 .. RING_LEN {
+        let dest = Zeroizing::new(random_scalar(&mut OsRng));
+        let commitment = Commitment::new(random_scalar(&mut OsRng), OsRng.next_u64());
+        ring.push([dest.deref() * &ED25519_BASEPOINT_TABLE, commitment.calculate()]);
+        if i == real {
+          secrets = Some((dest, commitment));
+        }
+      }
+      let (dest, commitment) = secrets.unwrap();
+
+      let image = generate_key_image(&dest);
+      let (clsag, pseudo_out) = Clsag::sign(
+        &mut OsRng,
+        vec![(
+          dest,
+          image,
+          ClsagInput::new(
+            commitment,
+            Decoys {
+              i: u8::try_from(real).unwrap(),
+              offsets: (1 ..= RING_LEN).collect(),
+              ring: ring.clone(),
+            },
+          )
+          .unwrap(),
+        )],
+        random_scalar(&mut OsRng),
+        MSG,
+      )
+      .swap_remove(0);
+      Signed { clsag, ring, image, pseudo_out }
+    })
+    .collect()
+}
+
+fn verify(c: &mut Criterion) {
+  let mut group = c.benchmark_group("CLSAG verification");
+  for amount in [1, 16, 64] {
+    let signed = clsags(amount);
+
+    group.bench_with_input(BenchmarkId::from_parameter(amount), &signed, |b, signed| {
+      b.iter(|| {
+        for signed in signed {
+          signed.clsag.verify(&signed.ring, &signed.image, &signed.pseudo_out, &MSG).unwrap();
+        }
+      })
+    });
+  }
+  group.finish();
+}
+
+criterion_group!(benches, verify);
+criterion_main!(benches);
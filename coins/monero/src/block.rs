@@ -1,10 +1,49 @@
 use std::io::{self, Read, Write};
 
 use crate::{
+  hash,
   serialize::*,
   transaction::{Input, Transaction},
 };
 
+// Block 202612 was created with a bugged Merkle tree hash, so its ID isn't the hash of its hashing
+// blob. Monero special cases it by the hash of the block's serialization
+const CORRUPT_BLOCK_NUMBER: u64 = 202612;
+const CORRUPT_BLOCK_BLOB_HASH: &str =
+  "3a8a2b3a29b50fc86ff73dd087ea43c6f0d6b8f936c849194d5c84c737903966";
+const CORRUPT_BLOCK_ID: &str = "bbd604d2ba11ba27935e006ed39c9bfdd99b76bf4a50654bc1e1e61217962698";
+
+/// Calculate the root of Monero's Merkle tree over the specified hashes.
+///
+/// Leaves are paired from the end, with the leading leaves carried up a layer as needed to make
+/// the tree's width a power of two. This panics if no hashes are provided.
+pub fn merkle_root(hashes: &[[u8; 32]]) -> [u8; 32] {
+  let pair = |hashes: &[[u8; 32]]| {
+    let mut buf = [0; 64];
+    buf[.. 32].copy_from_slice(&hashes[0]);
+    buf[32 ..].copy_from_slice(&hashes[1]);
+    hash(&buf)
+  };
+
+  match hashes.len() {
+    0 => panic!("calculating the Merkle root of no hashes"),
+    1 => hashes[0],
+    2 => pair(hashes),
+    len => {
+      // The largest power of two less than the amount of hashes
+      let width = len.next_power_of_two() >> 1;
+      let carried = (2 * width) - len;
+      let mut layer = hashes[.. carried].to_vec();
+      layer.extend(hashes[carried ..].chunks(2).map(pair));
+
+      while layer.len() > 2 {
+        layer = layer.chunks(2).map(pair).collect();
+      }
+      pair(&layer)
+    }
+  }
+}
+
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct BlockHeader {
   pub major_version: u64,
@@ -55,6 +94,42 @@ impl Block {
     }
   }
 
+  /// The hashes of the transactions in this block, starting with the miner transaction.
+  pub fn tx_hashes(&self) -> Vec<[u8; 32]> {
+    let mut hashes = Vec::with_capacity(1 + self.txs.len());
+    hashes.push(self.miner_tx.hash());
+    hashes.extend(&self.txs);
+    hashes
+  }
+
+  /// The root of the Merkle tree over this block's transactions.
+  pub fn tx_merkle_root(&self) -> [u8; 32] {
+    merkle_root(&self.tx_hashes())
+  }
+
+  /// Serialize the block as needed to calculate its ID.
+  pub fn serialize_hashable(&self) -> Vec<u8> {
+    let mut blob = self.header.serialize();
+    blob.extend(self.tx_merkle_root());
+    write_varint(&(1 + u64::try_from(self.txs.len()).unwrap()), &mut blob).unwrap();
+    blob
+  }
+
+  /// The ID of this block.
+  pub fn hash(&self) -> [u8; 32] {
+    if let Some(Input::Gen(CORRUPT_BLOCK_NUMBER)) = self.miner_tx.prefix.inputs.get(0) {
+      if hex::encode(hash(&self.serialize())) == CORRUPT_BLOCK_BLOB_HASH {
+        return hex::decode(CORRUPT_BLOCK_ID).unwrap().try_into().unwrap();
+      }
+    }
+
+    let hashable = self.serialize_hashable();
+    let mut buf = Vec::with_capacity(8 + hashable.len());
+    write_varint(&u64::try_from(hashable.len()).unwrap(), &mut buf).unwrap();
+    buf.extend(hashable);
+    hash(&buf)
+  }
+
   pub fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
     self.header.write(w)?;
     self.miner_tx.write(w)?;
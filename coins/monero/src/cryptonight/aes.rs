@@ -0,0 +1,60 @@
+use aes::{
+  Block,
+  hazmat::{cipher_round, inv_mix_columns},
+};
+
+// The round constants used by the first 10 round keys
+const RCON: [u8; 4] = [1, 2, 4, 8];
+
+// Apply AES's S-box to each byte of a word
+// This uses the aes crate's round function, as it's constant time, unlike indexing an S-box
+fn sub_word(word: [u8; 4]) -> [u8; 4] {
+  // With every column set to the word, ShiftRows doesn't change the block
+  // Undoing MixColumns then leaves solely SubBytes
+  let mut block = Block::default();
+  for column in block.chunks_mut(4) {
+    column.copy_from_slice(&word);
+  }
+  cipher_round(&mut block, &Block::default());
+  inv_mix_columns(&mut block);
+  block[.. 4].try_into().unwrap()
+}
+
+/// Expand a 256-bit key into the first 10 round keys of AES-256's key schedule.
+pub(crate) fn expand_key(key: &[u8; 32]) -> [[u8; 16]; 10] {
+  let mut words = [[0; 4]; 40];
+  for (i, word) in key.chunks(4).enumerate() {
+    words[i].copy_from_slice(word);
+  }
+
+  for i in 8 .. 40 {
+    let mut word = words[i - 1];
+    if (i % 8) == 0 {
+      word.rotate_left(1);
+      word = sub_word(word);
+      word[0] ^= RCON[(i / 8) - 1];
+    } else if (i % 8) == 4 {
+      word = sub_word(word);
+    }
+    for b in 0 .. 4 {
+      words[i][b] = words[i - 8][b] ^ word[b];
+    }
+  }
+
+  let mut keys = [[0; 16]; 10];
+  for (i, key) in keys.iter_mut().enumerate() {
+    for w in 0 .. 4 {
+      key[(w * 4) .. ((w + 1) * 4)].copy_from_slice(&words[(i * 4) + w]);
+    }
+  }
+  keys
+}
+
+/// A single AES encryption round (SubBytes, ShiftRows, MixColumns, AddRoundKey).
+///
+/// CryptoNight uses this round, without AES's initial key addition or special final round.
+pub(crate) fn round(block: &mut [u8; 16], key: &[u8; 16]) {
+  let mut res = Block::from(*block);
+  cipher_round(&mut res, &Block::from(*key));
+  block.copy_from_slice(&res);
+}
@@ -0,0 +1,152 @@
+// The original CryptoNight (variant 0), which wallet2 derives encryption keys with
+
+use zeroize::Zeroizing;
+
+use groestl::{Digest, Groestl256};
+use jh::Jh256;
+use skein::{digest::consts::U32, Skein512};
+
+// blake-hash implements an older version of digest, with its own Digest trait
+use blake_hash::{Digest as _, Blake256};
+
+pub(crate) mod aes;
+
+const MEMORY: usize = 1 << 21;
+const ITERATIONS: usize = 1 << 20;
+
+// Keccak, with the same rate as Keccak-256, yielding the entire state
+fn keccak1600(data: &[u8]) -> [u64; 25] {
+  const RATE: usize = 136;
+
+  let mut padded = data.to_vec();
+  padded.push(1);
+  while (padded.len() % RATE) != 0 {
+    padded.push(0);
+  }
+  *padded.last_mut().unwrap() |= 0x80;
+
+  let mut state = [0; 25];
+  for block in padded.chunks(RATE) {
+    for (lane, bytes) in state.iter_mut().zip(block.chunks(8)) {
+      *lane ^= u64::from_le_bytes(bytes.try_into().unwrap());
+    }
+    keccak::f1600(&mut state);
+  }
+  state
+}
+
+fn to_bytes(state: &[u64; 25]) -> Zeroizing<[u8; 200]> {
+  let mut bytes = Zeroizing::new([0; 200]);
+  for (bytes, lane) in bytes.chunks_mut(8).zip(state) {
+    bytes.copy_from_slice(&lane.to_le_bytes());
+  }
+  bytes
+}
+
+fn to_block(bytes: &[u8]) -> [u8; 16] {
+  bytes.try_into().unwrap()
+}
+
+fn xor(a: &[u8; 16], b: &[u8; 16]) -> [u8; 16] {
+  let mut res = [0; 16];
+  for i in 0 .. 16 {
+    res[i] = a[i] ^ b[i];
+  }
+  res
+}
+
+fn halves(block: &[u8; 16]) -> (u64, u64) {
+  (
+    u64::from_le_bytes(block[.. 8].try_into().unwrap()),
+    u64::from_le_bytes(block[8 ..].try_into().unwrap()),
+  )
+}
+
+fn from_halves(a: u64, b: u64) -> [u8; 16] {
+  let mut res = [0; 16];
+  res[.. 8].copy_from_slice(&a.to_le_bytes());
+  res[8 ..].copy_from_slice(&b.to_le_bytes());
+  res
+}
+
+// The index of the 16-byte block of the scratchpad a block addresses
+fn address(block: &[u8; 16]) -> usize {
+  (usize::try_from(u32::from_le_bytes(block[.. 4].try_into().unwrap())).unwrap() & (MEMORY - 1)) /
+    16
+}
+
+pub(crate) fn cryptonight(data: &[u8]) -> [u8; 32] {
+  let mut state = keccak1600(data);
+  let bytes = to_bytes(&state);
+
+  // Fill the scratchpad by repeatedly encrypting the state's bytes 64 .. 192
+  let keys = Zeroizing::new(aes::expand_key(bytes[.. 32].try_into().unwrap()));
+  let mut text = Zeroizing::new(bytes[64 .. 192].to_vec());
+  let mut scratchpad = Zeroizing::new(vec![[0; 16]; MEMORY / 16]);
+  for chunk in scratchpad.chunks_mut(8) {
+    for (block, scratch) in text.chunks_mut(16).zip(chunk) {
+      let mut encrypted = to_block(block);
+      for key in keys.iter() {
+        aes::round(&mut encrypted, key);
+      }
+      block.copy_from_slice(&encrypted);
+      *scratch = encrypted;
+    }
+  }
+
+  // The memory-hard loop
+  let mut a = xor(&to_block(&bytes[.. 16]), &to_block(&bytes[32 .. 48]));
+  let mut b = xor(&to_block(&bytes[16 .. 32]), &to_block(&bytes[48 .. 64]));
+  for _ in 0 .. (ITERATIONS / 2) {
+    let j = address(&a);
+    let mut c = scratchpad[j];
+    aes::round(&mut c, &a);
+    scratchpad[j] = xor(&c, &b);
+    b = c;
+
+    let j = address(&c);
+    let (c0, _) = halves(&c);
+    let (d0, d1) = halves(&scratchpad[j]);
+    let product = u128::from(c0) * u128::from(d0);
+    let (a0, a1) = halves(&a);
+    let sum = from_halves(
+      a0.wrapping_add(u64::try_from(product >> 64).unwrap()),
+      a1.wrapping_add(product as u64),
+    );
+    a = xor(&sum, &from_halves(d0, d1));
+    scratchpad[j] = sum;
+  }
+
+  // Fold the scratchpad back into the state, with keys from the state's bytes 32 .. 64
+  let keys = Zeroizing::new(aes::expand_key(bytes[32 .. 64].try_into().unwrap()));
+  let mut text = Zeroizing::new(bytes[64 .. 192].to_vec());
+  for chunk in scratchpad.chunks(8) {
+    for (block, scratch) in text.chunks_mut(16).zip(chunk) {
+      let mut encrypted = xor(&to_block(block), scratch);
+      for key in keys.iter() {
+        aes::round(&mut encrypted, key);
+      }
+      block.copy_from_slice(&encrypted);
+    }
+  }
+
+  let mut bytes = to_bytes(&state);
+  bytes[64 .. 192].copy_from_slice(&text);
+  for (lane, bytes) in state.iter_mut().zip(bytes.chunks(8)) {
+    *lane = u64::from_le_bytes(bytes.try_into().unwrap());
+  }
+  keccak::f1600(&mut state);
+
+  // Finish with one of four hash functions, as selected by the state
+  let bytes = to_bytes(&state);
+  let bytes = bytes.as_ref();
+  let mut hash = [0; 32];
+  match bytes[0] & 3 {
+    0 => hash.copy_from_slice(&Blake256::digest(bytes)),
+    1 => hash.copy_from_slice(&Groestl256::digest(bytes)),
+    2 => hash.copy_from_slice(&Jh256::digest(bytes)),
+    3 => hash.copy_from_slice(&Skein512::<U32>::digest(bytes)),
+    _ => unreachable!(),
+  }
+  hash
+}
@@ -21,6 +21,11 @@ pub use monero_generators::H;
 mod serialize;
 use serialize::{read_byte, read_u16};
 
+mod cryptonight;
+
+/// Ring signature structs and functionality.
+pub mod ring_signatures;
+
 /// RingCT structs and functionality.
 pub mod ringct;
 
@@ -31,6 +36,8 @@ pub mod block;
 
 /// Monero daemon RPC interface.
 pub mod rpc;
+/// Transaction verification functionality.
+pub mod verify;
 /// Wallet functionality, enabling scanning and sending transactions.
 pub mod wallet;
 
@@ -67,6 +74,15 @@ impl Protocol {
     }
   }
 
+  /// Whether or not the specified version uses view tags.
+  pub fn view_tags(&self) -> bool {
+    match self {
+      Protocol::v14 => false,
+      Protocol::v16 => true,
+      Protocol::Custom { .. } => false,
+    }
+  }
+
   pub(crate) fn write<W: io::Write>(&self, w: &mut W) -> io::Result<()> {
     match self {
       Protocol::v14 => w.write_all(&[0, 14]),
@@ -0,0 +1,134 @@
+#![allow(non_snake_case)]
+
+use core::ops::Deref;
+use std::io::{self, Read, Write};
+
+use zeroize::Zeroizing;
+
+use rand_core::{RngCore, CryptoRng};
+
+use curve25519_dalek::{constants::ED25519_BASEPOINT_TABLE, scalar::Scalar, edwards::EdwardsPoint};
+
+use crate::{random_scalar, hash_to_scalar, ringct::hash_to_point, serialize::*};
+
+/// A signature for a single ring member, as used by ring signatures prior to RingCT.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Signature {
+  pub c: Scalar,
+  pub r: Scalar,
+}
+
+impl Signature {
+  pub fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+    write_scalar(&self.c, w)?;
+    write_scalar(&self.r, w)
+  }
+
+  pub fn read<R: Read>(r: &mut R) -> io::Result<Signature> {
+    Ok(Signature { c: read_scalar(r)?, r: read_scalar(r)? })
+  }
+}
+
+/// A ring signature, as used by version 1 transactions.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RingSignature {
+  pub sigs: Vec<Signature>,
+}
+
+impl RingSignature {
+  pub fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+    write_raw_vec(Signature::write, &self.sigs, w)
+  }
+
+  pub fn read<R: Read>(members: usize, r: &mut R) -> io::Result<RingSignature> {
+    Ok(RingSignature { sigs: read_raw_vec(Signature::read, members, r)? })
+  }
+
+  // Create the signatures for every ring member other than the signer, returning them with the
+  // challenge for the signer, whose nonce commitments are L and R
+  // The signer's signature is left with a zero response, which must be set by the caller
+  pub(crate) fn sign_core<Rng: RngCore + CryptoRng>(
+    rng: &mut Rng,
+    msg: &[u8; 32],
+    ring: &[EdwardsPoint],
+    i: usize,
+    key_image: &EdwardsPoint,
+    L: EdwardsPoint,
+    R: EdwardsPoint,
+  ) -> (RingSignature, Scalar) {
+    let mut buf = Vec::with_capacity(32 + (64 * ring.len()));
+    buf.extend(msg);
+    let mut sigs = Vec::with_capacity(ring.len());
+    let mut sum = Scalar::zero();
+    for (j, P) in ring.iter().enumerate() {
+      if j == i {
+        buf.extend(L.compress().to_bytes());
+        buf.extend(R.compress().to_bytes());
+        sigs.push(Signature { c: Scalar::zero(), r: Scalar::zero() });
+        continue;
+      }
+
+      let sig = Signature { c: random_scalar(rng), r: random_scalar(rng) };
+      let L = EdwardsPoint::vartime_double_scalar_mul_basepoint(&sig.c, P, &sig.r);
+      let R = (sig.r * hash_to_point(*P)) + (sig.c * key_image);
+      buf.extend(L.compress().to_bytes());
+      buf.extend(R.compress().to_bytes());
+      sum += sig.c;
+      sigs.push(sig);
+    }
+
+    let c = hash_to_scalar(&buf) - sum;
+    sigs[i].c = c;
+    (RingSignature { sigs }, c)
+  }
+
+  /// Sign a message with the specified ring, where the signer's key is at index `i`.
+  ///
+  /// Returns the signature with the key image it's for.
+  pub fn sign<Rng: RngCore + CryptoRng>(
+    rng: &mut Rng,
+    msg: &[u8; 32],
+    ring: &[EdwardsPoint],
+    i: usize,
+    key: &Zeroizing<Scalar>,
+  ) -> (RingSignature, EdwardsPoint) {
+    let H = hash_to_point(ring[i]);
+    let key_image = key.deref() * H;
+
+    let nonce = Zeroizing::new(random_scalar(rng));
+    let (mut signature, c) = RingSignature::sign_core(
+      rng,
+      msg,
+      ring,
+      i,
+      &key_image,
+      nonce.deref() * &ED25519_BASEPOINT_TABLE,
+      nonce.deref() * H,
+    );
+    signature.sigs[i].r = nonce.deref() - (c * key.deref());
+    (signature, key_image)
+  }
+
+  /// Verify the ring signature, where the message is the hash of the transaction's prefix for
+  /// transactions, or the proof's message for transaction proofs.
+  ///
+  /// The key image must be torsion free, which is checked when deserializing it.
+  pub fn verify(&self, msg: &[u8; 32], ring: &[EdwardsPoint], key_image: &EdwardsPoint) -> bool {
+    if ring.len() != self.sigs.len() {
+      return false;
+    }
+
+    let mut buf = Vec::with_capacity(32 + (64 * ring.len()));
+    buf.extend(msg);
+    let mut sum = Scalar::zero();
+    for (P, sig) in ring.iter().zip(&self.sigs) {
+      let L = EdwardsPoint::vartime_double_scalar_mul_basepoint(&sig.c, P, &sig.r);
+      let R = (sig.r * hash_to_point(*P)) + (sig.c * key_image);
+      buf.extend(L.compress().to_bytes());
+      buf.extend(R.compress().to_bytes());
+      sum += sig.c;
+    }
+
+    sum == hash_to_scalar(&buf)
+  }
+}
@@ -0,0 +1,111 @@
+#![allow(non_snake_case)]
+
+use std::io::{self, Read, Write};
+
+use lazy_static::lazy_static;
+
+use curve25519_dalek::{scalar::Scalar, edwards::EdwardsPoint};
+
+use crate::{H, hash_to_scalar, serialize::*};
+
+lazy_static! {
+  // 2**i * H, for each bit of an amount
+  static ref H_POW_2: [EdwardsPoint; 64] = {
+    let mut res = [*H; 64];
+    for i in 1 .. 64 {
+      res[i] = res[i - 1] + res[i - 1];
+    }
+    res
+  };
+}
+
+/// A Borromean ring signature, as used by Monero's original range proofs.
+///
+/// The scalars are kept as bytes, as Monero didn't require s0 and s1 to be reduced and there are
+/// historical signatures with unreduced values. Reducing them would prevent re-serializing these
+/// signatures as they were on chain.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct BorromeanSignatures {
+  pub s0: [[u8; 32]; 64],
+  pub s1: [[u8; 32]; 64],
+  pub ee: [u8; 32],
+}
+
+impl BorromeanSignatures {
+  pub fn read<R: Read>(r: &mut R) -> io::Result<BorromeanSignatures> {
+    Ok(BorromeanSignatures {
+      s0: read_array(read_bytes, r)?,
+      s1: read_array(read_bytes, r)?,
+      ee: read_bytes(r)?,
+    })
+  }
+
+  pub fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+    for s0 in &self.s0 {
+      w.write_all(s0)?;
+    }
+    for s1 in &self.s1 {
+      w.write_all(s1)?;
+    }
+    w.write_all(&self.ee)
+  }
+
+  fn verify(&self, keys_a: &[EdwardsPoint], keys_b: &[EdwardsPoint]) -> bool {
+    let ee = Scalar::from_bytes_mod_order(self.ee);
+
+    let mut transcript = [0; 32 * 64];
+    for i in 0 .. 64 {
+      let LL = EdwardsPoint::vartime_double_scalar_mul_basepoint(
+        &ee,
+        &keys_a[i],
+        &Scalar::from_bytes_mod_order(self.s0[i]),
+      );
+      let LV = EdwardsPoint::vartime_double_scalar_mul_basepoint(
+        &hash_to_scalar(LL.compress().as_bytes()),
+        &keys_b[i],
+        &Scalar::from_bytes_mod_order(self.s1[i]),
+      );
+      transcript[(i * 32) .. ((i + 1) * 32)].copy_from_slice(LV.compress().as_bytes());
+    }
+
+    // ee is compared as bytes, as Monero would reject an unreduced ee
+    hash_to_scalar(&transcript).to_bytes() == self.ee
+  }
+}
+
+/// A Borromean range proof, proving a commitment's amount is within [0 .. 2^64).
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct BorromeanRange {
+  pub sigs: BorromeanSignatures,
+  pub bit_commitments: [EdwardsPoint; 64],
+}
+
+impl BorromeanRange {
+  pub fn read<R: Read>(r: &mut R) -> io::Result<BorromeanRange> {
+    Ok(BorromeanRange {
+      sigs: BorromeanSignatures::read(r)?,
+      bit_commitments: read_array(read_point, r)?,
+    })
+  }
+
+  pub fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+    self.sigs.write(w)?;
+    write_raw_vec(write_point, &self.bit_commitments, w)
+  }
+
+  /// Verify the range proof for the specified commitment.
+  pub fn verify(&self, commitment: &EdwardsPoint) -> bool {
+    if &self.bit_commitments.iter().sum::<EdwardsPoint>() != commitment {
+      return false;
+    }
+
+    // Each bit commitment must be a commitment to either 0 or 2**i
+    let bit_commitments_minus_bit = self
+      .bit_commitments
+      .iter()
+      .zip(H_POW_2.iter())
+      .map(|(commitment, bit)| commitment - bit)
+      .collect::<Vec<_>>();
+    self.sigs.verify(&self.bit_commitments, &bit_commitments_minus_bit)
+  }
+}
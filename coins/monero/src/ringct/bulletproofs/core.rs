@@ -105,20 +105,18 @@ pub(crate) fn alpha_rho<R: RngCore + CryptoRng>(
   (ar, (vector_exponent(generators, aL, aR) + (EdwardsPoint::generator() * ar)) * *INV_EIGHT)
 }
 
-pub(crate) fn LR_statements(
-  a: &ScalarVector,
+pub(crate) fn LR_statements<A: IntoIterator<Item = Scalar>>(
+  a: A,
   G_i: &[EdwardsPoint],
-  b: &ScalarVector,
+  b: &[Scalar],
   H_i: &[EdwardsPoint],
   cL: Scalar,
   U: EdwardsPoint,
 ) -> Vec<(Scalar, EdwardsPoint)> {
   let mut res = a
-    .0
-    .iter()
-    .cloned()
+    .into_iter()
     .zip(G_i.iter().cloned())
-    .chain(b.0.iter().cloned().zip(H_i.iter().cloned()))
+    .chain(b.iter().cloned().zip(H_i.iter().cloned()))
     .collect::<Vec<_>>();
   res.push((cL, U));
   res
@@ -34,24 +34,50 @@ pub enum Bulletproofs {
 }
 
 impl Bulletproofs {
-  pub(crate) fn fee_weight(plus: bool, outputs: usize) -> usize {
+  // Calculate the weight clawback for a proof of the specified amount of outputs, returning it
+  // and the length of the L/R vectors
+  pub(crate) fn calculate_clawback(plus: bool, outputs: usize) -> (usize, usize) {
+    let LR_len = usize::try_from(usize::BITS - (outputs - 1).leading_zeros()).unwrap();
+    (Bulletproofs::clawback(plus, 1 << LR_len), LR_len + LOG_N)
+  }
+
+  // Calculate the weight clawback for proofs of the specified amount of padded outputs, in total
+  // A proof's size is logarithmic to the amount of outputs, yet verifying it is linear, so Monero
+  // claws back the difference between the size of a proof for all of the padded outputs and the
+  // size of one proof per two outputs
+  // Monero sums the padded outputs of every proof in a transaction, which is only guaranteed to be
+  // a power of two when there's a single proof
+  pub(crate) fn clawback(plus: bool, padded_outputs: usize) -> usize {
+    if padded_outputs <= 2 {
+      return 0;
+    }
+
     let fields = if plus { 6 } else { 9 };
+    let LR_len =
+      usize::try_from(usize::BITS - (padded_outputs - 1).leading_zeros()).unwrap() + LOG_N;
+    let base = ((fields + (2 * (LOG_N + 1))) * 32) / 2;
+    let size = (fields + (2 * LR_len)) * 32;
+    ((base * padded_outputs) - size) * 4 / 5
+  }
 
-    // TODO: Shouldn't this use u32/u64?
-    #[allow(non_snake_case)]
-    let mut LR_len = usize::try_from(usize::BITS - (outputs - 1).leading_zeros()).unwrap();
-    let padded_outputs = 1 << LR_len;
-    LR_len += LOG_N;
-
-    let len = (fields + (2 * LR_len)) * 32;
-    len +
-      if padded_outputs <= 2 {
-        0
-      } else {
-        let base = ((fields + (2 * (LOG_N + 1))) * 32) / 2;
-        let size = (fields + (2 * LR_len)) * 32;
-        ((base * padded_outputs) - size) * 4 / 5
-      }
+  // The amount of outputs this proof was padded to, as Monero's n_bulletproof_max_amounts
+  // calculates it, or None if the length of its L vector is invalid
+  pub(crate) fn padded_outputs(&self) -> Option<usize> {
+    let L_len = match self {
+      Bulletproofs::Original(bp) => bp.L.len(),
+      Bulletproofs::Plus(bp) => bp.L.len(),
+    };
+    L_len
+      .checked_sub(LOG_N)
+      .and_then(|LR_len| 1usize.checked_shl(LR_len.try_into().ok()?))
+      .filter(|padded_outputs| *padded_outputs <= MAX_OUTPUTS)
+  }
+
+  pub(crate) fn fee_weight(plus: bool, outputs: usize) -> usize {
+    let fields = if plus { 6 } else { 9 };
+    let (clawback, LR_len) = Bulletproofs::calculate_clawback(plus, outputs);
+    // The L and R vectors are each prefixed by their length
+    (32 * (fields + (2 * LR_len))) + (2 * varint_len(LR_len)) + clawback
   }
 
   /// Prove the list of commitments are within [0 .. 2^64).
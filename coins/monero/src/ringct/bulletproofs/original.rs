@@ -3,7 +3,9 @@ use rand_core::{RngCore, CryptoRng};
 
 use zeroize::Zeroize;
 
-use curve25519_dalek::{scalar::Scalar as DalekScalar, edwards::EdwardsPoint as DalekPoint};
+use curve25519_dalek::{
+  scalar::Scalar as DalekScalar, traits::VartimeMultiscalarMul, edwards::EdwardsPoint as DalekPoint,
+};
 
 use group::{ff::Field, Group};
 use dalek_ff_group::{ED25519_BASEPOINT_POINT as G, Scalar, EdwardsPoint};
@@ -16,7 +18,7 @@ include!(concat!(env!("OUT_DIR"), "/generators.rs"));
 
 lazy_static! {
   static ref ONE_N: ScalarVector = ScalarVector(vec![Scalar::ONE; N]);
-  static ref IP12: Scalar = inner_product(&ONE_N, &TWO_N);
+  static ref IP12: Scalar = inner_product(&ONE_N.0, &TWO_N.0);
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -45,8 +47,8 @@ impl OriginalStruct {
     let commitments_points = commitments.iter().map(Commitment::calculate).collect::<Vec<_>>();
     let (mut cache, _) = hash_commitments(commitments_points.clone());
 
-    let (sL, sR) =
-      ScalarVector((0 .. (MN * 2)).map(|_| Scalar::random(&mut *rng)).collect::<Vec<_>>()).split();
+    let sL = ScalarVector((0 .. MN).map(|_| Scalar::random(&mut *rng)).collect());
+    let sR = ScalarVector((0 .. MN).map(|_| Scalar::random(&mut *rng)).collect());
 
     let (mut alpha, A) = alpha_rho(&mut *rng, &GENERATORS, &aL, &aR);
     let (mut rho, S) = alpha_rho(&mut *rng, &GENERATORS, &sL, &sR);
@@ -71,8 +73,8 @@ impl OriginalStruct {
     let r1 = yMN * sR;
 
     let (T1, T2, x, mut taux) = {
-      let t1 = inner_product(&l0, &r1) + inner_product(&l1, &r0);
-      let t2 = inner_product(&l1, &r1);
+      let t1 = inner_product(&l0.0, &r1.0) + inner_product(&l1.0, &r0.0);
+      let t2 = inner_product(&l1.0, &r1.0);
 
       let mut tau1 = Scalar::random(&mut *rng);
       let mut tau2 = Scalar::random(&mut *rng);
@@ -101,7 +103,7 @@ impl OriginalStruct {
     let l = &l0 + &(l1 * x);
     let r = &r0 + &(r1 * x);
 
-    let t = inner_product(&l, &r);
+    let t = inner_product(&l.0, &r.0);
 
     let x_ip =
       hash_cache(&mut cache, &[x.to_bytes(), taux.to_bytes(), mu.to_bytes(), t.to_bytes()]);
@@ -114,7 +116,10 @@ impl OriginalStruct {
 
     let mut G_proof = GENERATORS.G[.. a.len()].to_vec();
     let mut H_proof = GENERATORS.H[.. a.len()].to_vec();
-    H_proof.iter_mut().zip(yinvpow.0.iter()).for_each(|(this_H, yinvpow)| *this_H *= yinvpow);
+    // These are all public, allowing this to be variable time
+    H_proof.iter_mut().zip(yinvpow.0.iter()).for_each(|(this_H, yinvpow)| {
+      *this_H = EdwardsPoint(DalekPoint::vartime_multiscalar_mul([yinvpow.0], [this_H.0]))
+    });
     let U = *H * x_ip;
 
     let mut L = Vec::with_capacity(logMN);
@@ -124,26 +129,26 @@ impl OriginalStruct {
       let (aL, aR) = a.split();
       let (bL, bR) = b.split();
 
-      let cL = inner_product(&aL, &bR);
-      let cR = inner_product(&aR, &bL);
+      let cL = inner_product(aL, bR);
+      let cR = inner_product(aR, bL);
 
       let (G_L, G_R) = G_proof.split_at(aL.len());
       let (H_L, H_R) = H_proof.split_at(aL.len());
 
-      let L_i = prove_multiexp(&LR_statements(&aL, G_R, &bR, H_L, cL, U));
-      let R_i = prove_multiexp(&LR_statements(&aR, G_L, &bL, H_R, cR, U));
+      let L_i = prove_multiexp(&LR_statements(aL.iter().cloned(), G_R, bR, H_L, cL, U));
+      let R_i = prove_multiexp(&LR_statements(aR.iter().cloned(), G_L, bL, H_R, cR, U));
       L.push(L_i);
       R.push(R_i);
 
       let w = hash_cache(&mut cache, &[L_i.compress().to_bytes(), R_i.compress().to_bytes()]);
       let winv = w.invert().unwrap();
 
-      a = (aL * w) + (aR * winv);
-      b = (bL * winv) + (bR * w);
+      a.fold(w, winv);
+      b.fold(winv, w);
 
       if a.len() != 1 {
-        G_proof = hadamard_fold(G_L, G_R, winv, w);
-        H_proof = hadamard_fold(H_L, H_R, w, winv);
+        hadamard_fold(&mut G_proof, winv, w);
+        hadamard_fold(&mut H_proof, w, winv);
       }
     }
 
@@ -96,21 +96,22 @@ impl PlusStruct {
     while a.len() != 1 {
       let (aL, aR) = a.split();
       let (bL, bR) = b.split();
+      let half = aL.len();
 
-      let cL = weighted_inner_product(&aL, &bR, y);
-      let cR = weighted_inner_product(&(&aR * ypow[aR.len()]), &bL, y);
+      let cL = weighted_inner_product(aL, bR, y);
+      let cR = ypow[half] * weighted_inner_product(aR, bL, y);
 
       let (mut dL, mut dR) = (Scalar::random(&mut *rng), Scalar::random(&mut *rng));
 
-      let (G_L, G_R) = G_proof.split_at(aL.len());
-      let (H_L, H_R) = H_proof.split_at(aL.len());
+      let (G_L, G_R) = G_proof.split_at(half);
+      let (H_L, H_R) = H_proof.split_at(half);
 
-      let mut L_i = LR_statements(&(&aL * yinvpow[aL.len()]), G_R, &bR, H_L, cL, *H);
+      let mut L_i = LR_statements(aL.iter().map(|aL| *aL * yinvpow[half]), G_R, bR, H_L, cL, *H);
       L_i.push((dL, G));
       let L_i = prove_multiexp(&L_i);
       L.push(L_i);
 
-      let mut R_i = LR_statements(&(&aR * ypow[aR.len()]), G_L, &bL, H_R, cR, *H);
+      let mut R_i = LR_statements(aR.iter().map(|aR| *aR * ypow[half]), G_L, bL, H_R, cR, *H);
       R_i.push((dR, G));
       let R_i = prove_multiexp(&R_i);
       R.push(R_i);
@@ -118,11 +119,11 @@ impl PlusStruct {
       let w = hash_cache(&mut cache, &[L_i.compress().to_bytes(), R_i.compress().to_bytes()]);
       let winv = w.invert().unwrap();
 
-      G_proof = hadamard_fold(G_L, G_R, winv, w * yinvpow[aL.len()]);
-      H_proof = hadamard_fold(H_L, H_R, w, winv);
+      hadamard_fold(&mut G_proof, winv, w * yinvpow[half]);
+      hadamard_fold(&mut H_proof, w, winv);
 
-      a = (&aL * w) + (aR * (winv * ypow[aL.len()]));
-      b = (bL * winv) + (bR * w);
+      a.fold(w, winv * ypow[half]);
+      b.fold(winv, w);
 
       alpha1 += (dL * (w * w)) + (dR * (winv * winv));
 
@@ -2,6 +2,8 @@ use core::ops::{Add, Sub, Mul, Index};
 
 use zeroize::{Zeroize, ZeroizeOnDrop};
 
+use curve25519_dalek::{traits::VartimeMultiscalarMul, edwards::EdwardsPoint as DalekPoint};
+
 use group::ff::Field;
 use dalek_ff_group::{Scalar, EdwardsPoint};
 
@@ -88,9 +90,21 @@ impl ScalarVector {
     self.0.len()
   }
 
-  pub(crate) fn split(self) -> (ScalarVector, ScalarVector) {
-    let (l, r) = self.0.split_at(self.0.len() / 2);
-    (ScalarVector(l.to_vec()), ScalarVector(r.to_vec()))
+  // Split this vector into its halves, without copying them
+  pub(crate) fn split(&self) -> (&[Scalar], &[Scalar]) {
+    self.0.split_at(self.0.len() / 2)
+  }
+
+  // Fold this vector in half, in place, with each element becoming (l * a) + (r * b)
+  pub(crate) fn fold(&mut self, a: Scalar, b: Scalar) {
+    let half = self.len() / 2;
+    let (l, r) = self.0.split_at_mut(half);
+    for (l, r) in l.iter_mut().zip(r.iter()) {
+      *l = (*l * a) + (*r * b);
+    }
+    // Truncating doesn't clear the removed elements, which may be private
+    self.0[half ..].iter_mut().for_each(Zeroize::zeroize);
+    self.0.truncate(half);
   }
 }
 
@@ -101,17 +115,25 @@ impl Index<usize> for ScalarVector {
   }
 }
 
-pub(crate) fn inner_product(a: &ScalarVector, b: &ScalarVector) -> Scalar {
-  (a * b).sum()
+pub(crate) fn inner_product(a: &[Scalar], b: &[Scalar]) -> Scalar {
+  debug_assert_eq!(a.len(), b.len());
+  a.iter().zip(b).map(|(a, b)| *a * *b).sum()
 }
 
 pub(crate) fn weighted_powers(x: Scalar, len: usize) -> ScalarVector {
   ScalarVector(ScalarVector::powers(x, len + 1).0[1 ..].to_vec())
 }
 
-pub(crate) fn weighted_inner_product(a: &ScalarVector, b: &ScalarVector, y: Scalar) -> Scalar {
+pub(crate) fn weighted_inner_product(a: &[Scalar], b: &[Scalar], y: Scalar) -> Scalar {
+  debug_assert_eq!(a.len(), b.len());
   // y ** 0 is not used as a power
-  (a * b * weighted_powers(y, a.len())).sum()
+  let mut res = Scalar::ZERO;
+  let mut y_pow = y;
+  for (a, b) in a.iter().zip(b) {
+    res += *a * *b * y_pow;
+    y_pow *= y;
+  }
+  res
 }
 
 impl Mul<&[EdwardsPoint]> for &ScalarVector {
@@ -122,15 +144,13 @@ impl Mul<&[EdwardsPoint]> for &ScalarVector {
   }
 }
 
-pub(crate) fn hadamard_fold(
-  l: &[EdwardsPoint],
-  r: &[EdwardsPoint],
-  a: Scalar,
-  b: Scalar,
-) -> Vec<EdwardsPoint> {
-  let mut res = Vec::with_capacity(l.len() / 2);
-  for i in 0 .. l.len() {
-    res.push(multiexp(&[(a, l[i]), (b, r[i])]));
+// Fold the points in half, in place, with each point becoming (l * a) + (r * b)
+// The generators and challenges folded with are public, allowing this to be variable time
+pub(crate) fn hadamard_fold(points: &mut Vec<EdwardsPoint>, a: Scalar, b: Scalar) {
+  let half = points.len() / 2;
+  let (l, r) = points.split_at_mut(half);
+  for (l, r) in l.iter_mut().zip(r.iter()) {
+    *l = EdwardsPoint(DalekPoint::vartime_multiscalar_mul([a.0, b.0], [l.0, r.0]));
   }
-  res
+  points.truncate(half);
 }
@@ -8,17 +8,19 @@ use thiserror::Error;
 use rand_core::{RngCore, CryptoRng};
 
 use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
-use subtle::{ConstantTimeEq, Choice, CtOption};
+use subtle::{ConstantTimeEq, ConditionallySelectable};
 
 use curve25519_dalek::{
-  constants::ED25519_BASEPOINT_TABLE,
+  constants::{ED25519_BASEPOINT_TABLE, ED25519_BASEPOINT_POINT},
   scalar::Scalar,
   traits::{IsIdentity, VartimePrecomputedMultiscalarMul},
   edwards::{EdwardsPoint, VartimeEdwardsPrecomputation},
 };
 
 use crate::{
-  Commitment, random_scalar, hash_to_scalar, wallet::decoys::Decoys, ringct::hash_to_point,
+  Commitment, random_scalar, hash_to_scalar,
+  wallet::decoys::Decoys,
+  ringct::{raw_hash_to_point, hash_to_point},
   serialize::*,
 };
 
@@ -31,6 +33,8 @@ pub(crate) use multisig::add_key_image_share;
 
 lazy_static! {
   static ref INV_EIGHT: Scalar = Scalar::from(8u8).invert();
+  static ref G_PRECOMP: VartimeEdwardsPrecomputation =
+    VartimeEdwardsPrecomputation::new([ED25519_BASEPOINT_POINT]);
 }
 
 /// Errors returned when CLSAG signing fails.
@@ -120,9 +124,11 @@ fn core(
   to_hash.extend([0; 32 - PREFIX_AGG_0_LEN]);
 
   let mut P = Vec::with_capacity(n);
+  let mut P_bytes = Vec::with_capacity(n);
   for member in ring {
     P.push(member[0]);
-    to_hash.extend(member[0].compress().to_bytes());
+    P_bytes.push(member[0].compress().to_bytes());
+    to_hash.extend(P_bytes[P_bytes.len() - 1]);
   }
 
   let mut C = Vec::with_capacity(n);
@@ -171,29 +177,32 @@ fn core(
   }
 
   // Perform the core loop
-  let mut c1 = CtOption::new(Scalar::zero(), Choice::from(0));
+  // When signing for the last ring member, the initial c is c1
+  let mut c1 = c;
   for i in (start .. end).map(|i| i % n) {
-    // This will only execute once and shouldn't need to be constant time. Making it constant time
-    // removes the risk of branch prediction creating timing differences depending on ring index
-    // however
-    c1 = c1.or_else(|| CtOption::new(c, i.ct_eq(&0)));
-
     let c_p = mu_P * c;
     let c_c = mu_C * c;
 
-    let L = (&s[i] * &ED25519_BASEPOINT_TABLE) + (c_p * P[i]) + (c_c * C[i]);
-    let PH = hash_to_point(P[i]);
-    // Shouldn't be an issue as all of the variables in this vartime statement are public
-    let R = (s[i] * PH) + images_precomp.vartime_multiscalar_mul([c_p, c_c]);
+    // Shouldn't be an issue as all of the variables in these vartime statements are public
+    // The only secret s is the real spend's, which isn't used here when signing
+    let L = G_PRECOMP.vartime_mixed_multiscalar_mul([s[i]], [c_p, c_c], [P[i], C[i]]);
+    let PH = raw_hash_to_point(P_bytes[i]);
+    let R = images_precomp.vartime_mixed_multiscalar_mul([c_p, c_c], [s[i]], [PH]);
 
     to_hash.truncate(((2 * n) + 3) * 32);
     to_hash.extend(L.compress().to_bytes());
     to_hash.extend(R.compress().to_bytes());
     c = hash_to_scalar(&to_hash);
+
+    // The c calculated after the last ring member is c1. When verifying, this is the c1 to check
+    // This will only execute once and shouldn't need to be constant time. Making it constant time
+    // removes the risk of branch prediction creating timing differences depending on ring index
+    // however
+    c1.conditional_assign(&c, i.ct_eq(&(n - 1)));
   }
 
   // This first tuple is needed to continue signing, the latter is the c to be tested/worked with
-  ((D, c * mu_P, c * mu_C), c1.unwrap_or(c))
+  ((D, c * mu_P, c * mu_C), c1)
 }
 
 /// CLSAG signature, as used in Monero.
@@ -279,6 +288,10 @@ impl Clsag {
   }
 
   /// Verify the CLSAG signature against the given Transaction data.
+  ///
+  /// There's no batch verification path. Each round's challenge is the hash of the prior round's
+  /// points, so the rounds, and accordingly multiple CLSAGs, can't be combined into a single
+  /// multiexponentiation.
   pub fn verify(
     &self,
     ring: &[[EdwardsPoint; 2]],
@@ -0,0 +1,160 @@
+#![allow(non_snake_case)]
+
+use std::io::{self, Read, Write};
+
+use thiserror::Error;
+
+use curve25519_dalek::{scalar::Scalar, traits::IsIdentity, edwards::EdwardsPoint};
+
+use crate::{H, hash_to_scalar, ringct::hash_to_point, serialize::*};
+
+/// Errors returned when MLSAG verification fails.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Error)]
+pub enum MlsagError {
+  #[error("invalid ring")]
+  InvalidRing,
+  #[error("invalid amount of key images")]
+  InvalidAmountOfKeyImages,
+  #[error("invalid ss")]
+  InvalidSs,
+  #[error("invalid key image")]
+  InvalidImage,
+  #[error("invalid ci")]
+  InvalidCi,
+}
+
+/// A matrix of ring members, with each ring member being its keys followed by a commitment which
+/// must be to zero.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RingMatrix {
+  matrix: Vec<Vec<EdwardsPoint>>,
+}
+
+impl RingMatrix {
+  fn new(matrix: Vec<Vec<EdwardsPoint>>) -> Result<RingMatrix, MlsagError> {
+    // Monero requires there to be at least two ring members
+    if matrix.len() < 2 {
+      Err(MlsagError::InvalidRing)?;
+    }
+    // Each member must have at least one key and the commitment
+    let member_len = matrix[0].len();
+    if (member_len < 2) || matrix.iter().any(|member| member.len() != member_len) {
+      Err(MlsagError::InvalidRing)?;
+    }
+    Ok(RingMatrix { matrix })
+  }
+
+  /// Construct the matrix for an MLSAG over a single input, as used by every type other than
+  /// `RctType::MlsagAggregate`.
+  pub fn individual(
+    ring: &[[EdwardsPoint; 2]],
+    pseudo_out: EdwardsPoint,
+  ) -> Result<RingMatrix, MlsagError> {
+    RingMatrix::new(ring.iter().map(|member| vec![member[0], member[1] - pseudo_out]).collect())
+  }
+
+  /// Construct the matrix for an MLSAG over every input, as used by `RctType::MlsagAggregate`.
+  ///
+  /// Each ring must be the same length, with the commitment proven to be zero being the sum of
+  /// the ring members' commitments minus the output commitments and the fee.
+  pub fn aggregate(
+    rings: &[Vec<[EdwardsPoint; 2]>],
+    commitments: &[EdwardsPoint],
+    fee: u64,
+  ) -> Result<RingMatrix, MlsagError> {
+    let members = rings.get(0).ok_or(MlsagError::InvalidRing)?.len();
+    if rings.iter().any(|ring| ring.len() != members) {
+      Err(MlsagError::InvalidRing)?;
+    }
+
+    let outputs = commitments.iter().sum::<EdwardsPoint>() + (Scalar::from(fee) * *H);
+    RingMatrix::new(
+      (0 .. members)
+        .map(|m| {
+          let mut member = rings.iter().map(|ring| ring[m][0]).collect::<Vec<_>>();
+          member.push(rings.iter().map(|ring| ring[m][1]).sum::<EdwardsPoint>() - outputs);
+          member
+        })
+        .collect(),
+    )
+  }
+
+  /// The amount of ring members.
+  pub fn members(&self) -> usize {
+    self.matrix.len()
+  }
+
+  /// The length of each ring member, being the amount of keys plus one for the commitment.
+  pub fn member_len(&self) -> usize {
+    self.matrix[0].len()
+  }
+}
+
+/// An MLSAG, as used by RingCT prior to CLSAG.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Mlsag {
+  pub ss: Vec<Vec<Scalar>>,
+  pub cc: Scalar,
+}
+
+impl Mlsag {
+  pub fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+    for ss in &self.ss {
+      write_raw_vec(write_scalar, ss, w)?;
+    }
+    write_scalar(&self.cc, w)
+  }
+
+  pub fn read<R: Read>(members: usize, member_len: usize, r: &mut R) -> io::Result<Mlsag> {
+    Ok(Mlsag {
+      ss: (0 .. members)
+        .map(|_| read_raw_vec(read_scalar, member_len, r))
+        .collect::<Result<_, _>>()?,
+      cc: read_scalar(r)?,
+    })
+  }
+
+  /// Verify the MLSAG against the ring and the key images for each key within a ring member.
+  pub fn verify(
+    &self,
+    msg: &[u8; 32],
+    ring: &RingMatrix,
+    key_images: &[EdwardsPoint],
+  ) -> Result<(), MlsagError> {
+    // There's a key image for every key, yet not the commitment
+    if key_images.len() != (ring.member_len() - 1) {
+      Err(MlsagError::InvalidAmountOfKeyImages)?;
+    }
+    if (self.ss.len() != ring.members()) || self.ss.iter().any(|ss| ss.len() != ring.member_len()) {
+      Err(MlsagError::InvalidSs)?;
+    }
+    // Key images must also be torsion free, which is checked when deserializing them
+    if key_images.iter().any(IsIdentity::is_identity) {
+      Err(MlsagError::InvalidImage)?;
+    }
+
+    let mut buf = Vec::with_capacity(32 * (1 + (3 * ring.member_len())));
+    let mut ci = self.cc;
+    for (member, ss) in ring.matrix.iter().zip(&self.ss) {
+      buf.clear();
+      buf.extend(msg);
+      for (i, (P, s)) in member.iter().zip(ss).enumerate() {
+        let L = EdwardsPoint::vartime_double_scalar_mul_basepoint(&ci, P, s);
+        buf.extend(P.compress().to_bytes());
+        buf.extend(L.compress().to_bytes());
+
+        // The commitment, being the last element, doesn't have a key image
+        if let Some(I) = key_images.get(i) {
+          let R = (s * hash_to_point(*P)) + (ci * I);
+          buf.extend(R.compress().to_bytes());
+        }
+      }
+      ci = hash_to_scalar(&buf);
+    }
+
+    if ci != self.cc {
+      Err(MlsagError::InvalidCi)?;
+    }
+    Ok(())
+  }
+}
@@ -1,22 +1,28 @@
-use std::fmt::Debug;
+use std::{fmt::Debug, sync::Arc};
 
 use thiserror::Error;
 
+use async_trait::async_trait;
+
+use futures::lock::{Mutex, MutexGuard};
+
 use curve25519_dalek::edwards::{EdwardsPoint, CompressedEdwardsY};
 
 use serde::{Serialize, Deserialize, de::DeserializeOwned};
 use serde_json::{Value, json};
 
-use digest_auth::AuthContext;
-use reqwest::{Client, RequestBuilder};
-
 use crate::{
   Protocol,
   transaction::{Input, Timelock, Transaction},
   block::Block,
-  wallet::Fee,
+  wallet::{Fee, OutputDistribution, OutputLock},
 };
 
+#[cfg(feature = "http_rpc")]
+mod http;
+#[cfg(feature = "http_rpc")]
+pub use http::HttpRpc;
+
 #[derive(Deserialize, Debug)]
 pub struct EmptyResponse {}
 #[derive(Deserialize, Debug)]
@@ -73,48 +79,42 @@ fn rpc_point(point: &str) -> Result<EdwardsPoint, RpcError> {
   .ok_or_else(|| RpcError::InvalidPoint(point.to_string()))
 }
 
-#[derive(Clone, Debug)]
-pub struct Rpc {
-  client: Client,
-  userpass: Option<(String, String)>,
-  url: String,
+/// A connection to a Monero daemon, transporting RPC calls.
+///
+/// Implementors are left to handle anything transport specific, such as authentication.
+#[async_trait]
+pub trait RpcConnection: Clone + Debug + Send + Sync {
+  /// Perform a POST request to the specified route with the specified body, returning the
+  /// response's body.
+  ///
+  /// Routes ending in `.bin` have an epee-serialized body. All others have a JSON body.
+  async fn post(&self, route: &str, body: Vec<u8>) -> Result<Vec<u8>, RpcError>;
 }
 
-impl Rpc {
-  /// Create a new RPC connection.
-  /// A daemon requiring authentication can be used via including the username and password in the
-  /// URL.
-  pub fn new(mut url: String) -> Result<Rpc, RpcError> {
-    // Parse out the username and password
-    let userpass = if url.contains('@') {
-      let url_clone = url;
-      let split_url = url_clone.split('@').collect::<Vec<_>>();
-      if split_url.len() != 2 {
-        Err(RpcError::InvalidNode)?;
-      }
-      let mut userpass = split_url[0];
-      url = split_url[1].to_string();
+#[derive(Clone, Debug)]
+pub struct Rpc<R: RpcConnection> {
+  connection: R,
+  distribution: Arc<Mutex<OutputDistribution>>,
+}
 
-      // If there was additionally a protocol string, restore that to the daemon URL
-      if userpass.contains("://") {
-        let split_userpass = userpass.split("://").collect::<Vec<_>>();
-        if split_userpass.len() != 2 {
-          Err(RpcError::InvalidNode)?;
-        }
-        url = split_userpass[0].to_string() + "://" + &url;
-        userpass = split_userpass[1];
-      }
+impl<R: RpcConnection> Rpc<R> {
+  /// Create a new RPC client over the specified connection.
+  pub fn new(connection: R) -> Rpc<R> {
+    Rpc { connection, distribution: Arc::new(Mutex::new(OutputDistribution::new())) }
+  }
 
-      let split_userpass = userpass.split(':').collect::<Vec<_>>();
-      if split_userpass.len() != 2 {
-        Err(RpcError::InvalidNode)?;
-      }
-      Some((split_userpass[0].to_string(), split_userpass[1].to_string()))
-    } else {
-      None
-    };
+  /// The connection this client uses.
+  pub fn connection(&self) -> &R {
+    &self.connection
+  }
 
-    Ok(Rpc { client: Client::new(), userpass, url })
+  /// The cached output distribution used when selecting decoys, which is shared by every clone of
+  /// this Rpc.
+  ///
+  /// This may be used to roll the cache back after a reorganization, or to persist and restore
+  /// it.
+  pub async fn output_distribution(&self) -> MutexGuard<'_, OutputDistribution> {
+    self.distribution.lock().await
   }
 
   /// Perform a RPC call to the specified method with the provided parameters.
@@ -125,12 +125,20 @@ impl Rpc {
     method: &str,
     params: Option<Params>,
   ) -> Result<Response, RpcError> {
-    let mut builder = self.client.post(self.url.clone() + "/" + method);
-    if let Some(params) = params.as_ref() {
-      builder = builder.json(params);
-    }
+    let res = self
+      .connection
+      .post(
+        method,
+        if let Some(params) = params.as_ref() {
+          serde_json::to_vec(params).map_err(|_| RpcError::InternalError("Failed to serialize"))?
+        } else {
+          vec![]
+        },
+      )
+      .await?;
 
-    self.call_tail(method, builder).await
+    serde_json::from_slice(&res)
+      .map_err(|_| RpcError::InternalError("Failed to parse JSON response"))
   }
 
   /// Perform a JSON-RPC call to the specified method with the provided parameters
@@ -152,44 +160,10 @@ impl Rpc {
     method: &str,
     params: Vec<u8>,
   ) -> Result<Response, RpcError> {
-    let builder = self.client.post(self.url.clone() + "/" + method).body(params.clone());
-    self.call_tail(method, builder.header("Content-Type", "application/octet-stream")).await
-  }
-
-  async fn call_tail<Response: DeserializeOwned + Debug>(
-    &self,
-    method: &str,
-    mut builder: RequestBuilder,
-  ) -> Result<Response, RpcError> {
-    if let Some((user, pass)) = &self.userpass {
-      let req = self.client.post(&self.url).send().await.map_err(|_| RpcError::InvalidNode)?;
-      // Only provide authentication if this daemon actually expects it
-      if let Some(header) = req.headers().get("www-authenticate") {
-        builder = builder.header(
-          "Authorization",
-          digest_auth::parse(header.to_str().map_err(|_| RpcError::InvalidNode)?)
-            .map_err(|_| RpcError::InvalidNode)?
-            .respond(&AuthContext::new_post::<_, _, _, &[u8]>(
-              user,
-              pass,
-              "/".to_string() + method,
-              None,
-            ))
-            .map_err(|_| RpcError::InvalidNode)?
-            .to_header_string(),
-        );
-      }
-    }
+    let res = self.connection.post(method, params).await?;
 
-    let res = builder.send().await.map_err(|_| RpcError::ConnectionError)?;
-
-    Ok(if !method.ends_with(".bin") {
-      serde_json::from_str(&res.text().await.map_err(|_| RpcError::ConnectionError)?)
-        .map_err(|_| RpcError::InternalError("Failed to parse JSON response"))?
-    } else {
-      monero_epee_bin_serde::from_bytes(&res.bytes().await.map_err(|_| RpcError::ConnectionError)?)
-        .map_err(|_| RpcError::InternalError("Failed to parse binary response"))?
-    })
+    monero_epee_bin_serde::from_bytes(&res)
+      .map_err(|_| RpcError::InternalError("Failed to parse binary response"))
   }
 
   /// Get the active blockchain protocol version.
@@ -283,6 +257,56 @@ impl Rpc {
     self.get_transactions(&[tx]).await.map(|mut txs| txs.swap_remove(0))
   }
 
+  /// Get the hashes of the transactions currently in the node's mempool.
+  pub async fn get_transaction_pool_hashes(&self) -> Result<Vec<[u8; 32]>, RpcError> {
+    #[derive(Deserialize, Debug)]
+    struct PoolHashesResponse {
+      #[serde(default)]
+      tx_hashes: Vec<String>,
+    }
+
+    self
+      .rpc_call::<Option<()>, PoolHashesResponse>("get_transaction_pool_hashes", None)
+      .await?
+      .tx_hashes
+      .iter()
+      .map(|hash| hash_hex(hash))
+      .collect()
+  }
+
+  /// Get the transactions currently in the node's mempool.
+  ///
+  /// These are fetched in a single call, so they're a consistent view of the mempool, unlike
+  /// fetching the transactions for the hashes returned by `get_transaction_pool_hashes`.
+  pub async fn get_transaction_pool(&self) -> Result<Vec<Transaction>, RpcError> {
+    #[derive(Deserialize, Debug)]
+    struct PoolTransaction {
+      id_hash: String,
+      tx_blob: String,
+    }
+    #[derive(Deserialize, Debug)]
+    struct PoolResponse {
+      #[serde(default)]
+      transactions: Vec<PoolTransaction>,
+    }
+
+    self
+      .rpc_call::<Option<()>, PoolResponse>("get_transaction_pool", None)
+      .await?
+      .transactions
+      .iter()
+      .map(|res| {
+        let hash = hash_hex(&res.id_hash)?;
+        let tx = Transaction::read::<&[u8]>(&mut rpc_hex(&res.tx_blob)?.as_ref())
+          .map_err(|_| RpcError::InvalidTransaction(hash))?;
+        if tx.hash() != hash {
+          Err(RpcError::InvalidNode)?;
+        }
+        Ok(tx)
+      })
+      .collect()
+  }
+
   /// Get the hash of a block from the node by the block's numbers.
   /// This function does not verify the returned block hash is actually for the number in question.
   pub async fn get_block_hash(&self, number: usize) -> Result<[u8; 32], RpcError> {
@@ -301,7 +325,8 @@ impl Rpc {
   }
 
   /// Get a block from the node by its hash.
-  /// This function does not verify the returned block actually has the hash in question.
+  ///
+  /// The block is verified to have the requested hash, which commits to its transactions.
   pub async fn get_block(&self, hash: [u8; 32]) -> Result<Block, RpcError> {
     #[derive(Deserialize, Debug)]
     struct BlockResponse {
@@ -311,8 +336,12 @@ impl Rpc {
     let res: BlockResponse =
       self.json_rpc_call("get_block", Some(json!({ "hash": hex::encode(hash) }))).await?;
 
-    // TODO: Verify the TXs included are actually committed to by the header
-    Block::read::<&[u8]>(&mut rpc_hex(&res.blob)?.as_ref()).map_err(|_| RpcError::InvalidNode)
+    let block =
+      Block::read::<&[u8]>(&mut rpc_hex(&res.blob)?.as_ref()).map_err(|_| RpcError::InvalidNode)?;
+    if block.hash() != hash {
+      Err(RpcError::InvalidNode)?;
+    }
+    Ok(block)
   }
 
   pub async fn get_block_by_number(&self, number: usize) -> Result<Block, RpcError> {
@@ -410,6 +439,50 @@ impl Rpc {
     Ok(distributions.distributions.swap_remove(0).distribution)
   }
 
+  /// Get the specified outputs from the RingCT (zero-amount) pool, as their key and commitment.
+  /// Unlike `get_unlocked_outputs`, this doesn't consider their timelocks.
+  pub async fn get_outputs(&self, indexes: &[u64]) -> Result<Vec<[EdwardsPoint; 2]>, RpcError> {
+    self.get_outputs_of_amount(0, indexes).await
+  }
+
+  /// Get the specified outputs from the pool of outputs with the specified amount, as their key
+  /// and commitment. Outputs with transparent amounts are returned with the commitment Monero
+  /// assigns them, which has a mask of one.
+  pub async fn get_outputs_of_amount(
+    &self,
+    amount: u64,
+    indexes: &[u64],
+  ) -> Result<Vec<[EdwardsPoint; 2]>, RpcError> {
+    #[derive(Deserialize, Debug)]
+    struct Out {
+      key: String,
+      mask: String,
+    }
+
+    #[derive(Deserialize, Debug)]
+    struct Outs {
+      outs: Vec<Out>,
+    }
+
+    let outs: Outs = self
+      .rpc_call(
+        "get_outs",
+        Some(json!({
+          "get_txid": false,
+          "outputs": indexes.iter().map(|o| json!({
+            "amount": amount,
+            "index": o
+          })).collect::<Vec<_>>()
+        })),
+      )
+      .await?;
+
+    if outs.outs.len() != indexes.len() {
+      Err(RpcError::InvalidNode)?;
+    }
+    outs.outs.iter().map(|out| Ok([rpc_point(&out.key)?, rpc_point(&out.mask)?])).collect()
+  }
+
   /// Get the specified outputs from the RingCT (zero-amount) pool, but only return them if their
   /// timelock has been satisfied. This is distinct from being free of the 10-block lock applied to
   /// all Monero transactions.
@@ -469,6 +542,73 @@ impl Rpc {
       .collect()
   }
 
+  /// Get the locks on the specified outputs from the RingCT (zero-amount) pool.
+  pub async fn get_output_locks(&self, indexes: &[u64]) -> Result<Vec<OutputLock>, RpcError> {
+    #[derive(Deserialize, Debug)]
+    struct Out {
+      height: usize,
+      txid: String,
+    }
+
+    #[derive(Deserialize, Debug)]
+    struct Outs {
+      outs: Vec<Out>,
+    }
+
+    let outs: Outs = self
+      .rpc_call(
+        "get_outs",
+        Some(json!({
+          "get_txid": true,
+          "outputs": indexes.iter().map(|o| json!({
+            "amount": 0,
+            "index": o
+          })).collect::<Vec<_>>()
+        })),
+      )
+      .await?;
+    if outs.outs.len() != indexes.len() {
+      Err(RpcError::InvalidNode)?;
+    }
+
+    let txs = self
+      .get_transactions(
+        &outs.outs.iter().map(|out| hash_hex(&out.txid)).collect::<Result<Vec<_>, _>>()?,
+      )
+      .await?;
+
+    Ok(outs.outs.iter().zip(txs.iter()).map(|(out, tx)| OutputLock::new(out.height, tx)).collect())
+  }
+
+  /// Check if the specified key images have been spent, either on chain or in the mempool.
+  pub async fn is_key_image_spent(
+    &self,
+    key_images: &[EdwardsPoint],
+  ) -> Result<Vec<bool>, RpcError> {
+    #[derive(Deserialize, Debug)]
+    struct SpentResponse {
+      spent_status: Vec<u8>,
+    }
+
+    let res: SpentResponse = self
+      .rpc_call(
+        "is_key_image_spent",
+        Some(json!({
+          "key_images": key_images
+            .iter()
+            .map(|image| hex::encode(image.compress().to_bytes()))
+            .collect::<Vec<_>>()
+        })),
+      )
+      .await?;
+
+    if res.spent_status.len() != key_images.len() {
+      Err(RpcError::InvalidNode)?;
+    }
+    // 0 is unspent, 1 is spent on chain, and 2 is spent in the mempool
+    Ok(res.spent_status.iter().map(|status| *status != 0).collect())
+  }
+
   /// Get the currently estimated fee from the node. This may be manipulated to unsafe levels and
   /// MUST be sanity checked.
   // TODO: Take a sanity check argument
@@ -0,0 +1,80 @@
+use hex_literal::hex;
+
+use rand_core::{RngCore, OsRng};
+
+use crate::{
+  hash,
+  serialize::write_varint,
+  transaction::Input,
+  block::{merkle_root, Block},
+};
+
+// Mainnet's genesis block
+const GENESIS: &str = concat!(
+  "010000000000000000000000000000000000000000000000000000000000000000000010270000013c01ff0001ffffff",
+  "ffffff03029b2e4c0281c0b02e7c53291a94d1d0cbff8883f8024f5142ee494ffbbd08807121017767aafcde9be00dc",
+  "fd098715ebcf7f410daebc582fda69d24a28e9d0bc890d100"
+);
+const GENESIS_ID: [u8; 32] =
+  hex!("418015bb9ae982a1975da7d79277c2705727a56894ba0fb246adaabb1f4632e3");
+
+// The ID Monero special cases for block 202612, whose blob isn't included here
+const CORRUPT_BLOCK_ID: [u8; 32] =
+  hex!("bbd604d2ba11ba27935e006ed39c9bfdd99b76bf4a50654bc1e1e61217962698");
+
+fn pair(a: [u8; 32], b: [u8; 32]) -> [u8; 32] {
+  hash(&[a, b].concat())
+}
+
+#[test]
+fn genesis() {
+  let genesis = hex::decode(GENESIS).unwrap();
+  let block = Block::read::<&[u8]>(&mut genesis.as_ref()).unwrap();
+  assert_eq!(block.serialize(), genesis);
+  assert_eq!(block.number(), 0);
+  assert_eq!(block.tx_hashes(), vec![block.miner_tx.hash()]);
+  assert_eq!(block.tx_merkle_root(), block.miner_tx.hash());
+  assert_eq!(block.hash(), GENESIS_ID);
+}
+
+#[test]
+fn merkle() {
+  let hashes = (0 .. 8)
+    .map(|_| {
+      let mut hash = [0; 32];
+      OsRng.fill_bytes(&mut hash);
+      hash
+    })
+    .collect::<Vec<_>>();
+  let h = |i: usize| hashes[i];
+
+  assert_eq!(merkle_root(&hashes[.. 1]), h(0));
+  assert_eq!(merkle_root(&hashes[.. 2]), pair(h(0), h(1)));
+  assert_eq!(merkle_root(&hashes[.. 3]), pair(h(0), pair(h(1), h(2))));
+  assert_eq!(merkle_root(&hashes[.. 4]), pair(pair(h(0), h(1)), pair(h(2), h(3))));
+  assert_eq!(merkle_root(&hashes[.. 5]), pair(pair(h(0), h(1)), pair(h(2), pair(h(3), h(4)))));
+  assert_eq!(
+    merkle_root(&hashes[.. 6]),
+    pair(pair(h(0), h(1)), pair(pair(h(2), h(3)), pair(h(4), h(5))))
+  );
+  assert_eq!(
+    merkle_root(&hashes),
+    pair(pair(pair(h(0), h(1)), pair(h(2), h(3))), pair(pair(h(4), h(5)), pair(h(6), h(7))))
+  );
+}
+
+#[test]
+fn corrupt_block_number() {
+  // Only block 202612 itself has its ID special cased, not any block with its number
+  let genesis = hex::decode(GENESIS).unwrap();
+  let mut block = Block::read::<&[u8]>(&mut genesis.as_ref()).unwrap();
+  block.miner_tx.prefix.inputs = vec![Input::Gen(202612)];
+  assert_eq!(block.number(), 202612);
+
+  let hashable = block.serialize_hashable();
+  let mut buf = vec![];
+  write_varint(&u64::try_from(hashable.len()).unwrap(), &mut buf).unwrap();
+  buf.extend(hashable);
+  assert_eq!(block.hash(), hash(&buf));
+  assert_ne!(block.hash(), CORRUPT_BLOCK_ID);
+}
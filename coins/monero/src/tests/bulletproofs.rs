@@ -1,14 +1,24 @@
 use hex_literal::hex;
 use rand::rngs::OsRng;
+use rand_core::SeedableRng;
+use rand_chacha::ChaCha20Rng;
 
 use curve25519_dalek::{scalar::Scalar, edwards::CompressedEdwardsY};
+use group::Group;
+use dalek_ff_group as dfg;
 use multiexp::BatchVerifier;
 
 use crate::{
   Commitment, random_scalar,
-  ringct::bulletproofs::{Bulletproofs, original::OriginalStruct},
+  ringct::bulletproofs::{
+    Bulletproofs,
+    core::{ScalarVector, hadamard_fold},
+    original::OriginalStruct,
+  },
 };
 
+const BULLETPROOFS_JSON: &str = include_str!("vectors/bulletproofs.json");
+
 #[test]
 fn bulletproofs_vector() {
   let scalar = |scalar| Scalar::from_canonical_bytes(scalar).unwrap();
@@ -56,6 +66,52 @@ fn bulletproofs_vector() {
   ));
 }
 
+#[test]
+fn folding() {
+  let scalar = || dfg::Scalar(random_scalar(&mut OsRng));
+  let (a, b) = (scalar(), scalar());
+
+  // The in-place folds must match folding into new vectors, as the provers previously did
+  let scalars = ScalarVector((0 .. 16).map(|_| scalar()).collect());
+  let (l, r) = scalars.split();
+  let mut folded = scalars.clone();
+  folded.fold(a, b);
+  assert_eq!(folded.0, ((ScalarVector(l.to_vec()) * a) + (ScalarVector(r.to_vec()) * b)).0);
+
+  let points = (0 .. 16).map(|_| dfg::EdwardsPoint::random(&mut OsRng)).collect::<Vec<_>>();
+  let mut folded = points.clone();
+  hadamard_fold(&mut folded, a, b);
+  assert_eq!(folded.len(), 8);
+  for (folded, (l, r)) in folded.iter().zip(points[.. 8].iter().zip(&points[8 ..])) {
+    assert_eq!(*folded, (*l * a) + (*r * b));
+  }
+}
+
+#[test]
+fn deterministic_proofs() {
+  #[derive(serde::Deserialize)]
+  struct Vector {
+    plus: bool,
+    outputs: u64,
+    hex: String,
+  }
+
+  // Multisig signers each prove the outputs with a shared seed, and must create identical proofs
+  // These vectors were created by the prover before it folded vectors in place, which must not
+  // have changed its proofs
+  for vector in serde_json::from_str::<Vec<Vector>>(BULLETPROOFS_JSON).unwrap() {
+    let mut rng = ChaCha20Rng::from_seed([0xff; 32]);
+    let commitments = (1 ..= vector.outputs)
+      .map(|amount| Commitment::new(random_scalar(&mut rng), amount))
+      .collect::<Vec<_>>();
+    let bp = Bulletproofs::prove(&mut rng, &commitments, vector.plus).unwrap();
+    assert_eq!(hex::encode(bp.serialize()), vector.hex);
+
+    let commitments = commitments.iter().map(Commitment::calculate).collect::<Vec<_>>();
+    assert!(bp.verify(&mut OsRng, &commitments));
+  }
+}
+
 macro_rules! bulletproofs_tests {
   ($name: ident, $max: ident, $plus: literal) => {
     #[test]
@@ -17,7 +17,7 @@ use crate::{
   wallet::Decoys,
   ringct::{
     generate_key_image,
-    clsag::{ClsagInput, Clsag},
+    clsag::{ClsagError, ClsagInput, Clsag},
   },
 };
 #[cfg(feature = "multisig")]
@@ -77,6 +77,15 @@ fn clsag() {
     )
     .swap_remove(0);
     clsag.verify(&ring, &image, &pseudo_out, &msg).unwrap();
+
+    // Altering the signature, or what it signs, should invalidate it
+    let mut invalid = clsag.clone();
+    invalid.c1 += Scalar::one();
+    assert_eq!(invalid.verify(&ring, &image, &pseudo_out, &msg), Err(ClsagError::InvalidC1));
+    let mut invalid = clsag.clone();
+    invalid.s[usize::try_from(real).unwrap()] += Scalar::one();
+    assert_eq!(invalid.verify(&ring, &image, &pseudo_out, &msg), Err(ClsagError::InvalidC1));
+    assert_eq!(clsag.verify(&ring, &image, &pseudo_out, &[2; 32]), Err(ClsagError::InvalidC1));
   }
 }
 
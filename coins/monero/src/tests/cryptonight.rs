@@ -0,0 +1,47 @@
+use hex_literal::hex;
+
+use crate::cryptonight::{aes, cryptonight};
+
+#[test]
+fn aes_key_schedule() {
+  // FIPS-197's AES-256 key expansion example (Appendix A.3), truncated to CryptoNight's 10 keys
+  assert_eq!(
+    aes::expand_key(&hex!("603deb1015ca71be2b73aef0857d77811f352c073b6108d72d9810a30914dff4")),
+    [
+      hex!("603deb1015ca71be2b73aef0857d7781"),
+      hex!("1f352c073b6108d72d9810a30914dff4"),
+      hex!("9ba354118e6925afa51a8b5f2067fcde"),
+      hex!("a8b09c1a93d194cdbe49846eb75d5b9a"),
+      hex!("d59aecb85bf3c917fee94248de8ebe96"),
+      hex!("b5a9328a2678a647983122292f6c79b3"),
+      hex!("812c81addadf48ba24360af2fab8b464"),
+      hex!("98c5bfc9bebd198e268c3ba709e04214"),
+      hex!("68007bacb2df331696e939e46c518d80"),
+      hex!("c814e20476a9fb8a5025c02d59c58239"),
+    ]
+  );
+}
+
+#[test]
+fn aes_round() {
+  // The first round of FIPS-197's AES-128 example (Appendix C.1)
+  let mut block = hex!("00102030405060708090a0b0c0d0e0f0");
+  aes::round(&mut block, &hex!("d6aa74fdd2af72fadaa678f1d6ab76fe"));
+  assert_eq!(block, hex!("89d810e8855ace682d1843d8cb128fe4"));
+}
+
+#[test]
+fn cryptonight_vectors() {
+  // Monero's tests-slow.txt, whose vectors each finish with a different one of the four hashes
+  for (data, hash) in [
+    ("de omnibus dubitandum", "2f8e3df40bd11f9ac90c743ca8e32bb391da4fb98612aa3b6cdc639ee00b31f5"),
+    (
+      "abundans cautela non nocet",
+      "722fa8ccd594d40e4a41f3822734304c8d5eff7e1b528408e2229da38ba553c4",
+    ),
+    ("caveat emptor", "bbec2cacf69866a8e740380fe7b818fc78f8571221742d729d9d02d7f8989b87"),
+    ("ex nihilo nihil fit", "b1257de4efc5ce28c6b40ceb1c6c8f812a64634eb3e81c5220bee9b2b76a6f05"),
+  ] {
+    assert_eq!(hex::encode(cryptonight(data.as_bytes())), hash);
+  }
+}
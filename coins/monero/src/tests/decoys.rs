@@ -0,0 +1,27 @@
+use crate::{
+  serialize::{write_varint, write_vec},
+  wallet::OutputDistribution,
+};
+
+#[test]
+fn output_distribution() {
+  let raw = [0, 1, 1, 5, 9, 12];
+  let mut serialized = vec![];
+  write_vec(write_varint, &raw, &mut serialized).unwrap();
+
+  let mut distribution = OutputDistribution::read::<&[u8]>(&mut serialized.as_ref()).unwrap();
+  assert_eq!(distribution.distribution(), raw);
+  assert_eq!(distribution.serialize(), serialized);
+
+  // Rolling back removes the specified height and every block after it
+  distribution.rollback(4);
+  assert_eq!(distribution.len(), 4);
+  assert_eq!(distribution.distribution(), &raw[.. 4]);
+  distribution.rollback(10);
+  assert_eq!(distribution.len(), 4);
+
+  // A cumulative distribution can't decrease
+  let mut serialized = vec![];
+  write_vec(write_varint, &[0, 5, 4], &mut serialized).unwrap();
+  assert!(OutputDistribution::read::<&[u8]>(&mut serialized.as_ref()).is_err());
+}
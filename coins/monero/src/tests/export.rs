@@ -0,0 +1,100 @@
+use std::collections::HashSet;
+
+use rand_core::OsRng;
+
+use crate::{
+  serialize::write_varint,
+  wallet::{address::SubaddressIndex, Scanner, SpendableOutput, ExportedOutput, ExportedOutputs},
+  tests::scanner::{view_pair, transaction},
+};
+
+#[test]
+fn exported_outputs() {
+  let pair = view_pair();
+  let subaddress = SubaddressIndex::new(0, 1).unwrap();
+  let tx = transaction(&pair, subaddress);
+
+  let mut scanner = Scanner::from_view(pair.clone(), Some(HashSet::new()));
+  scanner.register_subaddress(subaddress);
+  let output = SpendableOutput {
+    output: scanner.scan_transaction(&tx).ignore_timelock().swap_remove(0),
+    global_index: 5,
+  };
+
+  let exported = ExportedOutput::new(&output, &tx).unwrap();
+  assert_eq!(exported.key, output.key());
+  assert_eq!(exported.global_index, 5);
+  assert!(exported.rct);
+  assert!(!exported.key_image_known);
+  assert_eq!(exported.amount, output.commitment().amount);
+  assert_eq!(exported.subaddress, Some(subaddress));
+
+  // The output can only be exported alongside the transaction it was received in
+  assert!(ExportedOutput::new(&output, &transaction(&pair, subaddress)).is_none());
+
+  // The wallet which received the output can recover its key offset, unlike any other
+  assert_eq!(exported.key_offset(&pair), Some(output.key_offset()));
+  assert_eq!(exported.key_offset(&view_pair()), None);
+  let mut other_subaddress = exported.clone();
+  other_subaddress.subaddress = SubaddressIndex::new(0, 2);
+  assert_eq!(other_subaddress.key_offset(&pair), None);
+
+  // wallet2's exported_transfer_details
+  let mut expected = vec![1];
+  expected.extend(exported.key.compress().to_bytes());
+  expected.push(0);
+  expected.push(5);
+  expected.extend(exported.tx_key.compress().to_bytes());
+  // Only the RCT flag is set
+  expected.push(1 << 2);
+  write_varint(&exported.amount, &mut expected).unwrap();
+  expected.extend([0, 0, 1]);
+  assert_eq!(exported.serialize(), expected);
+  assert_eq!(ExportedOutput::read::<&[u8]>(&mut expected.as_ref()).unwrap(), exported);
+
+  // Unknown flags are rejected
+  let mut flagged = expected.clone();
+  flagged[1 + 32 + 1 + 1 + 32] |= 1 << 6;
+  assert!(ExportedOutput::read::<&[u8]>(&mut flagged.as_ref()).is_err());
+
+  // The exported outputs are prefixed with the wallet's keys, followed by the tuple
+  // (offset, total, outputs)
+  let outputs = ExportedOutputs { offset: 2, total: 3, outputs: vec![exported] };
+  let serialized = outputs.serialize(&pair);
+  let mut expected_outputs = pair.spend().compress().to_bytes().to_vec();
+  expected_outputs.extend(pair.view().compress().to_bytes());
+  expected_outputs.extend([3, 2, 3, 1]);
+  expected_outputs.extend(&expected);
+  assert_eq!(serialized, expected_outputs);
+  assert_eq!(ExportedOutputs::read::<&[u8]>(&pair, &mut serialized.as_ref()).unwrap(), outputs);
+
+  // Outputs exported by another wallet are rejected
+  assert!(ExportedOutputs::read::<&[u8]>(&view_pair(), &mut serialized.as_ref()).is_err());
+}
+
+#[test]
+fn encrypted_exported_outputs() {
+  let pair = view_pair();
+  let outputs = ExportedOutputs { offset: 0, total: 0, outputs: vec![] };
+
+  let exported = outputs.export(&mut OsRng, &pair);
+  assert!(exported.starts_with(b"Monero output export\x03"));
+  // The magic, the IV, the encrypted serialization, and the signature
+  assert_eq!(exported.len(), 21 + 8 + outputs.serialize(&pair).len() + 64);
+  assert_eq!(ExportedOutputs::import(&pair, &exported).unwrap(), outputs);
+
+  // Another wallet can't decrypt them
+  assert!(ExportedOutputs::import(&view_pair(), &exported).is_err());
+
+  // Modifying the ciphertext is detected
+  for i in [21, 21 + 8, exported.len() - 1] {
+    let mut modified = exported.clone();
+    modified[i] ^= 1;
+    assert!(ExportedOutputs::import(&pair, &modified).is_err());
+  }
+
+  // As is the wrong magic
+  let mut modified = exported;
+  modified[20] = 4;
+  assert!(ExportedOutputs::import(&pair, &modified).is_err());
+}
@@ -0,0 +1,82 @@
+use std::collections::{HashSet, HashMap};
+
+use zeroize::Zeroizing;
+
+use rand_core::OsRng;
+
+use frost::{
+  curve::Ed25519,
+  Participant, FrostError,
+  tests::{key_gen, recover_key},
+};
+
+use crate::{
+  random_scalar,
+  ringct::hash_to_point,
+  wallet::{address::SubaddressIndex, ViewPair, Scanner, SpendableOutput, KeyImageMachine},
+  tests::scanner::{view_pair, transaction},
+};
+
+fn outputs(pair: &ViewPair) -> Vec<SpendableOutput> {
+  let mut scanner = Scanner::from_view(pair.clone(), Some(HashSet::new()));
+  let subaddresses = [SubaddressIndex::new(0, 1).unwrap(), SubaddressIndex::new(0, 2).unwrap()];
+  for subaddress in subaddresses {
+    scanner.register_subaddress(subaddress);
+  }
+
+  let txs = subaddresses.map(|subaddress| transaction(pair, subaddress));
+  scanner.scan_transactions(&txs, 0).iter().flat_map(|outputs| outputs.ignore_timelock()).collect()
+}
+
+#[test]
+fn multisig_key_images() {
+  let keys = key_gen::<_, Ed25519>(&mut OsRng);
+  let t = keys[&Participant::new(1).unwrap()].params().t();
+  let group_key = keys[&Participant::new(1).unwrap()].group_key().0;
+
+  let pair = ViewPair::new(group_key, Zeroizing::new(random_scalar(&mut OsRng)));
+  // Outputs for another wallet can't have their key images calculated
+  let other = view_pair();
+  assert!(
+    KeyImageMachine::new(keys[&Participant::new(1).unwrap()].clone(), &outputs(&other)).is_none()
+  );
+
+  let outputs = outputs(&pair);
+  assert_eq!(outputs.len(), 2);
+
+  let mut machines = HashMap::new();
+  let mut shares = HashMap::new();
+  for i in (1 ..= t).map(|i| Participant::new(i).unwrap()) {
+    let machine = KeyImageMachine::new(keys[&i].clone(), &outputs).unwrap();
+    shares.insert(i, machine.shares(&mut OsRng).serialize());
+    machines.insert(i, machine);
+  }
+
+  let secret = recover_key(&keys).0;
+  let expected = outputs
+    .iter()
+    .map(|output| (secret + output.key_offset()) * hash_to_point(output.key()))
+    .collect::<Vec<_>>();
+
+  for (i, machine) in machines.drain() {
+    let received = shares
+      .iter()
+      .filter(|(l, _)| **l != i)
+      .map(|(l, shares)| (*l, machine.read_shares(&mut shares.as_slice()).unwrap()))
+      .collect::<HashMap<_, _>>();
+
+    // Shares attributed to the wrong participant are rejected
+    if i == Participant::new(1).unwrap() {
+      let mut swapped = received.clone();
+      let (two, three) = (Participant::new(2).unwrap(), Participant::new(3).unwrap());
+      swapped.insert(two, received[&three].clone());
+      swapped.insert(three, received[&two].clone());
+      assert_eq!(
+        KeyImageMachine::new(keys[&i].clone(), &outputs).unwrap().complete(swapped),
+        Err(FrostError::InvalidPreprocess(two))
+      );
+    }
+
+    assert_eq!(machine.complete(received).unwrap(), expected);
+  }
+}
@@ -0,0 +1,63 @@
+use rand_core::OsRng;
+
+use curve25519_dalek::constants::ED25519_BASEPOINT_TABLE;
+
+use crate::{
+  random_scalar,
+  transaction::{Timelock, Input},
+  wallet::{address::SubaddressIndex, Spendability, OutputLock},
+  tests::scanner::{view_pair, transaction},
+};
+
+#[test]
+fn output_lock() {
+  let lock = OutputLock { block: 100, coinbase: false, timelock: Timelock::None };
+  assert_eq!(lock.spendability(101, 0), Spendability::Young(110));
+  assert_eq!(lock.spendability(109, 0), Spendability::Young(110));
+  assert_eq!(lock.spendability(110, 0), Spendability::Spendable);
+}
+
+#[test]
+fn coinbase_lock() {
+  let lock = OutputLock { block: 100, coinbase: true, timelock: Timelock::Block(160) };
+  assert_eq!(lock.spendability(101, 0), Spendability::Immature(160));
+  assert_eq!(lock.spendability(159, 0), Spendability::Immature(160));
+  assert_eq!(lock.spendability(160, 0), Spendability::Spendable);
+}
+
+#[test]
+fn timelocks() {
+  let block = OutputLock { block: 100, coinbase: false, timelock: Timelock::Block(200) };
+  assert_eq!(block.spendability(150, 0), Spendability::Timelocked(Timelock::Block(200)));
+  assert_eq!(block.spendability(200, 0), Spendability::Spendable);
+
+  // A timelock which expires before the output is 10 blocks old leaves it young
+  let early = OutputLock { block: 100, coinbase: false, timelock: Timelock::Block(105) };
+  assert_eq!(early.spendability(104, 0), Spendability::Timelocked(Timelock::Block(105)));
+  assert_eq!(early.spendability(105, 0), Spendability::Young(110));
+
+  let time = Timelock::Time(1_700_000_000);
+  let time_lock = OutputLock { block: 100, coinbase: false, timelock: time };
+  assert_eq!(time_lock.spendability(200, 1_600_000_000), Spendability::Timelocked(time));
+  assert_eq!(time_lock.spendability(200, 1_700_000_000), Spendability::Spendable);
+  // The age of the output is still enforced once the timelock expires
+  assert_eq!(time_lock.spendability(105, 1_700_000_000), Spendability::Young(110));
+}
+
+#[test]
+fn output_lock_from_transaction() {
+  let pair = view_pair();
+  let mut tx = transaction(&pair, SubaddressIndex::new(0, 1).unwrap());
+  tx.prefix.timelock = Timelock::Block(160);
+  assert_eq!(
+    OutputLock::new(100, &tx),
+    OutputLock { block: 100, coinbase: true, timelock: Timelock::Block(160) }
+  );
+
+  tx.prefix.inputs = vec![Input::ToKey {
+    amount: 0,
+    key_offsets: vec![0],
+    key_image: &random_scalar(&mut OsRng) * &ED25519_BASEPOINT_TABLE,
+  }];
+  assert!(!OutputLock::new(100, &tx).coinbase);
+}
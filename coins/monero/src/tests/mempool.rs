@@ -0,0 +1,107 @@
+use std::collections::HashSet;
+
+use rand_core::OsRng;
+
+use curve25519_dalek::{constants::ED25519_BASEPOINT_TABLE, edwards::EdwardsPoint};
+
+use crate::{
+  random_scalar,
+  transaction::{Input, Transaction},
+  wallet::{
+    address::{Network, SubaddressIndex, AddressSpec},
+    ViewPair, SubaddressLookahead, Scanner, Mempool,
+  },
+  tests::scanner::{view_pair, transaction_to, transaction as subaddress_transaction},
+};
+
+fn key_image() -> EdwardsPoint {
+  &random_scalar(&mut OsRng) * &ED25519_BASEPOINT_TABLE
+}
+
+// Create a transaction paying the view pair which spends the specified key images
+fn transaction(pair: &ViewPair, key_images: &[EdwardsPoint]) -> Transaction {
+  let mut tx = transaction_to(
+    &pair.address(Network::Mainnet, AddressSpec::Standard),
+    random_scalar(&mut OsRng),
+  );
+  tx.prefix.inputs = key_images
+    .iter()
+    .map(|key_image| Input::ToKey { amount: 0, key_offsets: vec![0], key_image: *key_image })
+    .collect();
+  tx
+}
+
+#[test]
+fn mempool() {
+  let pair = view_pair();
+
+  let (a, b, c) = (key_image(), key_image(), key_image());
+  let first = transaction(&pair, &[a, b]);
+  let second = transaction(&pair, &[c]);
+  let mempool = Mempool::new(vec![first.clone(), second.clone()]);
+
+  assert!(mempool.contains(first.hash()));
+  assert!(!mempool.contains(transaction(&pair, &[]).hash()));
+  assert_eq!(mempool.spent_by(&a), &[first.hash()]);
+  assert_eq!(mempool.spent_by(&c), &[second.hash()]);
+  assert!(mempool.spent_by(&key_image()).is_empty());
+
+  // A transaction doesn't double spend itself
+  assert!(mempool.double_spends(&first).is_empty());
+  // A transaction spending a key image spent in the mempool double spends that transaction
+  let conflicting = transaction(&pair, &[b, c]);
+  assert_eq!(mempool.double_spends(&conflicting), vec![first.hash(), second.hash()]);
+  assert!(mempool.double_spends(&transaction(&pair, &[key_image()])).is_empty());
+}
+
+#[test]
+fn scan_mempool() {
+  let pair = view_pair();
+  let mut scanner = Scanner::from_view(pair.clone(), Some(HashSet::new()));
+
+  let key_image = key_image();
+  let tx = transaction(&pair, &[key_image]);
+  let unconfirmed = scanner.scan_mempool(&Mempool::new(vec![tx.clone()]));
+  assert_eq!(unconfirmed.len(), 1);
+  let output = unconfirmed[0].not_locked().swap_remove(0);
+  assert_eq!(output.output.absolute.tx, tx.hash());
+  assert_eq!(output.output.commitment().amount, 1);
+  assert!(!output.double_spent);
+
+  // Scanning the mempool again still finds the output
+  assert_eq!(scanner.scan_mempool(&Mempool::new(vec![tx.clone()])).len(), 1);
+  // As does scanning the transaction once it's confirmed
+  assert_eq!(scanner.scan_transaction(&tx).not_locked(), vec![output.output.clone()]);
+  // Once it's been confirmed, it's no longer reported as unconfirmed
+  assert!(scanner.scan_mempool(&Mempool::new(vec![tx.clone()])).is_empty());
+
+  // Transactions spending the same key image are flagged as double spent
+  let double_spend = transaction(&pair, &[key_image]);
+  let mut unconfirmed = scanner.scan_mempool(&Mempool::new(vec![double_spend.clone(), tx]));
+  assert_eq!(unconfirmed.len(), 1);
+  let output = unconfirmed.swap_remove(0).not_locked().swap_remove(0);
+  assert_eq!(output.output.absolute.tx, double_spend.hash());
+  assert!(output.double_spent);
+}
+
+#[test]
+fn scan_mempool_lookahead() {
+  let pair = view_pair();
+  let mut scanner = Scanner::from_view(pair.clone(), Some(HashSet::new()));
+  scanner.set_subaddress_lookahead(SubaddressLookahead::new(1, 2).unwrap());
+  let state = scanner.serialize();
+
+  // (0, 2) is only within the lookahead once (0, 1) is in use
+  let first = subaddress_transaction(&pair, SubaddressIndex::new(0, 1).unwrap());
+  let second = subaddress_transaction(&pair, SubaddressIndex::new(0, 2).unwrap());
+
+  // Outputs within the mempool extend the lookahead for the rest of the mempool
+  assert_eq!(scanner.scan_mempool(&Mempool::new(vec![first.clone(), second.clone()])).len(), 2);
+  // Yet the lookahead is restored once the mempool has been scanned
+  assert_eq!(scanner.serialize(), state);
+  assert!(scanner.scan_mempool(&Mempool::new(vec![second.clone()])).is_empty());
+
+  // Once the first transaction is confirmed, the lookahead is extended
+  assert_eq!(scanner.scan_transaction(&first).not_locked().len(), 1);
+  assert_eq!(scanner.scan_mempool(&Mempool::new(vec![second])).len(), 1);
+}
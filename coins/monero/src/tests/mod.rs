@@ -2,3 +2,19 @@ mod clsag;
 mod bulletproofs;
 mod address;
 mod seed;
+mod transaction;
+mod block;
+mod verify;
+mod decoys;
+mod scanner;
+mod sweep;
+mod weight;
+mod unsigned;
+mod state;
+mod export;
+mod cryptonight;
+mod mempool;
+mod lock;
+#[cfg(feature = "multisig")]
+mod key_images;
+mod proofs;
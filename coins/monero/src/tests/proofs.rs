@@ -0,0 +1,267 @@
+use core::ops::Deref;
+use std::collections::{HashSet, HashMap};
+
+use zeroize::Zeroizing;
+
+use rand_core::OsRng;
+
+use curve25519_dalek::{constants::ED25519_BASEPOINT_TABLE, scalar::Scalar, edwards::EdwardsPoint};
+
+#[cfg(feature = "multisig")]
+use transcript::{Transcript, RecommendedTranscript};
+#[cfg(feature = "multisig")]
+use frost::{
+  curve::Ed25519,
+  Participant,
+  tests::{key_gen, recover_key, sign_without_caching},
+};
+
+use crate::{
+  random_scalar,
+  ringct::{hash_to_point, RctBase, RctPrunable, RctSignatures},
+  transaction::{Timelock, Input, TransactionPrefix, Transaction},
+  wallet::{
+    address::{Network, SubaddressIndex, AddressSpec},
+    ViewPair, Scanner, ReceivedOutput,
+    proofs::{ProofError, TxProofType, TxProof, SpendProof, ReserveProof},
+  },
+  tests::scanner::transaction_to,
+};
+
+const RING_LEN: u64 = 11;
+const RING_INDEX: u64 = 3;
+
+fn wallet() -> (Zeroizing<Scalar>, ViewPair) {
+  let spend = Zeroizing::new(random_scalar(&mut OsRng));
+  let pair = ViewPair::new(
+    spend.deref() * &ED25519_BASEPOINT_TABLE,
+    Zeroizing::new(random_scalar(&mut OsRng)),
+  );
+  (spend, pair)
+}
+
+fn subaddress() -> SubaddressIndex {
+  SubaddressIndex::new(0, 1).unwrap()
+}
+
+// Create transactions paying the primary address and a subaddress
+fn transactions(pair: &ViewPair) -> [Transaction; 2] {
+  [AddressSpec::Standard, AddressSpec::Subaddress(subaddress())]
+    .map(|spec| transaction_to(&pair.address(Network::Mainnet, spec), random_scalar(&mut OsRng)))
+}
+
+fn received(pair: &ViewPair, txs: &[Transaction]) -> Vec<ReceivedOutput> {
+  let mut scanner = Scanner::from_view(pair.clone(), Some(HashSet::new()));
+  scanner.register_subaddress(subaddress());
+  txs.iter().flat_map(|tx| scanner.scan_transaction(tx).ignore_timelock()).collect()
+}
+
+fn key_image(spend: &Zeroizing<Scalar>, output: &ReceivedOutput) -> EdwardsPoint {
+  (spend.deref() + output.key_offset()) * hash_to_point(output.key())
+}
+
+// Create a transaction spending the output with the specified key image, along with the ring
+// members it references
+fn spending_transaction(
+  output: &ReceivedOutput,
+  key_image: EdwardsPoint,
+) -> (HashMap<(u64, u64), [EdwardsPoint; 2]>, Transaction) {
+  let mut ring = HashMap::new();
+  for i in 0 .. RING_LEN {
+    let key = if i == RING_INDEX {
+      output.key()
+    } else {
+      &random_scalar(&mut OsRng) * &ED25519_BASEPOINT_TABLE
+    };
+    // RingCT inputs have an amount of 0
+    ring.insert((0, 100 + i), [key, &random_scalar(&mut OsRng) * &ED25519_BASEPOINT_TABLE]);
+  }
+
+  let mut key_offsets = vec![100];
+  key_offsets.extend((1 .. RING_LEN).map(|_| 1));
+  let tx = Transaction {
+    prefix: TransactionPrefix {
+      version: 2,
+      timelock: Timelock::None,
+      inputs: vec![Input::ToKey { amount: 0, key_offsets, key_image }],
+      outputs: vec![],
+      extra: vec![],
+    },
+    signatures: vec![],
+    rct_signatures: RctSignatures {
+      base: RctBase { fee: 0, pseudo_outs: vec![], ecdh_info: vec![], commitments: vec![] },
+      prunable: RctPrunable::Null,
+    },
+  };
+  (ring, tx)
+}
+
+#[test]
+fn tx_proofs() {
+  let (_, pair) = wallet();
+  let (_, other) = wallet();
+
+  for (spec, index) in
+    [(AddressSpec::Standard, None), (AddressSpec::Subaddress(subaddress()), Some(subaddress()))]
+  {
+    let address = pair.address(Network::Mainnet, spec);
+    let r = Zeroizing::new(random_scalar(&mut OsRng));
+    let tx = transaction_to(&address, *r);
+
+    let out_proof = TxProof::out_proof(&mut OsRng, &tx, &address, &r, &[], b"message");
+    assert_eq!(out_proof.kind(), TxProofType::Out);
+    let in_proof = TxProof::in_proof(&mut OsRng, &tx, &pair, index, b"message").unwrap();
+    assert_eq!(in_proof.kind(), TxProofType::In);
+
+    for proof in [out_proof, in_proof] {
+      assert_eq!(proof.to_string().parse::<TxProof>().unwrap(), proof);
+      assert_eq!(proof.verify(&tx, &address, b"message"), Some(1));
+      assert_eq!(proof.verify(&tx, &address, b"other message"), None);
+      assert_eq!(proof.verify(&tx, &other.address(Network::Mainnet, spec), b"message"), None);
+    }
+
+    // A valid proof for an address which wasn't paid proves it received nothing
+    // Only possible for primary addresses, as the transaction key is bound to the subaddress paid
+    if index.is_none() {
+      let other_address = other.address(Network::Mainnet, spec);
+      let proof = TxProof::out_proof(&mut OsRng, &tx, &other_address, &r, &[], b"message");
+      assert_eq!(proof.verify(&tx, &other_address, b"message"), Some(0));
+    }
+  }
+
+  assert_eq!("OutProofV2".parse::<TxProof>(), Err(ProofError::InvalidEncoding));
+  assert_eq!("SpendProofV1".parse::<TxProof>(), Err(ProofError::InvalidEncoding));
+}
+
+#[tokio::test]
+async fn spend_proof() {
+  let (spend_key, pair) = wallet();
+  let output = received(&pair, &transactions(&pair)).swap_remove(1);
+  let (ring, tx) = spending_transaction(&output, key_image(&spend_key, &output));
+
+  let proof = SpendProof::new(&mut OsRng, &ring, &tx, &spend_key, &[output.clone()], b"message")
+    .await
+    .unwrap();
+  assert_eq!(proof.to_string().parse::<SpendProof>().unwrap(), proof);
+  assert!(proof.verify(&ring, &tx, b"message").await.unwrap());
+  assert!(!proof.verify(&ring, &tx, b"other message").await.unwrap());
+
+  // The wallet which didn't send the transaction can't prove it did
+  let (other, _) = wallet();
+  assert_eq!(
+    SpendProof::new(&mut OsRng, &ring, &tx, &other, &[output], b"message").await,
+    Err(ProofError::WrongPrivateKey)
+  );
+}
+
+#[test]
+fn reserve_proof() {
+  let (spend, pair) = wallet();
+  let txs = transactions(&pair);
+  let outputs = received(&pair, &txs);
+  assert_eq!(outputs.len(), 2);
+
+  let proof = ReserveProof::new(&mut OsRng, &spend, &pair, &txs, &outputs, b"message").unwrap();
+  assert_eq!(proof.to_string().parse::<ReserveProof>().unwrap(), proof);
+  assert_eq!(proof.transactions(), txs.iter().map(Transaction::hash).collect::<Vec<_>>());
+
+  let address = pair.address(Network::Mainnet, AddressSpec::Standard);
+  assert_eq!(
+    proof.verify(&address, b"message", &txs),
+    Some(outputs.iter().map(|output| (key_image(&spend, output), 1)).collect())
+  );
+  assert_eq!(proof.verify(&address, b"other message", &txs), None);
+  // The transactions must be provided
+  assert_eq!(proof.verify(&address, b"message", &txs[.. 1]), None);
+  // Reserve proofs are only for the primary address
+  assert_eq!(
+    proof.verify(
+      &pair.address(Network::Mainnet, AddressSpec::Subaddress(subaddress())),
+      b"message",
+      &txs
+    ),
+    None
+  );
+
+  // A proof solely for outputs received by a subaddress still proves the primary spend key
+  let proof =
+    ReserveProof::new(&mut OsRng, &spend, &pair, &txs, &outputs[1 ..], b"message").unwrap();
+  assert_eq!(
+    proof.verify(&address, b"message", &txs),
+    Some(vec![(key_image(&spend, &outputs[1]), 1)])
+  );
+
+  // Remove the primary spend key, which is the first of the two spend keys serialized last
+  let mut serialized = proof.serialize();
+  let pair_len = 1 + 32 + 64;
+  let spend_keys = serialized.len() - 1 - (2 * pair_len);
+  assert_eq!(serialized[spend_keys], 2);
+  serialized[spend_keys] = 1;
+  serialized.drain((spend_keys + 1) .. (spend_keys + 1 + pair_len));
+  let without_primary = ReserveProof::read::<&[u8]>(&mut serialized.as_ref()).unwrap();
+  assert_eq!(without_primary.verify(&address, b"message", &txs), None);
+
+  let (other, _) = wallet();
+  assert_eq!(
+    ReserveProof::new(&mut OsRng, &other, &pair, &txs, &outputs, b"message"),
+    Err(ProofError::WrongPrivateKey)
+  );
+}
+
+#[cfg(feature = "multisig")]
+#[tokio::test]
+async fn multisig_proofs() {
+  let keys = key_gen::<_, Ed25519>(&mut OsRng);
+  let t = keys[&Participant::new(1).unwrap()].params().t();
+  let spend = Zeroizing::new(recover_key(&keys).0);
+
+  let pair = ViewPair::new(
+    keys[&Participant::new(1).unwrap()].group_key().0,
+    Zeroizing::new(random_scalar(&mut OsRng)),
+  );
+  let txs = transactions(&pair);
+  let outputs = received(&pair, &txs);
+  let key_images = outputs.iter().map(|output| key_image(&spend, output)).collect::<Vec<_>>();
+
+  let (ring, tx) = spending_transaction(&outputs[1], key_images[1]);
+  let mut machines = HashMap::new();
+  for i in (1 ..= t).map(|i| Participant::new(i).unwrap()) {
+    machines.insert(
+      i,
+      SpendProof::multisig(
+        &ring,
+        keys[&i].clone(),
+        RecommendedTranscript::new(b"Monero Serai Spend Proof Test"),
+        &tx,
+        &outputs[1 ..],
+        b"message",
+      )
+      .await
+      .unwrap(),
+    );
+  }
+  let proof = sign_without_caching(&mut OsRng, machines, &[]);
+  assert!(proof.verify(&ring, &tx, b"message").await.unwrap());
+
+  let mut machines = HashMap::new();
+  for i in (1 ..= t).map(|i| Participant::new(i).unwrap()) {
+    machines.insert(
+      i,
+      ReserveProof::multisig(
+        keys[&i].clone(),
+        RecommendedTranscript::new(b"Monero Serai Reserve Proof Test"),
+        &pair,
+        &txs,
+        &outputs,
+        &key_images,
+        b"message",
+      )
+      .unwrap(),
+    );
+  }
+  let proof = sign_without_caching(&mut OsRng, machines, &[]);
+  assert_eq!(
+    proof.verify(&pair.address(Network::Mainnet, AddressSpec::Standard), b"message", &txs),
+    Some(key_images.into_iter().map(|key_image| (key_image, 1)).collect())
+  );
+}
@@ -0,0 +1,178 @@
+use std::collections::HashSet;
+
+use zeroize::Zeroizing;
+
+use rand_core::OsRng;
+
+use curve25519_dalek::{constants::ED25519_BASEPOINT_TABLE, scalar::Scalar};
+
+use crate::{
+  Commitment, random_scalar,
+  transaction::{Timelock, Input, Output, TransactionPrefix, Transaction},
+  ringct::{EncryptedAmount, RctBase, RctPrunable, RctSignatures},
+  wallet::{
+    shared_key, commitment_mask, amount_encryption,
+    extra::Extra,
+    address::{Network, SubaddressIndex, AddressSpec, MoneroAddress},
+    ViewPair, SubaddressLookahead, Scanner,
+  },
+};
+
+pub(crate) fn view_pair() -> ViewPair {
+  ViewPair::new(
+    &random_scalar(&mut OsRng) * &ED25519_BASEPOINT_TABLE,
+    Zeroizing::new(random_scalar(&mut OsRng)),
+  )
+}
+
+// Create a transaction with a single output to the specified address, using the specified
+// transaction key
+pub(crate) fn transaction_to(address: &MoneroAddress, r: Scalar) -> Transaction {
+  let (view_tag, shared_key, _) = shared_key(None, r * address.view, 0);
+  let amount = 1;
+
+  Transaction {
+    prefix: TransactionPrefix {
+      version: 2,
+      timelock: Timelock::None,
+      inputs: vec![Input::Gen(0)],
+      outputs: vec![Output {
+        amount: 0,
+        key: ((&shared_key * &ED25519_BASEPOINT_TABLE) + address.spend).compress(),
+        view_tag: Some(view_tag),
+      }],
+      extra: Extra::new(
+        if address.is_subaddress() { r * address.spend } else { &r * &ED25519_BASEPOINT_TABLE },
+        vec![],
+      )
+      .serialize(),
+    },
+    signatures: vec![],
+    rct_signatures: RctSignatures {
+      base: RctBase {
+        fee: 0,
+        pseudo_outs: vec![],
+        ecdh_info: vec![EncryptedAmount::Compact { amount: amount_encryption(amount, shared_key) }],
+        commitments: vec![Commitment::new(commitment_mask(shared_key), amount).calculate()],
+      },
+      prunable: RctPrunable::Null,
+    },
+  }
+}
+
+// Create a transaction with a single output to the specified subaddress
+pub(crate) fn transaction(pair: &ViewPair, index: SubaddressIndex) -> Transaction {
+  transaction_to(
+    &pair.address(Network::Mainnet, AddressSpec::Subaddress(index)),
+    random_scalar(&mut OsRng),
+  )
+}
+
+fn received(scanner: &mut Scanner, tx: &Transaction) -> Option<Option<SubaddressIndex>> {
+  scanner.scan_transaction(tx).ignore_timelock().get(0).map(|output| output.metadata.subaddress)
+}
+
+#[test]
+fn subaddress_lookahead() {
+  let pair = view_pair();
+  let mut scanner = Scanner::from_view(pair.clone(), Some(HashSet::new()));
+  scanner.set_subaddress_lookahead(SubaddressLookahead::new(2, 3).unwrap());
+
+  // The initial window is accounts [0, 2) with addresses [0, 3)
+  let index = |account, address| SubaddressIndex::new(account, address).unwrap();
+  for (account, address) in [(0, 1), (0, 2), (1, 0), (1, 2)] {
+    let tx = transaction(&pair, index(account, address));
+    assert_eq!(received(&mut scanner, &tx), Some(Some(index(account, address))));
+  }
+
+  // (1, 2) was the highest address in account 1, and account 1 was the highest account
+  // This extended the window to accounts [0, 3), with addresses [0, 5) for accounts 0 and 1
+  assert_eq!(scanner.accounts(), 2);
+  assert_eq!(scanner.addresses(0), 3);
+  assert_eq!(scanner.addresses(1), 3);
+  assert_eq!(received(&mut scanner, &transaction(&pair, index(1, 4))), Some(Some(index(1, 4))));
+
+  // Account 2 isn't in use, so only its first 3 addresses are registered, and account 3 isn't
+  let beyond = [(0, 5), (2, 3), (3, 0)];
+  let beyond = beyond.map(|(account, address)| transaction(&pair, index(account, address)));
+  for tx in &beyond {
+    assert_eq!(received(&mut scanner, tx), None);
+  }
+
+  // Receiving to (0, 3) extends account 0's window to cover (0, 5)
+  assert!(received(&mut scanner, &transaction(&pair, index(0, 3))).is_some());
+  assert_eq!(received(&mut scanner, &beyond[0]), Some(Some(index(0, 5))));
+
+  // The state, including the extended window, survives serialization
+  let serialized = scanner.serialize();
+  let mut read = Scanner::read::<&[u8]>(pair.clone(), &mut serialized.as_ref()).unwrap();
+  assert_eq!(read.serialize(), serialized);
+  assert_eq!(read.subaddress_lookahead(), SubaddressLookahead::new(2, 3));
+  assert_eq!(read.accounts(), 2);
+  assert_eq!(read.addresses(0), 6);
+  assert_eq!(read.subaddresses, scanner.subaddresses);
+
+  // Receiving to account 2 puts it in use, registering account 3 and extending account 2
+  assert!(received(&mut read, &transaction(&pair, index(2, 2))).is_some());
+  assert_eq!(read.accounts(), 3);
+  assert_eq!(received(&mut read, &beyond[1]), Some(Some(index(2, 3))));
+  assert_eq!(received(&mut read, &beyond[2]), Some(Some(index(3, 0))));
+}
+
+#[test]
+fn create_subaddresses() {
+  let pair = view_pair();
+
+  // Without a lookahead, only the created subaddresses are registered
+  let mut scanner = Scanner::from_view(pair.clone(), Some(HashSet::new()));
+  assert_eq!(scanner.subaddress_lookahead(), None);
+  assert_eq!(scanner.create_subaddress(1), None);
+  assert_eq!(scanner.create_account(), 1);
+  assert_eq!(scanner.create_subaddress(1), SubaddressIndex::new(1, 1));
+  assert_eq!(scanner.create_subaddress(0), SubaddressIndex::new(0, 1));
+  assert_eq!(scanner.addresses(1), 2);
+  assert_eq!(scanner.subaddresses.len(), 4);
+
+  let index = SubaddressIndex::new(1, 1).unwrap();
+  assert_eq!(received(&mut scanner, &transaction(&pair, index)), Some(Some(index)));
+  let index = SubaddressIndex::new(1, 2).unwrap();
+  assert_eq!(received(&mut scanner, &transaction(&pair, index)), None);
+
+  // Setting a lookahead registers the subaddresses after those created
+  scanner.set_subaddress_lookahead(SubaddressLookahead::new(1, 2).unwrap());
+  assert_eq!(received(&mut scanner, &transaction(&pair, index)), Some(Some(index)));
+
+  // Creating a subaddress extends the lookahead
+  let index = SubaddressIndex::new(1, 4).unwrap();
+  assert_eq!(received(&mut scanner, &transaction(&pair, index)), None);
+  assert_eq!(scanner.create_subaddress(1), SubaddressIndex::new(1, 3));
+  assert_eq!(received(&mut scanner, &transaction(&pair, index)), Some(Some(index)));
+
+  assert!(SubaddressLookahead::new(0, 1).is_none());
+  assert!(SubaddressLookahead::new(1, 0).is_none());
+  assert_eq!(SubaddressLookahead::default(), SubaddressLookahead::new(50, 200).unwrap());
+}
+
+#[test]
+fn scanner_serialization() {
+  let pair = view_pair();
+  let mut scanner = Scanner::from_view(pair.clone(), None);
+  scanner.register_subaddress(SubaddressIndex::new(7, 9).unwrap());
+
+  let serialized = scanner.serialize();
+  let read = Scanner::read::<&[u8]>(pair.clone(), &mut serialized.as_ref()).unwrap();
+  assert_eq!(read.serialize(), serialized);
+  assert_eq!(read.subaddresses, scanner.subaddresses);
+  assert!(read.burning_bug.is_none());
+
+  // Scanned output keys are preserved for the burning bug protection
+  let mut scanner = Scanner::from_view(pair.clone(), Some(HashSet::new()));
+  let tx = transaction(&pair, SubaddressIndex::new(0, 1).unwrap());
+  scanner.register_subaddress(SubaddressIndex::new(0, 1).unwrap());
+  assert!(received(&mut scanner, &tx).is_some());
+
+  let serialized = scanner.serialize();
+  let mut read = Scanner::read::<&[u8]>(pair, &mut serialized.as_ref()).unwrap();
+  assert_eq!(read.burning_bug, scanner.burning_bug);
+  assert_eq!(received(&mut read, &tx), None);
+}
@@ -6,7 +6,11 @@ use curve25519_dalek::scalar::Scalar;
 
 use crate::{
   hash,
-  wallet::seed::{Seed, Language, classic::trim_by_lang},
+  wallet::seed::{
+    Seed, SeedError, Language,
+    classic::trim_by_lang,
+    polyseed::{self, Polyseed},
+  },
 };
 
 #[test]
@@ -175,3 +179,147 @@ fn test_classic_seed() {
     }
   }
 }
+
+#[test]
+fn test_polyseed() {
+  struct Vector {
+    language: polyseed::Language,
+    seed: String,
+    entropy: String,
+    // The time the seed was created at
+    created: u64,
+    // The created time, rounded down to the start of its time step
+    birthday: u64,
+  }
+
+  // These vectors are from the reference implementation's tests
+  let vectors = [
+    Vector {
+      language: polyseed::Language::English,
+      seed: "raven tail swear infant grief assist regular lamp \
+             duck valid someone little harsh puppy airport language"
+        .into(),
+      entropy: "dd76e7359a0ded37cd0ff0f3c829a5ae01673300000000000000000000000000".into(),
+      created: 1638446400,
+      birthday: 1638397746,
+    },
+    Vector {
+      language: polyseed::Language::French,
+      seed: "valable arracher décaler jeudi amusant dresser mener épaissir \
+             risible prouesse réserve ampleur ajuster muter caméra enchère"
+        .into(),
+      entropy: "11cfd870324b26657342c37360c424a14a050b00000000000000000000000000".into(),
+      created: 1679314966,
+      birthday: 1677843936,
+    },
+    Vector {
+      language: polyseed::Language::Italian,
+      seed: "caduco midollo copione meninge isotopo illogico riflesso tartaruga \
+             fermento olandese normale tristezza episodio voragine forbito achille"
+        .into(),
+      entropy: "7ecc57c9b4652d4e31428f62bec91cfd55500600000000000000000000000000".into(),
+      created: 1679316358,
+      birthday: 1677843936,
+    },
+    Vector {
+      language: polyseed::Language::Portuguese,
+      seed: "caverna custear azedo adeus senador apertada sedoso omitir \
+             sujeito aurora videira molho cartaz gesso dentista tapar"
+        .into(),
+      entropy: "45473063711376cae38f1b3eba18c874124e1d00000000000000000000000000".into(),
+      created: 1679316657,
+      birthday: 1677843936,
+    },
+  ];
+
+  for vector in &vectors {
+    // Test against the reference implementation
+    {
+      let seed = Polyseed::from_string(Zeroizing::new(vector.seed.clone())).unwrap();
+      assert_eq!(seed.language(), vector.language);
+      assert_eq!(hex::encode(seed.entropy()), vector.entropy);
+      assert_eq!(seed.birthday(), vector.birthday);
+      assert_eq!(*seed.to_string(), vector.seed);
+
+      let entropy = Zeroizing::new(hex::decode(&vector.entropy).unwrap().try_into().unwrap());
+      assert_eq!(Polyseed::from(vector.language, vector.created, entropy).unwrap(), seed);
+
+      let generic = Seed::from_string(Zeroizing::new(vector.seed.clone())).unwrap();
+      assert_eq!(generic, Seed::Polyseed(seed.clone()));
+      assert_eq!(generic.key(), seed.key());
+    }
+
+    // Test against ourself
+    {
+      let seed = Polyseed::new(&mut OsRng, vector.language);
+      assert_eq!(seed, Polyseed::from_string(seed.to_string()).unwrap());
+      assert_eq!(seed, Polyseed::from(vector.language, seed.birthday(), seed.entropy()).unwrap());
+
+      let encrypted = seed.to_encrypted_string("passphrase");
+      assert_eq!(
+        Polyseed::from_string(encrypted.clone()).unwrap_err(),
+        SeedError::EncryptedPolyseed
+      );
+      assert_eq!(Polyseed::from_encrypted_string(encrypted, "passphrase").unwrap(), seed);
+    }
+  }
+
+  // Words may be shortened to their first four characters, and accents may be omitted
+  assert_eq!(
+    Polyseed::from_string(Zeroizing::new(
+      "vala arra deca jeud amus dres mene epai risi prou rese ampl ajus mute came ench".into()
+    ))
+    .unwrap()
+    .entropy(),
+    Polyseed::from_string(Zeroizing::new(vectors[1].seed.clone())).unwrap().entropy()
+  );
+
+  // Test the English vector's keys against the reference implementation
+  {
+    let seed = Polyseed::from_string(Zeroizing::new(vectors[0].seed.clone())).unwrap();
+    let spend: [u8; 32] =
+      hex::decode("6dd6b2029bfdf1c44a36ce8b229f35dcaa5800b8d858da9facf4b0a778dc2800")
+        .unwrap()
+        .try_into()
+        .unwrap();
+    assert_eq!(*seed.key(), spend);
+    // The view key is derived as H(spend), as done for classic seeds
+    let view: [u8; 32] =
+      hex::decode("3c56a3cc3e7f94dc428ffe3b856adb6054552dfa14360d4cdec3f7730b999107")
+        .unwrap()
+        .try_into()
+        .unwrap();
+    assert_eq!(
+      Scalar::from_bytes_mod_order(hash(&spend)),
+      Scalar::from_canonical_bytes(view).unwrap()
+    );
+  }
+
+  // Test decrypting the English vector, encrypted with the passphrase "password"
+  {
+    let encrypted = "soup festival edge attend unusual hawk slush grocery \
+                     lady still knock way bubble shallow receive admit";
+    let seed =
+      Polyseed::from_encrypted_string(Zeroizing::new(encrypted.into()), "password").unwrap();
+    assert_eq!(
+      hex::encode(seed.entropy()),
+      "dd76e7359a0ded37cd0ff0f3c829a5ae01673300000000000000000000000000"
+    );
+    assert_eq!(*seed.to_encrypted_string("password"), encrypted);
+    assert_eq!(
+      Polyseed::from_encrypted_string(seed.to_string(), "password").unwrap_err(),
+      SeedError::UnencryptedPolyseed
+    );
+  }
+
+  // Swapping two words should invalidate the checksum
+  assert_eq!(
+    Polyseed::from_string(Zeroizing::new(
+      "raven tail swear infant grief assist regular lamp \
+       duck valid someone little harsh puppy language airport"
+        .into()
+    ))
+    .unwrap_err(),
+    SeedError::InvalidChecksum
+  );
+}
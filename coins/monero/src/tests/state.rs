@@ -0,0 +1,216 @@
+use core::ops::Deref;
+use std::collections::HashSet;
+
+use zeroize::Zeroizing;
+
+use rand_core::OsRng;
+
+use curve25519_dalek::{constants::ED25519_BASEPOINT_TABLE, scalar::Scalar, edwards::EdwardsPoint};
+
+use crate::{
+  random_scalar,
+  ringct::{hash_to_point, RctBase, RctPrunable, RctSignatures},
+  transaction::{Timelock, Input, TransactionPrefix, Transaction},
+  wallet::{
+    address::SubaddressIndex, ViewPair, Scanner, SpendableOutput, SignedKeyImage, OutputState,
+    Balance, WalletState, Mempool,
+  },
+  tests::scanner::transaction,
+};
+
+fn wallet(spend: bool) -> (ViewPair, Zeroizing<Scalar>, WalletState) {
+  let spend_key = Zeroizing::new(random_scalar(&mut OsRng));
+  let pair = ViewPair::new(
+    spend_key.deref() * &ED25519_BASEPOINT_TABLE,
+    Zeroizing::new(random_scalar(&mut OsRng)),
+  );
+  let mut scanner = Scanner::from_view(pair.clone(), Some(HashSet::new()));
+  scanner.register_subaddress(SubaddressIndex::new(0, 1).unwrap());
+  let state = WalletState::new(scanner, if spend { Some(spend_key.clone()) } else { None }, 100);
+  (pair, spend_key, state.unwrap())
+}
+
+fn key_image(spend: &Zeroizing<Scalar>, output: &SpendableOutput) -> EdwardsPoint {
+  (spend.deref() + output.key_offset()) * hash_to_point(output.key())
+}
+
+// Create a transaction spending the output with the specified key image
+fn spend(global_index: u64, key_image: EdwardsPoint) -> Transaction {
+  Transaction {
+    prefix: TransactionPrefix {
+      version: 2,
+      timelock: Timelock::None,
+      inputs: vec![Input::ToKey { amount: 0, key_offsets: vec![global_index], key_image }],
+      outputs: vec![],
+      extra: vec![],
+    },
+    signatures: vec![],
+    rct_signatures: RctSignatures {
+      base: RctBase { fee: 0, pseudo_outs: vec![], ecdh_info: vec![], commitments: vec![] },
+      prunable: RctPrunable::Null,
+    },
+  }
+}
+
+fn balance(unspent: u64, locked: u64, spent: u64) -> Balance {
+  Balance { unspent, locked, spent }
+}
+
+#[test]
+fn wallet_state() {
+  let (pair, spend_key, mut state) = wallet(true);
+
+  // The spend key must be the Scanner's
+  assert!(WalletState::new(
+    Scanner::from_view(pair.clone(), None),
+    Some(Zeroizing::new(random_scalar(&mut OsRng))),
+    0
+  )
+  .is_none());
+
+  let received = transaction(&pair, SubaddressIndex::new(0, 1).unwrap());
+  state.process(0, &[received.clone()], Some(50));
+  assert_eq!(state.height(), 101);
+  assert!(state.missing_key_images().is_empty());
+
+  let output = state.outputs()[0].clone();
+  assert_eq!(output.output().global_index, 50);
+  assert_eq!(output.height(), 100);
+  assert_eq!(output.key_image(), Some(key_image(&spend_key, output.output())));
+  assert_eq!(state.state(&output), OutputState::Locked);
+  assert_eq!(state.balance(), balance(0, 1, 0));
+
+  // Outputs unlock once they're 10 blocks old
+  for _ in 0 .. 8 {
+    state.process(0, &[], None);
+  }
+  assert_eq!(state.balance(), balance(0, 1, 0));
+  assert!(state.spendable().is_empty());
+  state.process(0, &[], None);
+  assert_eq!(state.height(), 110);
+  assert_eq!(state.balance(), balance(1, 0, 0));
+  assert_eq!(state.spendable(), vec![output.output()]);
+
+  // Spending the output is detected by its key image
+  let spending = spend(50, output.key_image().unwrap());
+  let other = spend(49, &random_scalar(&mut OsRng) * &ED25519_BASEPOINT_TABLE);
+  state.process(0, &[other], None);
+  assert_eq!(state.balance(), balance(1, 0, 0));
+  state.process(0, &[spending.clone()], None);
+  assert_eq!(state.state(state.outputs()[0]), OutputState::Spent(spending.hash()));
+  assert_eq!(state.outputs()[0].spent(), Some(spending.hash()));
+  assert_eq!(state.balance(), balance(0, 0, 1));
+
+  // Rolling back the spend restores the output
+  state.rollback(111, 0);
+  assert_eq!(state.height(), 111);
+  assert_eq!(state.balance(), balance(1, 0, 0));
+
+  // Rolling back the receipt removes the output, which can then be scanned again
+  state.rollback(100, 0);
+  assert!(state.outputs().is_empty());
+  assert_eq!(state.balance(), Balance::default());
+  state.process(0, &[received], Some(50));
+  assert_eq!(state.balance(), balance(0, 1, 0));
+}
+
+#[test]
+fn wallet_state_key_images() {
+  let (pair, spend_key, mut state) = wallet(false);
+
+  let received = transaction(&pair, SubaddressIndex::new(0, 1).unwrap());
+  state.process(0, &[received], Some(3));
+  let output = state.outputs()[0].output().clone();
+  assert_eq!(state.missing_key_images(), vec![&output]);
+
+  // Without the key image, the spend isn't detected
+  let key_image = key_image(&spend_key, &output);
+  state.process(0, &[spend(3, key_image)], None);
+  assert_eq!(state.state(state.outputs()[0]), OutputState::Locked);
+
+  assert!(!state.set_key_image(4, key_image));
+  assert!(state.set_key_image(3, key_image));
+  assert!(state.missing_key_images().is_empty());
+  let spending = spend(3, key_image);
+  state.process(0, &[spending.clone()], None);
+  assert_eq!(state.state(state.outputs()[0]), OutputState::Spent(spending.hash()));
+}
+
+#[test]
+fn wallet_state_signed_key_images() {
+  let (pair, spend_key, mut state) = wallet(false);
+  state.process(0, &[transaction(&pair, SubaddressIndex::new(0, 1).unwrap())], Some(7));
+  let output = state.outputs()[0].output().clone();
+
+  let signed = SignedKeyImage::new(&mut OsRng, &spend_key, &output.output);
+  assert_eq!(signed.key_image, key_image(&spend_key, &output));
+  assert!(signed.verify(output.key()));
+  assert_eq!(SignedKeyImage::read(&mut signed.serialize().as_slice()).unwrap(), signed);
+
+  // Signatures for other outputs, or by other keys, are rejected
+  assert!(!signed.verify(&random_scalar(&mut OsRng) * &ED25519_BASEPOINT_TABLE));
+  let other = Zeroizing::new(random_scalar(&mut OsRng));
+  let forged = SignedKeyImage::new(&mut OsRng, &other, &output.output);
+  assert!(!forged.verify(output.key()));
+  assert!(!state.import_key_image(7, &forged));
+  assert_eq!(state.missing_key_images(), vec![&output]);
+
+  assert!(!state.import_key_image(8, &signed));
+  assert!(state.import_key_image(7, &signed));
+  assert!(state.missing_key_images().is_empty());
+  assert_eq!(state.outputs()[0].key_image(), Some(signed.key_image));
+}
+
+#[test]
+fn wallet_state_timelocks() {
+  let (pair, _, mut state) = wallet(false);
+
+  let mut block_locked = transaction(&pair, SubaddressIndex::new(0, 1).unwrap());
+  block_locked.prefix.timelock = Timelock::Block(115);
+  let mut time_locked = transaction(&pair, SubaddressIndex::new(0, 1).unwrap());
+  time_locked.prefix.timelock = Timelock::Time(1_700_000_000);
+  state.process(1_600_000_000, &[block_locked, time_locked], Some(0));
+
+  for _ in 0 .. 9 {
+    state.process(1_600_000_000, &[], None);
+  }
+  // Neither timelock has been reached, despite the outputs being 10 blocks old
+  assert_eq!(state.balance(), balance(0, 2, 0));
+
+  for _ in 0 .. 5 {
+    state.process(1_600_000_000, &[], None);
+  }
+  assert_eq!(state.height(), 115);
+  assert_eq!(state.balance(), balance(1, 1, 0));
+  assert_eq!(state.state(state.outputs()[0]), OutputState::Unspent);
+
+  state.process(1_700_000_000, &[], None);
+  assert_eq!(state.balance(), balance(2, 0, 0));
+
+  // Rolling back restores the time of the latest block remaining
+  state.rollback(115, 1_600_000_000);
+  assert_eq!(state.balance(), balance(1, 1, 0));
+}
+
+#[test]
+fn wallet_state_mempool_spends() {
+  let (pair, spend_key, mut state) = wallet(true);
+
+  state.process(0, &[transaction(&pair, SubaddressIndex::new(0, 1).unwrap())], Some(0));
+  let output = state.outputs()[0].clone();
+  let image = key_image(&spend_key, output.output());
+
+  assert!(state.mempool_spends(&Mempool::default()).is_empty());
+  let spending = spend(0, image);
+  // Also include a transaction spending another wallet's output
+  let unrelated = spend(1, &random_scalar(&mut OsRng) * &ED25519_BASEPOINT_TABLE);
+  let mempool = Mempool::new(vec![spending.clone(), unrelated]);
+  let spends = state.mempool_spends(&mempool);
+  assert_eq!(spends.len(), 1);
+  assert_eq!(spends[0].0, &output);
+  assert_eq!(spends[0].1, &[spending.hash()]);
+
+  // Once the spend is confirmed, it's no longer pending
+  state.process(0, &[spending], None);
+  assert!(state.mempool_spends(&mempool).is_empty());
+}
@@ -0,0 +1,118 @@
+use std::collections::HashSet;
+
+use rand_core::OsRng;
+
+use crate::{
+  Protocol, random_scalar,
+  wallet::{
+    address::{Network, AddressSpec, MoneroAddress},
+    Scanner, SpendableOutput, Fee, Change, TransactionError, SignableTransactionBuilder,
+  },
+  tests::scanner::{view_pair, transaction_to},
+};
+
+const FEE: Fee = Fee { per_weight: 100, mask: 10000 };
+
+fn address() -> MoneroAddress {
+  view_pair().address(Network::Mainnet, AddressSpec::Standard)
+}
+
+// Create inputs with the specified amounts. As their rings are only fetched when signing, the
+// global index is irrelevant
+pub(crate) fn inputs(amounts: &[u64]) -> Vec<SpendableOutput> {
+  let view = view_pair();
+  let address = view.address(Network::Mainnet, AddressSpec::Standard);
+  let mut scanner = Scanner::from_view(view, Some(HashSet::new()));
+
+  amounts
+    .iter()
+    .map(|amount| {
+      let mut output = scanner
+        .scan_transaction(&transaction_to(&address, random_scalar(&mut OsRng)))
+        .ignore_timelock()
+        .swap_remove(0);
+      output.data.commitment.amount = *amount;
+      SpendableOutput { output, global_index: 0 }
+    })
+    .collect()
+}
+
+fn builder(inputs: &[SpendableOutput]) -> SignableTransactionBuilder {
+  let mut builder =
+    SignableTransactionBuilder::new(Protocol::v16, FEE, Some(Change::new(&view_pair(), false)));
+  builder.add_inputs(inputs);
+  builder
+}
+
+fn sum(inputs: &[SpendableOutput]) -> u64 {
+  inputs.iter().map(|input| input.commitment().amount).sum()
+}
+
+#[test]
+fn sweep() {
+  let dest = address();
+  let inputs = inputs(&[1_000_000_000_000; 200]);
+
+  let txs = builder(&inputs).sweep(dest).unwrap();
+  // 200 inputs won't fit in a single transaction
+  assert!(txs.len() > 1);
+  assert_eq!(txs.iter().map(|tx| tx.inputs().len()).sum::<usize>(), inputs.len());
+
+  for tx in txs {
+    let payments = tx.payments();
+    assert_eq!(payments.len(), 2);
+    assert_eq!(payments[0], (dest, sum(tx.inputs()) - tx.fee()));
+    assert_eq!(payments[1].1, 0);
+  }
+}
+
+#[test]
+fn sweep_dust() {
+  let dest = address();
+  // 128 inputs fit in a transaction. If split into 128, 128, and 1 inputs, the final transaction
+  // wouldn't be able to pay for its fee
+  let inputs = inputs(&[90_000; 257]);
+
+  let txs = builder(&inputs).sweep(dest).unwrap();
+  assert_eq!(txs.iter().map(|tx| tx.inputs().len()).collect::<Vec<_>>(), vec![86, 86, 85]);
+  for tx in txs {
+    assert_eq!(tx.payments()[0], (dest, sum(tx.inputs()) - tx.fee()));
+  }
+}
+
+#[test]
+fn sweep_below() {
+  let dest = address();
+  let inputs = inputs(&[1_000_000_000, 1_000_000_000_000, 2_000_000_000, 3_000_000_000]);
+
+  let txs = builder(&inputs).sweep_below(dest, 10_000_000_000).unwrap();
+  assert_eq!(txs.len(), 1);
+  let tx = &txs[0];
+  assert_eq!(tx.inputs().len(), 3);
+  assert!(tx.inputs().iter().all(|input| input.commitment().amount < 10_000_000_000));
+  assert_eq!(tx.payments()[0], (dest, 6_000_000_000 - tx.fee()));
+
+  assert_eq!(builder(&inputs).sweep_below(dest, 1).unwrap_err(), TransactionError::NoInputs);
+}
+
+#[test]
+fn sweep_errors() {
+  let dest = address();
+  let inputs = inputs(&[1_000_000_000_000, 1]);
+
+  assert_eq!(builder(&[]).sweep(dest).unwrap_err(), TransactionError::NoInputs);
+
+  let mut with_payment = builder(&inputs);
+  with_payment.add_payment(dest, 5);
+  assert_eq!(with_payment.sweep(dest).unwrap_err(), TransactionError::PaymentsInSweep);
+
+  let mut without_change = SignableTransactionBuilder::new(Protocol::v16, FEE, None);
+  without_change.add_inputs(&inputs);
+  assert_eq!(without_change.sweep(dest).unwrap_err(), TransactionError::NoChange);
+
+  // An input which can't pay for its own inclusion can't be swept
+  assert!(matches!(
+    builder(&inputs).sweep_below(dest, 2).unwrap_err(),
+    TransactionError::NotEnoughFunds(1, _)
+  ));
+}
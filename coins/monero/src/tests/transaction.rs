@@ -0,0 +1,651 @@
+#![allow(non_snake_case)]
+
+use core::ops::Deref;
+
+use zeroize::Zeroizing;
+use rand_core::{RngCore, OsRng};
+
+use curve25519_dalek::{constants::ED25519_BASEPOINT_TABLE, scalar::Scalar, edwards::EdwardsPoint};
+
+use crate::{
+  Commitment, H, hash, hash_to_scalar, random_scalar,
+  ring_signatures::{Signature, RingSignature},
+  ringct::{
+    generate_key_image, hash_to_point,
+    mlsag::{RingMatrix, Mlsag},
+    borromean::{BorromeanSignatures, BorromeanRange},
+    bulletproofs::Bulletproofs,
+    RctType, EncryptedAmount, RctBase, RctPrunable, RctSignatures,
+  },
+  transaction::{Input, Output, Timelock, TransactionPrefix, Transaction},
+};
+
+const TRANSACTIONS_JSON: &str = include_str!("vectors/transactions.json");
+
+const RING_LEN: usize = 11;
+const FEE: u64 = 1337;
+
+#[test]
+fn transaction_vectors() {
+  #[derive(serde::Deserialize)]
+  struct Vector {
+    hex: String,
+    hash: String,
+    prefix_hash: String,
+    rct_type: u8,
+  }
+
+  let vectors = serde_json::from_str::<Vec<Vector>>(TRANSACTIONS_JSON).unwrap();
+  for vector in vectors {
+    let serialized = hex::decode(vector.hex).unwrap();
+    let tx = Transaction::read::<&[u8]>(&mut serialized.as_ref()).unwrap();
+    assert_eq!(tx.serialize(), serialized);
+    assert_eq!(hex::encode(tx.hash()), vector.hash);
+    assert_eq!(hex::encode(hash(&tx.prefix.serialize())), vector.prefix_hash);
+    assert_eq!(tx.rct_signatures.rct_type(), RctType::from_byte(vector.rct_type).unwrap());
+
+    if tx.prefix.version == 1 {
+      assert_eq!(hex::encode(tx.signature_hash()), vector.prefix_hash);
+    }
+
+    if let RctPrunable::MlsagBulletproofs { bulletproofs, mlsags, pseudo_outs } =
+      &tx.rct_signatures.prunable
+    {
+      assert_eq!(bulletproofs.len(), 1);
+      assert!(bulletproofs[0].verify(&mut OsRng, &tx.rct_signatures.base.commitments));
+      assert_eq!(mlsags.len(), tx.prefix.inputs.len());
+      assert_eq!(pseudo_outs.len(), tx.prefix.inputs.len());
+
+      // The inputs must balance with the outputs and fee
+      assert_eq!(
+        pseudo_outs.iter().sum::<EdwardsPoint>(),
+        tx.rct_signatures.base.commitments.iter().sum::<EdwardsPoint>() +
+          (Scalar::from(tx.rct_signatures.base.fee) * *H)
+      );
+    }
+  }
+}
+
+#[test]
+fn ring_signature() {
+  for real in 0 .. RING_LEN {
+    let msg = [1; 32];
+
+    let mut secret = Zeroizing::new(Scalar::zero());
+    let mut ring = vec![];
+    for i in 0 .. RING_LEN {
+      let dest = Zeroizing::new(random_scalar(&mut OsRng));
+      ring.push(dest.deref() * &ED25519_BASEPOINT_TABLE);
+      if i == real {
+        secret = dest;
+      }
+    }
+    let image = generate_key_image(&secret);
+
+    // Monero's generate_ring_signature
+    let mut buf = msg.to_vec();
+    let mut sigs = vec![];
+    let mut sum = Scalar::zero();
+    let k = random_scalar(&mut OsRng);
+    for (i, P) in ring.iter().enumerate() {
+      if i == real {
+        buf.extend((&k * &ED25519_BASEPOINT_TABLE).compress().to_bytes());
+        buf.extend((k * hash_to_point(*P)).compress().to_bytes());
+        sigs.push(Signature { c: Scalar::zero(), r: Scalar::zero() });
+        continue;
+      }
+
+      let sig = Signature { c: random_scalar(&mut OsRng), r: random_scalar(&mut OsRng) };
+      buf.extend(
+        EdwardsPoint::vartime_double_scalar_mul_basepoint(&sig.c, P, &sig.r).compress().to_bytes(),
+      );
+      buf.extend(((sig.r * hash_to_point(*P)) + (sig.c * image)).compress().to_bytes());
+      sum += sig.c;
+      sigs.push(sig);
+    }
+    sigs[real].c = hash_to_scalar(&buf) - sum;
+    sigs[real].r = k - (sigs[real].c * secret.deref());
+
+    let sig = RingSignature { sigs };
+    assert!(sig.verify(&msg, &ring, &image));
+    assert!(!sig.verify(&[2; 32], &ring, &image));
+
+    let mut serialized = vec![];
+    sig.write(&mut serialized).unwrap();
+    assert_eq!(RingSignature::read::<&[u8]>(RING_LEN, &mut serialized.as_ref()).unwrap(), sig);
+  }
+}
+
+// Monero's MLSAG_Gen, over a matrix whose last column is the commitment
+fn sign_mlsag(
+  msg: &[u8; 32],
+  matrix: &[Vec<EdwardsPoint>],
+  real: usize,
+  secrets: &[Scalar],
+) -> (Mlsag, Vec<EdwardsPoint>) {
+  let member_len = matrix[0].len();
+  let images = (0 .. (member_len - 1))
+    .map(|j| secrets[j] * hash_to_point(matrix[real][j]))
+    .collect::<Vec<_>>();
+
+  let mut ss = vec![vec![Scalar::zero(); member_len]; matrix.len()];
+  let alpha = (0 .. member_len).map(|_| random_scalar(&mut OsRng)).collect::<Vec<_>>();
+
+  let mut buf = msg.to_vec();
+  for (j, P) in matrix[real].iter().enumerate() {
+    buf.extend(P.compress().to_bytes());
+    buf.extend((&alpha[j] * &ED25519_BASEPOINT_TABLE).compress().to_bytes());
+    if j < images.len() {
+      buf.extend((alpha[j] * hash_to_point(*P)).compress().to_bytes());
+    }
+  }
+  let mut c = hash_to_scalar(&buf);
+
+  let mut cc = Scalar::zero();
+  let mut i = (real + 1) % matrix.len();
+  if i == 0 {
+    cc = c;
+  }
+  while i != real {
+    buf.clear();
+    buf.extend(msg);
+    for (j, P) in matrix[i].iter().enumerate() {
+      ss[i][j] = random_scalar(&mut OsRng);
+      buf.extend(P.compress().to_bytes());
+      buf.extend(
+        EdwardsPoint::vartime_double_scalar_mul_basepoint(&c, P, &ss[i][j]).compress().to_bytes(),
+      );
+      if let Some(image) = images.get(j) {
+        buf.extend(((ss[i][j] * hash_to_point(*P)) + (c * image)).compress().to_bytes());
+      }
+    }
+    c = hash_to_scalar(&buf);
+
+    i = (i + 1) % matrix.len();
+    if i == 0 {
+      cc = c;
+    }
+  }
+
+  ss[real] = alpha.iter().zip(secrets).map(|(alpha, secret)| alpha - (c * secret)).collect();
+  (Mlsag { ss, cc }, images)
+}
+
+#[test]
+fn mlsag_individual() {
+  for real in 0 .. RING_LEN {
+    let msg = [1; 32];
+    let amount = OsRng.next_u64();
+
+    let mut secrets = (Zeroizing::new(Scalar::zero()), Scalar::zero());
+    let mut ring = vec![];
+    for i in 0 .. RING_LEN {
+      let dest = Zeroizing::new(random_scalar(&mut OsRng));
+      let mask = random_scalar(&mut OsRng);
+      let amount = if i == real { amount } else { OsRng.next_u64() };
+      ring
+        .push([dest.deref() * &ED25519_BASEPOINT_TABLE, Commitment::new(mask, amount).calculate()]);
+      if i == real {
+        secrets = (dest, mask);
+      }
+    }
+
+    let pseudo_out_mask = random_scalar(&mut OsRng);
+    let pseudo_out = Commitment::new(pseudo_out_mask, amount).calculate();
+    let matrix =
+      ring.iter().map(|member| vec![member[0], member[1] - pseudo_out]).collect::<Vec<_>>();
+
+    let (mlsag, images) =
+      sign_mlsag(&msg, &matrix, real, &[*secrets.0, secrets.1 - pseudo_out_mask]);
+    let ring_matrix = RingMatrix::individual(&ring, pseudo_out).unwrap();
+    mlsag.verify(&msg, &ring_matrix, &images).unwrap();
+    assert!(mlsag.verify(&[2; 32], &ring_matrix, &images).is_err());
+
+    let mut serialized = vec![];
+    mlsag.write(&mut serialized).unwrap();
+    assert_eq!(Mlsag::read::<&[u8]>(RING_LEN, 2, &mut serialized.as_ref()).unwrap(), mlsag);
+  }
+}
+
+#[test]
+fn mlsag_aggregate() {
+  let msg = [1; 32];
+  let real = usize::try_from(OsRng.next_u64() % u64::try_from(RING_LEN).unwrap()).unwrap();
+  let fee = 1337;
+  let amounts = [OsRng.next_u64() >> 2, OsRng.next_u64() >> 2];
+
+  let mut rings = vec![];
+  let mut secrets = vec![];
+  let mut mask_sum = Scalar::zero();
+  for amount in amounts {
+    let mut ring = vec![];
+    for i in 0 .. RING_LEN {
+      let dest = random_scalar(&mut OsRng);
+      let mask = random_scalar(&mut OsRng);
+      ring.push([
+        &dest * &ED25519_BASEPOINT_TABLE,
+        Commitment::new(mask, if i == real { amount } else { OsRng.next_u64() }).calculate(),
+      ]);
+      if i == real {
+        secrets.push(dest);
+        mask_sum += mask;
+      }
+    }
+    rings.push(ring);
+  }
+
+  let output_mask = random_scalar(&mut OsRng);
+  let outputs = [Commitment::new(output_mask, amounts.iter().sum::<u64>() - fee).calculate()];
+  secrets.push(mask_sum - output_mask);
+
+  let matrix = (0 .. RING_LEN)
+    .map(|m| {
+      let mut member = rings.iter().map(|ring| ring[m][0]).collect::<Vec<_>>();
+      member.push(
+        rings.iter().map(|ring| ring[m][1]).sum::<EdwardsPoint>() -
+          outputs[0] -
+          (Scalar::from(fee) * *H),
+      );
+      member
+    })
+    .collect::<Vec<_>>();
+
+  let (mlsag, images) = sign_mlsag(&msg, &matrix, real, &secrets);
+  let ring_matrix = RingMatrix::aggregate(&rings, &outputs, fee).unwrap();
+  mlsag.verify(&msg, &ring_matrix, &images).unwrap();
+
+  // A different fee means the commitments no longer balance
+  let ring_matrix = RingMatrix::aggregate(&rings, &outputs, fee + 1).unwrap();
+  assert!(mlsag.verify(&msg, &ring_matrix, &images).is_err());
+}
+
+// Monero's genBorromean, where the first key is the bit commitment and the second key is the bit
+// commitment minus 2**i * H
+// Returns the range proof with the mask of the commitment it's for
+fn prove_borromean(amount: u64) -> (BorromeanRange, Scalar) {
+  let mut masks = vec![];
+  let mut bit_commitments = vec![];
+  for i in 0 .. 64 {
+    let mask = random_scalar(&mut OsRng);
+    bit_commitments.push(Commitment::new(mask, amount & (1 << i)).calculate());
+    masks.push(mask);
+  }
+
+  let mut s0 = [[0; 32]; 64];
+  let mut s1 = [[0; 32]; 64];
+  let mut alpha = vec![];
+  let mut transcript = vec![];
+  for i in 0 .. 64 {
+    let a = random_scalar(&mut OsRng);
+    let L = &a * &ED25519_BASEPOINT_TABLE;
+    if ((amount >> i) & 1) == 0 {
+      let s = random_scalar(&mut OsRng);
+      s1[i] = s.to_bytes();
+      let bit_commitment_minus_bit = bit_commitments[i] - (Scalar::from(1u64 << i) * *H);
+      transcript.extend(
+        EdwardsPoint::vartime_double_scalar_mul_basepoint(
+          &hash_to_scalar(L.compress().as_bytes()),
+          &bit_commitment_minus_bit,
+          &s,
+        )
+        .compress()
+        .to_bytes(),
+      );
+    } else {
+      transcript.extend(L.compress().to_bytes());
+    }
+    alpha.push(a);
+  }
+  let ee = hash_to_scalar(&transcript);
+
+  for i in 0 .. 64 {
+    if ((amount >> i) & 1) == 0 {
+      s0[i] = (alpha[i] - (masks[i] * ee)).to_bytes();
+    } else {
+      let s = random_scalar(&mut OsRng);
+      s0[i] = s.to_bytes();
+      let LL = EdwardsPoint::vartime_double_scalar_mul_basepoint(&ee, &bit_commitments[i], &s);
+      s1[i] = (alpha[i] - (masks[i] * hash_to_scalar(LL.compress().as_bytes()))).to_bytes();
+    }
+  }
+
+  (
+    BorromeanRange {
+      sigs: BorromeanSignatures { s0, s1, ee: ee.to_bytes() },
+      bit_commitments: bit_commitments.try_into().unwrap(),
+    },
+    masks.iter().sum(),
+  )
+}
+
+#[test]
+fn borromean() {
+  let amount = OsRng.next_u64();
+  let (range, mask) = prove_borromean(amount);
+  let commitment = Commitment::new(mask, amount).calculate();
+  assert_eq!(range.bit_commitments.iter().sum::<EdwardsPoint>(), commitment);
+  assert!(range.verify(&commitment));
+  assert!(!range.verify(&(commitment + *H)));
+
+  let mut serialized = vec![];
+  range.write(&mut serialized).unwrap();
+  assert_eq!(BorromeanRange::read::<&[u8]>(&mut serialized.as_ref()).unwrap(), range);
+}
+
+// Create a transaction of the specified type, spending two inputs to two outputs, signed over
+// randomly generated rings. `RctType::Null` creates a version 1 transaction, signed with ring
+// signatures.
+// Returns the transaction with the ring of each input. The commitments of version 1 ring members
+// are those Monero uses for outputs with transparent amounts, which have a mask of one.
+pub(crate) fn signed_transaction(rct_type: RctType) -> (Transaction, Vec<Vec<[EdwardsPoint; 2]>>) {
+  signed_transaction_with_proofs(rct_type, &[1, 1])
+}
+
+// Create a transaction as `signed_transaction` does, with an output for every output proven by
+// `proofs`. The original Bulletproofs type has a proof per entry in `proofs`, each proving the
+// specified amount of outputs, in order.
+pub(crate) fn signed_transaction_with_proofs(
+  rct_type: RctType,
+  proofs: &[usize],
+) -> (Transaction, Vec<Vec<[EdwardsPoint; 2]>>) {
+  let version_1 = rct_type == RctType::Null;
+  // An aggregate MLSAG has a single real member, which must be at the same index in every ring
+  let aggregate_real =
+    usize::try_from(OsRng.next_u64() % u64::try_from(RING_LEN).unwrap()).unwrap();
+
+  let mut inputs = vec![];
+  let mut rings = vec![];
+  let mut spends = vec![];
+  for _ in 0 .. 2 {
+    let real = if rct_type == RctType::MlsagAggregate {
+      aggregate_real
+    } else {
+      usize::try_from(OsRng.next_u64() % u64::try_from(RING_LEN).unwrap()).unwrap()
+    };
+    let amount = OsRng.next_u64() >> 8;
+    let mask = if version_1 { Scalar::one() } else { random_scalar(&mut OsRng) };
+
+    let mut ring = vec![];
+    let mut key = Zeroizing::new(Scalar::zero());
+    for i in 0 .. RING_LEN {
+      let dest = Zeroizing::new(random_scalar(&mut OsRng));
+      let commitment = if i == real {
+        Commitment::new(mask, amount)
+      } else if version_1 {
+        // Version 1 rings are composed of outputs with the same amount
+        Commitment::new(Scalar::one(), amount)
+      } else {
+        Commitment::new(random_scalar(&mut OsRng), OsRng.next_u64())
+      };
+      ring.push([dest.deref() * &ED25519_BASEPOINT_TABLE, commitment.calculate()]);
+      if i == real {
+        key = dest;
+      }
+    }
+
+    let mut key_offsets = vec![OsRng.next_u64() % (1 << 20)];
+    key_offsets.extend((1 .. RING_LEN).map(|_| 1 + (OsRng.next_u64() % 100)));
+    inputs.push(Input::ToKey {
+      amount: if version_1 { amount } else { 0 },
+      key_offsets,
+      key_image: generate_key_image(&key),
+    });
+    rings.push(ring);
+    spends.push((real, key, mask, amount));
+  }
+
+  let total = spends.iter().map(|spend| spend.3).sum::<u64>() - FEE;
+  let amounts = {
+    let outputs = proofs.iter().sum::<usize>();
+    let mut amounts = (1 .. outputs)
+      .map(|_| OsRng.next_u64() % (total / u64::try_from(outputs).unwrap()))
+      .collect::<Vec<_>>();
+    amounts.push(total - amounts.iter().sum::<u64>());
+    amounts
+  };
+
+  // Borromean range proofs determine the masks of the commitments they're for
+  let mut borromean = vec![];
+  let mut masks = vec![];
+  for amount in amounts.iter().copied() {
+    if matches!(rct_type, RctType::MlsagAggregate | RctType::MlsagIndividual) {
+      let (range, mask) = prove_borromean(amount);
+      borromean.push(range);
+      masks.push(mask);
+    } else {
+      masks.push(random_scalar(&mut OsRng));
+    }
+  }
+  let outputs = masks
+    .iter()
+    .zip(&amounts)
+    .map(|(mask, amount)| Commitment::new(*mask, *amount))
+    .collect::<Vec<_>>();
+
+  let mut extra = vec![1];
+  extra.extend((&random_scalar(&mut OsRng) * &ED25519_BASEPOINT_TABLE).compress().to_bytes());
+  let prefix = TransactionPrefix {
+    version: if version_1 { 1 } else { 2 },
+    timelock: Timelock::None,
+    inputs,
+    outputs: amounts
+      .iter()
+      .map(|amount| Output {
+        amount: if version_1 { *amount } else { 0 },
+        key: (&random_scalar(&mut OsRng) * &ED25519_BASEPOINT_TABLE).compress(),
+        view_tag: None,
+      })
+      .collect(),
+    extra,
+  };
+
+  let mut tx = Transaction {
+    prefix,
+    signatures: vec![],
+    rct_signatures: RctSignatures {
+      base: RctBase { fee: FEE, pseudo_outs: vec![], ecdh_info: vec![], commitments: vec![] },
+      prunable: RctPrunable::Null,
+    },
+  };
+
+  if version_1 {
+    let msg = tx.signature_hash();
+    tx.signatures = spends
+      .iter()
+      .zip(&rings)
+      .map(|((real, key, _, _), ring)| {
+        let ring = ring.iter().map(|member| member[0]).collect::<Vec<_>>();
+        RingSignature::sign(&mut OsRng, &msg, &ring, *real, key).0
+      })
+      .collect();
+    return (tx, rings);
+  }
+
+  // The pseudo-outputs' masks must sum to the outputs' masks for the commitments to balance
+  let mut pseudo_out_masks =
+    (1 .. spends.len()).map(|_| random_scalar(&mut OsRng)).collect::<Vec<_>>();
+  pseudo_out_masks.push(masks.iter().sum::<Scalar>() - pseudo_out_masks.iter().sum::<Scalar>());
+  let pseudo_outs = spends
+    .iter()
+    .zip(&pseudo_out_masks)
+    .map(|(spend, mask)| Commitment::new(*mask, spend.3).calculate())
+    .collect::<Vec<_>>();
+
+  let base = &mut tx.rct_signatures.base;
+  base.commitments = outputs.iter().map(Commitment::calculate).collect();
+  base.ecdh_info = (0 .. outputs.len())
+    .map(|_| {
+      if rct_type.compact_encrypted_amounts() {
+        EncryptedAmount::Compact { amount: OsRng.next_u64().to_le_bytes() }
+      } else {
+        EncryptedAmount::Original {
+          mask: random_scalar(&mut OsRng).to_bytes(),
+          amount: random_scalar(&mut OsRng).to_bytes(),
+        }
+      }
+    })
+    .collect();
+  if rct_type == RctType::MlsagIndividual {
+    base.pseudo_outs = pseudo_outs.clone();
+  }
+
+  // The MLSAGs are over the range proofs, so they're set before signing
+  let empty = Mlsag { ss: vec![], cc: Scalar::zero() };
+  tx.rct_signatures.prunable = match rct_type {
+    RctType::MlsagAggregate => RctPrunable::AggregateMlsagBorromean { borromean, mlsag: empty },
+    RctType::MlsagIndividual => {
+      RctPrunable::MlsagBorromean { borromean, mlsags: vec![empty; spends.len()] }
+    }
+    // The original Bulletproofs type was used with a proof per output, yet allowed any split
+    RctType::Bulletproofs => RctPrunable::MlsagBulletproofs {
+      bulletproofs: {
+        let mut remaining = outputs.as_slice();
+        proofs
+          .iter()
+          .map(|proven| {
+            let (these, rest) = remaining.split_at(*proven);
+            remaining = rest;
+            Bulletproofs::prove(&mut OsRng, these, false).unwrap()
+          })
+          .collect()
+      },
+      mlsags: vec![empty; spends.len()],
+      pseudo_outs,
+    },
+    RctType::BulletproofsCompactAmount => RctPrunable::MlsagBulletproofs {
+      bulletproofs: vec![Bulletproofs::prove(&mut OsRng, &outputs, false).unwrap()],
+      mlsags: vec![empty; spends.len()],
+      pseudo_outs,
+    },
+    _ => panic!("signing an unsupported RctType"),
+  };
+
+  let msg = tx.signature_hash();
+  match &mut tx.rct_signatures.prunable {
+    RctPrunable::AggregateMlsagBorromean { mlsag, .. } => {
+      let out =
+        tx.rct_signatures.base.commitments.iter().sum::<EdwardsPoint>() + (Scalar::from(FEE) * *H);
+      let matrix = (0 .. RING_LEN)
+        .map(|m| {
+          let mut member = rings.iter().map(|ring| ring[m][0]).collect::<Vec<_>>();
+          member.push(rings.iter().map(|ring| ring[m][1]).sum::<EdwardsPoint>() - out);
+          member
+        })
+        .collect::<Vec<_>>();
+      let mut secrets = spends.iter().map(|spend| *spend.1).collect::<Vec<_>>();
+      secrets
+        .push(spends.iter().map(|spend| spend.2).sum::<Scalar>() - masks.iter().sum::<Scalar>());
+      *mlsag = sign_mlsag(&msg, &matrix, aggregate_real, &secrets).0;
+    }
+    RctPrunable::MlsagBorromean { mlsags, .. } | RctPrunable::MlsagBulletproofs { mlsags, .. } => {
+      for (i, ((real, key, mask, _), ring)) in spends.iter().zip(&rings).enumerate() {
+        let pseudo_out = Commitment::new(pseudo_out_masks[i], spends[i].3).calculate();
+        let matrix =
+          ring.iter().map(|member| vec![member[0], member[1] - pseudo_out]).collect::<Vec<_>>();
+        mlsags[i] = sign_mlsag(&msg, &matrix, *real, &[**key, mask - pseudo_out_masks[i]]).0;
+      }
+    }
+    _ => unreachable!(),
+  }
+
+  (tx, rings)
+}
+
+// Verify a transaction's ring signatures or MLSAGs, and its range proofs, against its rings
+fn verify_signatures(tx: &Transaction, rings: &[Vec<[EdwardsPoint; 2]>]) -> bool {
+  let msg = tx.signature_hash();
+  let images = tx
+    .prefix
+    .inputs
+    .iter()
+    .map(|input| match input {
+      Input::ToKey { key_image, .. } => *key_image,
+      Input::Gen(_) => panic!("verifying a miner transaction"),
+    })
+    .collect::<Vec<_>>();
+
+  let base = &tx.rct_signatures.base;
+  let individual = |mlsags: &[Mlsag], pseudo_outs: &[EdwardsPoint]| {
+    mlsags.iter().zip(rings).zip(pseudo_outs).zip(&images).all(
+      |(((mlsag, ring), pseudo_out), image)| {
+        mlsag.verify(&msg, &RingMatrix::individual(ring, *pseudo_out).unwrap(), &[*image]).is_ok()
+      },
+    )
+  };
+  let borromean = |borromean: &[BorromeanRange]| {
+    borromean.iter().zip(&base.commitments).all(|(range, commitment)| range.verify(commitment))
+  };
+
+  match &tx.rct_signatures.prunable {
+    RctPrunable::Null => {
+      tx.signatures.iter().zip(rings).zip(&images).all(|((sig, ring), image)| {
+        sig.verify(&msg, &ring.iter().map(|member| member[0]).collect::<Vec<_>>(), image)
+      })
+    }
+    RctPrunable::AggregateMlsagBorromean { borromean: ranges, mlsag } => {
+      borromean(ranges) &&
+        mlsag
+          .verify(
+            &msg,
+            &RingMatrix::aggregate(rings, &base.commitments, base.fee).unwrap(),
+            &images,
+          )
+          .is_ok()
+    }
+    RctPrunable::MlsagBorromean { borromean: ranges, mlsags } => {
+      borromean(ranges) && individual(mlsags, &base.pseudo_outs)
+    }
+    RctPrunable::MlsagBulletproofs { bulletproofs, mlsags, pseudo_outs } => {
+      let per_proof = base.commitments.len() / bulletproofs.len();
+      bulletproofs
+        .iter()
+        .zip(base.commitments.chunks(per_proof))
+        .all(|(bp, commitments)| bp.verify(&mut OsRng, commitments)) &&
+        individual(mlsags, pseudo_outs)
+    }
+    RctPrunable::Clsag { .. } => panic!("verifying CLSAGs without their verifier"),
+  }
+}
+
+#[test]
+fn signed_transactions() {
+  for rct_type in [
+    RctType::Null,
+    RctType::MlsagAggregate,
+    RctType::MlsagIndividual,
+    RctType::Bulletproofs,
+    RctType::BulletproofsCompactAmount,
+  ] {
+    let (tx, rings) = signed_transaction(rct_type);
+
+    let serialized = tx.serialize();
+    let read = Transaction::read::<&[u8]>(&mut serialized.as_ref()).unwrap();
+    assert_eq!(read, tx);
+    assert_eq!(read.serialize(), serialized);
+    assert_eq!(read.rct_signatures.rct_type(), rct_type);
+
+    // Hash the serialization directly, as Monero does, instead of the parsed structures
+    let (prefix, rest) = serialized.split_at(tx.prefix.serialize().len());
+    if rct_type == RctType::Null {
+      assert_eq!(read.hash(), hash(&serialized));
+      assert_eq!(read.signature_hash(), hash(prefix));
+      assert_eq!(read.rct_signatures.base.fee, FEE);
+    } else {
+      let mut base = vec![];
+      tx.rct_signatures.base.write(&mut base, rct_type).unwrap();
+      let (base, prunable) = rest.split_at(base.len());
+      assert_eq!(read.hash(), hash(&[hash(prefix), hash(base), hash(prunable)].concat()));
+    }
+
+    assert!(verify_signatures(&read, &rings));
+
+    // Changing the amounts invalidates the signatures
+    let mut tampered = read.clone();
+    if rct_type == RctType::Null {
+      tampered.prefix.outputs[0].amount += 1;
+    } else {
+      tampered.rct_signatures.base.fee += 1;
+    }
+    assert!(!verify_signatures(&tampered, &rings));
+  }
+}
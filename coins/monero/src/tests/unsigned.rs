@@ -0,0 +1,142 @@
+use std::io;
+
+use zeroize::Zeroizing;
+
+use rand_core::{RngCore, OsRng};
+
+use curve25519_dalek::constants::ED25519_BASEPOINT_TABLE;
+
+use crate::{
+  Protocol, random_scalar,
+  serialize::{write_varint, write_vec},
+  ringct::bulletproofs::MAX_OUTPUTS,
+  wallet::{
+    address::{Network, AddressSpec},
+    SpendableOutput, Decoys, Fee, SignableTransaction, UnsignedTransaction,
+  },
+  tests::{scanner::view_pair, sweep::inputs},
+};
+
+const FEE: Fee = Fee { per_weight: 100, mask: 10000 };
+const RING_LEN: u64 = 16;
+const INPUTS: u64 = 3_000_000_000_000;
+
+// Create decoys for the specified inputs, with the other ring members being random
+fn decoys(inputs: &[SpendableOutput]) -> Vec<Decoys> {
+  inputs
+    .iter()
+    .map(|input| {
+      let real = OsRng.next_u64() % RING_LEN;
+      Decoys {
+        i: u8::try_from(real).unwrap(),
+        offsets: (1 ..= RING_LEN).collect(),
+        ring: (0 .. RING_LEN)
+          .map(|i| {
+            if i == real {
+              [input.key(), input.commitment().calculate()]
+            } else {
+              [
+                &random_scalar(&mut OsRng) * &ED25519_BASEPOINT_TABLE,
+                &random_scalar(&mut OsRng) * &ED25519_BASEPOINT_TABLE,
+              ]
+            }
+          })
+          .collect(),
+      }
+    })
+    .collect()
+}
+
+fn read(mut serialized: &[u8]) -> io::Result<UnsignedTransaction> {
+  UnsignedTransaction::read(&mut serialized)
+}
+
+#[test]
+fn unsigned_transaction() {
+  let inputs = inputs(&[1_000_000_000_000, 2_000_000_000_000]);
+  let dest = view_pair().address(Network::Mainnet, AddressSpec::Standard);
+  let r_seed = [0xff; 32];
+  // Without a change output, the payments must spend everything not used for the fee
+  let fee =
+    SignableTransaction::necessary_fee(Protocol::v16, 2, &[dest, dest], None, &[], FEE).unwrap();
+  let payments = vec![(dest, 5), (dest, INPUTS - fee - 5)];
+  let signable = SignableTransaction::new(
+    Protocol::v16,
+    Some(Zeroizing::new(r_seed)),
+    inputs.clone(),
+    payments.clone(),
+    None,
+    vec![],
+    FEE,
+  )
+  .unwrap();
+  let decoys = decoys(&inputs);
+
+  let unsigned = UnsignedTransaction::new(signable.clone(), decoys.clone());
+  assert_eq!(unsigned.payments(), payments);
+  assert_eq!(unsigned.fee(), fee);
+
+  let serialized = unsigned.serialize();
+  assert_eq!(read(&serialized).unwrap(), unsigned);
+  assert!(read(&serialized[.. (serialized.len() - 1)]).is_err());
+
+  // Every input needs its decoys
+  let missing_decoys = UnsignedTransaction::new(signable.clone(), decoys[.. 1].to_vec());
+  assert!(read(&missing_decoys.serialize()).is_err());
+  assert!(read(&UnsignedTransaction::new(signable.clone(), vec![]).serialize()).is_err());
+
+  // The payments are serialized after the protocol, r_seed, and inputs, and before the data, fee,
+  // and decoys
+  let mut prefix = vec![];
+  Protocol::v16.write(&mut prefix).unwrap();
+  prefix.extend(r_seed);
+  write_vec(SpendableOutput::write, &inputs, &mut prefix).unwrap();
+  // No data
+  let mut suffix = vec![0];
+  suffix.extend(fee.to_le_bytes());
+  write_vec(Decoys::write, &decoys, &mut suffix).unwrap();
+
+  let with_payments = |payments: &[&[u8]]| {
+    let mut serialized = prefix.clone();
+    write_varint(&u64::try_from(payments.len()).unwrap(), &mut serialized).unwrap();
+    for payment in payments {
+      serialized.extend(*payment);
+    }
+    serialized.extend(&suffix);
+    serialized
+  };
+  // Both payments are to the same address, so their serializations are the same length
+  let payments = &serialized[(prefix.len() + 1) .. (serialized.len() - suffix.len())];
+  let (payment, other) = payments.split_at(payments.len() / 2);
+  assert_eq!(with_payments(&[payment, other]), serialized);
+
+  // The payment's amount is its last 8 bytes
+  let with_amount = |amount: u64| {
+    let mut payment = payment.to_vec();
+    let amount_start = payment.len() - 8;
+    payment[amount_start ..].copy_from_slice(&amount.to_le_bytes());
+    payment
+  };
+  // A payment of the funds remaining after the specified amount of payments of 5
+  let remaining =
+    |payments: usize| with_amount(INPUTS - fee - (5 * u64::try_from(payments).unwrap()));
+
+  // There must be two payments, as there'd be with a change output, and no more than Monero allows
+  assert!(read(&with_payments(&[])).is_err());
+  assert!(read(&with_payments(&[&with_amount(INPUTS - fee)])).is_err());
+  let mut max = vec![payment; MAX_OUTPUTS - 1];
+  let last = remaining(MAX_OUTPUTS - 1);
+  max.push(&last);
+  assert!(read(&with_payments(&max)).is_ok());
+  let mut too_many = vec![payment; MAX_OUTPUTS];
+  let last = remaining(MAX_OUTPUTS);
+  too_many.push(&last);
+  assert!(read(&with_payments(&too_many)).is_err());
+
+  // The payments and fee must sum to the inputs, as the change output does
+  assert!(read(&with_payments(&[payment, &remaining(1)])).is_ok());
+  assert!(read(&with_payments(&[payment, &remaining(2)])).is_err());
+  assert!(read(&with_payments(&[payment, &with_amount(INPUTS - fee - 4)])).is_err());
+  // The payments also can't exceed the inputs
+  assert!(read(&with_payments(&[&with_amount(INPUTS), &with_amount(INPUTS)])).is_err());
+}
@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+
+use rand_core::OsRng;
+
+use curve25519_dalek::{constants::EIGHT_TORSION, edwards::EdwardsPoint};
+
+use multiexp::BatchVerifier;
+
+use crate::{
+  ringct::{RctType, RctPrunable, bulletproofs::Bulletproofs},
+  transaction::{Input, Transaction},
+  verify::{VerifyError, absolute_offsets, verify_transaction, verify_transactions},
+  tests::transaction::{signed_transaction, signed_transaction_with_proofs},
+};
+
+const TRANSACTIONS_JSON: &str = include_str!("vectors/transactions.json");
+
+fn vectors() -> Vec<Transaction> {
+  #[derive(serde::Deserialize)]
+  struct Vector {
+    hex: String,
+  }
+
+  serde_json::from_str::<Vec<Vector>>(TRANSACTIONS_JSON)
+    .unwrap()
+    .into_iter()
+    .map(|vector| {
+      Transaction::read::<&[u8]>(&mut hex::decode(vector.hex).unwrap().as_ref()).unwrap()
+    })
+    .collect()
+}
+
+// A signed transaction of the specified type, with a provider for its ring members
+fn transaction_with_provider(
+  rct_type: RctType,
+) -> (Transaction, HashMap<(u64, u64), [EdwardsPoint; 2]>) {
+  with_provider(signed_transaction(rct_type))
+}
+
+fn with_provider(
+  (tx, rings): (Transaction, Vec<Vec<[EdwardsPoint; 2]>>),
+) -> (Transaction, HashMap<(u64, u64), [EdwardsPoint; 2]>) {
+  let mut provider = HashMap::new();
+  for (input, ring) in tx.prefix.inputs.iter().zip(rings) {
+    let (amount, key_offsets) = match input {
+      Input::ToKey { amount, key_offsets, .. } => (amount, key_offsets),
+      Input::Gen(_) => panic!("signed a miner transaction"),
+    };
+    for (index, member) in absolute_offsets(key_offsets).unwrap().into_iter().zip(ring) {
+      provider.insert((*amount, index), member);
+    }
+  }
+  (tx, provider)
+}
+
+fn key_image(tx: &mut Transaction, i: usize) -> &mut EdwardsPoint {
+  match &mut tx.prefix.inputs[i] {
+    Input::ToKey { key_image, .. } => key_image,
+    Input::Gen(_) => panic!("signed a miner transaction"),
+  }
+}
+
+fn bulletproofs(tx: &mut Transaction) -> &mut Vec<Bulletproofs> {
+  match &mut tx.rct_signatures.prunable {
+    RctPrunable::MlsagBulletproofs { bulletproofs, .. } => bulletproofs,
+    _ => panic!("signed transaction didn't have the original Bulletproofs"),
+  }
+}
+
+#[tokio::test]
+async fn verify_without_ring_members() {
+  let txs = vectors();
+  let provider = HashMap::<(u64, u64), [EdwardsPoint; 2]>::new();
+
+  // Miner transactions aren't supported
+  assert_eq!(
+    verify_transactions(&mut OsRng, &provider, &txs[.. 1]).await,
+    Err((0, VerifyError::MinerTransaction))
+  );
+
+  // Without the ring members, the RingCT transaction can't be verified
+  let mut verifier = BatchVerifier::new(1);
+  let res = verify_transaction(&mut OsRng, &provider, &txs[1], &mut verifier, 0).await;
+  assert!(matches!(res, Err(VerifyError::MissingRingMember(_))));
+}
+
+#[tokio::test]
+async fn verify_with_ring_members() {
+  for rct_type in [
+    RctType::Null,
+    RctType::MlsagAggregate,
+    RctType::MlsagIndividual,
+    RctType::Bulletproofs,
+    RctType::BulletproofsCompactAmount,
+  ] {
+    let (tx, provider) = transaction_with_provider(rct_type);
+    verify_transactions(&mut OsRng, &provider, &[tx.clone()]).await.unwrap();
+
+    // The ring members must be for the input's amount, which is only non-zero for version 1
+    let other_amount = provider
+      .iter()
+      .map(|((amount, index), member)| ((amount + 1, *index), *member))
+      .collect::<HashMap<_, _>>();
+    assert!(matches!(
+      verify_transactions(&mut OsRng, &other_amount, &[tx]).await,
+      Err((0, VerifyError::MissingRingMember(_)))
+    ));
+  }
+}
+
+#[tokio::test]
+async fn verify_unbalanced() {
+  // Version 1 outputs may not exceed the inputs
+  let (mut tx, provider) = transaction_with_provider(RctType::Null);
+  tx.prefix.outputs[0].amount += tx.rct_signatures.base.fee + 1;
+  assert_eq!(
+    verify_transactions(&mut OsRng, &provider, &[tx]).await,
+    Err((0, VerifyError::Unbalanced))
+  );
+
+  // RingCT pseudo-outputs must equal the outputs and the fee
+  for rct_type in [RctType::MlsagIndividual, RctType::Bulletproofs] {
+    let (mut tx, provider) = transaction_with_provider(rct_type);
+    tx.rct_signatures.base.fee += 1;
+    assert_eq!(
+      verify_transactions(&mut OsRng, &provider, &[tx]).await,
+      Err((0, VerifyError::Unbalanced))
+    );
+  }
+
+  // The aggregate MLSAG proves the balance itself
+  let (mut tx, provider) = transaction_with_provider(RctType::MlsagAggregate);
+  tx.rct_signatures.base.fee += 1;
+  assert!(matches!(
+    verify_transactions(&mut OsRng, &provider, &[tx]).await,
+    Err((0, VerifyError::InvalidMlsag(_)))
+  ));
+}
+
+#[tokio::test]
+async fn verify_invalid_key_images() {
+  for rct_type in [RctType::Null, RctType::BulletproofsCompactAmount] {
+    // A key image with a torsion component could be used to spend an output multiple times
+    let (mut tx, provider) = transaction_with_provider(rct_type);
+    *key_image(&mut tx, 0) += EIGHT_TORSION[1];
+    assert_eq!(
+      verify_transactions(&mut OsRng, &provider, &[tx]).await,
+      Err((0, VerifyError::InvalidKeyImage))
+    );
+
+    let (mut tx, provider) = transaction_with_provider(rct_type);
+    let image = *key_image(&mut tx, 0);
+    *key_image(&mut tx, 1) = image;
+    assert_eq!(
+      verify_transactions(&mut OsRng, &provider, &[tx]).await,
+      Err((0, VerifyError::DuplicateKeyImage))
+    );
+
+    // Key images may not be spent multiple times within a batch either
+    let (tx, provider) = transaction_with_provider(rct_type);
+    assert_eq!(
+      verify_transactions(&mut OsRng, &provider, &[tx.clone(), tx]).await,
+      Err((1, VerifyError::DuplicateKeyImage))
+    );
+  }
+}
+
+#[tokio::test]
+async fn verify_invalid_ring_signature() {
+  let (mut tx, provider) = transaction_with_provider(RctType::Null);
+  tx.signatures.swap(0, 1);
+  assert_eq!(
+    verify_transactions(&mut OsRng, &provider, &[tx]).await,
+    Err((0, VerifyError::InvalidRingSignature))
+  );
+}
+
+#[tokio::test]
+async fn verify_split_bulletproofs() {
+  // The original Bulletproofs type allows any split of the outputs across proofs
+  for proofs in [&[1, 1, 1][..], &[2, 1], &[1, 2], &[3], &[2, 2], &[1, 3]] {
+    let (tx, provider) =
+      with_provider(signed_transaction_with_proofs(RctType::Bulletproofs, proofs));
+    verify_transactions(&mut OsRng, &provider, &[tx]).await.unwrap();
+  }
+
+  // The proofs must prove every output
+  let (mut tx, provider) = transaction_with_provider(RctType::Bulletproofs);
+  bulletproofs(&mut tx).pop();
+  assert_eq!(
+    verify_transactions(&mut OsRng, &provider, &[tx]).await,
+    Err((0, VerifyError::InvalidAmountOfRangeProofs))
+  );
+
+  // And may not outnumber the outputs
+  let (mut tx, provider) = transaction_with_provider(RctType::Bulletproofs);
+  let proof = bulletproofs(&mut tx)[0].clone();
+  bulletproofs(&mut tx).push(proof);
+  assert_eq!(
+    verify_transactions(&mut OsRng, &provider, &[tx]).await,
+    Err((0, VerifyError::InvalidAmountOfRangeProofs))
+  );
+}
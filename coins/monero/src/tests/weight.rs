@@ -0,0 +1,158 @@
+use crate::{
+  Protocol,
+  transaction::Transaction,
+  ringct::{bulletproofs::Bulletproofs, RctType, RctPrunable},
+  wallet::{
+    address::{Network, SubaddressIndex, AddressSpec, MoneroAddress},
+    extra::MAX_ARBITRARY_DATA_SIZE,
+    Fee, Change, TransactionError, SignableTransaction,
+  },
+  tests::{scanner::view_pair, sweep::inputs, transaction::signed_transaction},
+};
+
+const FEE: Fee = Fee { per_weight: 100, mask: 10000 };
+
+fn subaddress() -> MoneroAddress {
+  view_pair()
+    .address(Network::Mainnet, AddressSpec::Subaddress(SubaddressIndex::new(0, 1).unwrap()))
+}
+
+#[test]
+fn bulletproofs_clawback() {
+  // Proofs for up to two outputs have no clawback
+  assert_eq!(Bulletproofs::calculate_clawback(true, 1), (0, 6));
+  assert_eq!(Bulletproofs::calculate_clawback(true, 2), (0, 7));
+  // Non-power-of-two amounts of outputs are padded
+  assert_eq!(Bulletproofs::calculate_clawback(true, 3), (460, 8));
+  assert_eq!(Bulletproofs::calculate_clawback(true, 4), (460, 8));
+  assert_eq!(Bulletproofs::calculate_clawback(true, 16), (3430, 10));
+  assert_eq!(Bulletproofs::calculate_clawback(false, 16), (3968, 10));
+}
+
+fn bulletproofs(tx: &mut Transaction) -> &mut Vec<Bulletproofs> {
+  match &mut tx.rct_signatures.prunable {
+    RctPrunable::MlsagBulletproofs { bulletproofs, .. } => bulletproofs,
+    _ => panic!("transaction didn't have MLSAGs with Bulletproofs"),
+  }
+}
+
+#[test]
+fn transaction_weight() {
+  // A single aggregate proof for two outputs has no clawback
+  let (tx, _) = signed_transaction(RctType::BulletproofsCompactAmount);
+  assert_eq!(tx.weight(), tx.serialize().len());
+
+  // RctType::Bulletproofs transactions may have a proof per output, whose padded outputs are
+  // summed
+  let (mut tx, _) = signed_transaction(RctType::Bulletproofs);
+  assert_eq!(bulletproofs(&mut tx).len(), 2);
+  assert_eq!(tx.weight(), tx.serialize().len());
+
+  // Three padded outputs aren't padded to four, as they would be by a single proof
+  let proof = bulletproofs(&mut tx)[0].clone();
+  bulletproofs(&mut tx).push(proof.clone());
+  assert_eq!(Bulletproofs::clawback(false, 3), 243);
+  assert_eq!(tx.weight(), tx.serialize().len() + 243);
+
+  bulletproofs(&mut tx).push(proof);
+  assert_eq!(tx.weight(), tx.serialize().len() + Bulletproofs::calculate_clawback(false, 4).0);
+
+  // No weight is clawed back if any proof has an invalid length
+  match &mut bulletproofs(&mut tx)[0] {
+    Bulletproofs::Original(bp) => bp.L.truncate(5),
+    Bulletproofs::Plus(_) => panic!("Bulletproofs transaction had a Bulletproof+"),
+  }
+  assert_eq!(tx.weight(), tx.serialize().len());
+}
+
+// Build a transaction with the most outputs possible, which also requires additional keys, and the
+// specified amount of maximally sized data fields
+fn transaction_with_data(len: usize, data: usize) -> Result<SignableTransaction, TransactionError> {
+  SignableTransaction::new(
+    Protocol::v16,
+    None,
+    inputs(&vec![1_000_000_000_000; len]),
+    (0 .. 15).map(|_| (subaddress(), 1)).collect(),
+    Some(Change::fingerprintable(subaddress())),
+    vec![vec![0; MAX_ARBITRARY_DATA_SIZE]; data],
+    FEE,
+  )
+}
+
+// Build a transaction with the most outputs and data possible
+fn largest_transaction(len: usize) -> Result<SignableTransaction, TransactionError> {
+  transaction_with_data(len, 1)
+}
+
+#[test]
+fn max_inputs() {
+  // The processor spends up to 120 inputs per transaction, which must fit regardless of the
+  // outputs and data
+  let tx = largest_transaction(120).unwrap();
+  assert!(tx.weight() < 100_000);
+  assert_eq!(tx.fee(), FEE.calculate(tx.weight()));
+  assert_eq!(
+    SignableTransaction::necessary_fee(
+      Protocol::v16,
+      120,
+      &(0 .. 15).map(|_| subaddress()).collect::<Vec<_>>(),
+      Some(&Change::fingerprintable(subaddress())),
+      &[vec![0; MAX_ARBITRARY_DATA_SIZE]],
+      FEE,
+    )
+    .unwrap(),
+    tx.fee()
+  );
+
+  // There's room for a single additional input
+  assert!(largest_transaction(121).is_ok());
+  match largest_transaction(122) {
+    Err(TransactionError::TooLargeTransaction(weight)) => assert!(weight >= 100_000),
+    _ => panic!("created a transaction exceeding the size limit"),
+  }
+}
+
+#[test]
+fn max_data() {
+  // With 16 outputs, TX extra only has room for a single maximally sized data field, which the
+  // processor's MAX_DATA_PAYMENTS for Monero relies on
+  assert!(transaction_with_data(1, 1).is_ok());
+  assert!(matches!(transaction_with_data(1, 2), Err(TransactionError::TooMuchData)));
+}
+
+#[test]
+fn necessary_fee() {
+  let view = view_pair();
+  let change = Change::new(&view, false);
+  let fee = |inputs, payments: &[MoneroAddress], change: Option<&Change>| {
+    SignableTransaction::necessary_fee(Protocol::v16, inputs, payments, change, &[], FEE)
+  };
+
+  // The fee is the same as the one calculated when actually creating the transaction
+  let standard = view.address(Network::Mainnet, AddressSpec::Standard);
+  let tx = SignableTransaction::new(
+    Protocol::v16,
+    None,
+    inputs(&[1_000_000_000_000, 1_000_000_000_000]),
+    vec![(standard, 5)],
+    Some(change.clone()),
+    vec![],
+    FEE,
+  )
+  .unwrap();
+  assert_eq!(fee(2, &[standard], Some(&change)).unwrap(), tx.fee());
+
+  // A subaddress requires additional keys, unless the only other output is change whose view key
+  // is known
+  let fingerprintable =
+    Change::fingerprintable(view_pair().address(Network::Mainnet, AddressSpec::Standard));
+  assert_eq!(fee(2, &[subaddress()], Some(&change)), fee(2, &[standard], Some(&change)));
+  assert!(
+    fee(2, &[subaddress()], Some(&fingerprintable)).unwrap() >
+      fee(2, &[standard], Some(&fingerprintable)).unwrap()
+  );
+
+  assert_eq!(fee(0, &[standard], Some(&change)), Err(TransactionError::NoInputs));
+  assert_eq!(fee(2, &[], Some(&change)), Err(TransactionError::NoOutputs));
+  assert_eq!(fee(2, &[standard], None), Err(TransactionError::NoChange));
+}
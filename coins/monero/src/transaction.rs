@@ -3,15 +3,13 @@ use std::io::{self, Read, Write};
 
 use zeroize::Zeroize;
 
-use curve25519_dalek::{
-  scalar::Scalar,
-  edwards::{EdwardsPoint, CompressedEdwardsY},
-};
+use curve25519_dalek::edwards::{EdwardsPoint, CompressedEdwardsY};
 
 use crate::{
   Protocol, hash,
   serialize::*,
-  ringct::{RctBase, RctPrunable, RctSignatures},
+  ring_signatures::RingSignature,
+  ringct::{bulletproofs::Bulletproofs, RctType, RctBase, RctPrunable, RctSignatures},
 };
 
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -22,6 +20,8 @@ pub enum Input {
 
 impl Input {
   // Worst-case predictive len
+  // This is exact except for the key offsets, whose lengths aren't known until the decoys are
+  // selected. 8 bytes are assumed for each, enough for any offset under 2**56
   pub(crate) fn fee_weight(ring_len: usize) -> usize {
     // Uses 1 byte for the VarInt amount due to amount being 0
     // Uses 1 byte for the VarInt encoding of the length of the ring as well
@@ -74,8 +74,8 @@ pub struct Output {
 }
 
 impl Output {
-  pub(crate) fn fee_weight() -> usize {
-    1 + 1 + 32 + 1
+  pub(crate) fn fee_weight(view_tags: bool) -> usize {
+    1 + 1 + 32 + usize::from(view_tags)
   }
 
   pub fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
@@ -164,13 +164,18 @@ pub struct TransactionPrefix {
 }
 
 impl TransactionPrefix {
-  pub(crate) fn fee_weight(ring_len: usize, inputs: usize, outputs: usize, extra: usize) -> usize {
+  pub(crate) fn fee_weight(
+    protocol: Protocol,
+    inputs: usize,
+    outputs: usize,
+    extra: usize,
+  ) -> usize {
     // Assumes Timelock::None since this library won't let you create a TX with a timelock
     1 + 1 +
       varint_len(inputs) +
-      (inputs * Input::fee_weight(ring_len)) +
-      1 +
-      (outputs * Output::fee_weight()) +
+      (inputs * Input::fee_weight(protocol.ring_len())) +
+      varint_len(outputs) +
+      (outputs * Output::fee_weight(protocol.view_tags())) +
       varint_len(extra) +
       extra
   }
@@ -207,7 +212,8 @@ impl TransactionPrefix {
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct Transaction {
   pub prefix: TransactionPrefix,
-  pub signatures: Vec<(Scalar, Scalar)>,
+  /// The ring signatures of a version 1 transaction, one per non-Gen input.
+  pub signatures: Vec<RingSignature>,
   pub rct_signatures: RctSignatures,
 }
 
@@ -217,17 +223,17 @@ impl Transaction {
     inputs: usize,
     outputs: usize,
     extra: usize,
+    fee: u64,
   ) -> usize {
-    TransactionPrefix::fee_weight(protocol.ring_len(), inputs, outputs, extra) +
-      RctSignatures::fee_weight(protocol, inputs, outputs)
+    TransactionPrefix::fee_weight(protocol, inputs, outputs, extra) +
+      RctSignatures::fee_weight(protocol, inputs, outputs, fee)
   }
 
   pub fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
     self.prefix.write(w)?;
     if self.prefix.version == 1 {
       for sig in &self.signatures {
-        write_scalar(&sig.0, w)?;
-        write_scalar(&sig.1, w)?;
+        sig.write(w)?;
       }
       Ok(())
     } else if self.prefix.version == 2 {
@@ -247,13 +253,15 @@ impl Transaction {
     let prefix = TransactionPrefix::read(r)?;
     let mut signatures = vec![];
     let mut rct_signatures = RctSignatures {
-      base: RctBase { fee: 0, ecdh_info: vec![], commitments: vec![] },
+      base: RctBase { fee: 0, pseudo_outs: vec![], ecdh_info: vec![], commitments: vec![] },
       prunable: RctPrunable::Null,
     };
 
     if prefix.version == 1 {
-      for _ in 0 .. prefix.inputs.len() {
-        signatures.push((read_scalar(r)?, read_scalar(r)?));
+      for input in &prefix.inputs {
+        if let Input::ToKey { key_offsets, .. } = input {
+          signatures.push(RingSignature::read(key_offsets.len(), r)?);
+        }
       }
       rct_signatures.base.fee = prefix
         .inputs
@@ -296,14 +304,15 @@ impl Transaction {
       hashes.extend(hash(&buf));
       buf.clear();
 
-      self.rct_signatures.base.write(&mut buf, self.rct_signatures.prunable.rct_type()).unwrap();
+      let rct_type = self.rct_signatures.rct_type();
+      self.rct_signatures.base.write(&mut buf, rct_type).unwrap();
       hashes.extend(hash(&buf));
       buf.clear();
 
       match self.rct_signatures.prunable {
         RctPrunable::Null => buf.resize(32, 0),
         _ => {
-          self.rct_signatures.prunable.write(&mut buf).unwrap();
+          self.rct_signatures.prunable.write(&mut buf, rct_type).unwrap();
           buf = hash(&buf).to_vec();
         }
       }
@@ -313,16 +322,47 @@ impl Transaction {
     }
   }
 
+  /// The weight of this transaction, which its fee is paid for and which is limited by the
+  /// network.
+  ///
+  /// This is the length of its serialization, plus the clawback for its Bulletproofs. Since a
+  /// Bulletproof's verification time is linear to the amount of outputs proven, yet its size is
+  /// logarithmic, transactions with more than two padded outputs are charged as if they had one
+  /// proof per two outputs. As `RctType::Bulletproofs` transactions may have a proof per output,
+  /// the padded outputs of every proof are summed.
+  pub fn weight(&self) -> usize {
+    let blob_size = self.serialize().len();
+    let bp_plus = match self.rct_signatures.rct_type() {
+      RctType::Bulletproofs | RctType::BulletproofsCompactAmount | RctType::Clsag => false,
+      RctType::BulletproofsPlus => true,
+      _ => return blob_size,
+    };
+    let bulletproofs = match &self.rct_signatures.prunable {
+      RctPrunable::MlsagBulletproofs { bulletproofs, .. } |
+      RctPrunable::Clsag { bulletproofs, .. } => bulletproofs,
+      _ => return blob_size,
+    };
+    // Monero doesn't claw back any weight if any proof has an invalid length
+    let padded_outputs =
+      bulletproofs.iter().map(Bulletproofs::padded_outputs).sum::<Option<usize>>().unwrap_or(0);
+    blob_size + Bulletproofs::clawback(bp_plus, padded_outputs)
+  }
+
   /// Calculate the hash of this transaction as needed for signing it.
+  ///
+  /// For version 1 transactions, this is solely the hash of the prefix.
   pub fn signature_hash(&self) -> [u8; 32] {
     let mut buf = Vec::with_capacity(2048);
     let mut sig_hash = Vec::with_capacity(96);
 
     self.prefix.write(&mut buf).unwrap();
+    if self.prefix.version == 1 {
+      return hash(&buf);
+    }
     sig_hash.extend(hash(&buf));
     buf.clear();
 
-    self.rct_signatures.base.write(&mut buf, self.rct_signatures.prunable.rct_type()).unwrap();
+    self.rct_signatures.base.write(&mut buf, self.rct_signatures.rct_type()).unwrap();
     sig_hash.extend(hash(&buf));
     buf.clear();
 
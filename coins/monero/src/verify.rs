@@ -0,0 +1,381 @@
+use std::collections::{HashSet, HashMap};
+
+use thiserror::Error;
+
+use async_trait::async_trait;
+
+use zeroize::Zeroize;
+
+use rand_core::{RngCore, CryptoRng};
+
+use curve25519_dalek::{scalar::Scalar, traits::IsIdentity, edwards::EdwardsPoint};
+
+use multiexp::BatchVerifier;
+
+use crate::{
+  H,
+  ringct::{
+    RctType, RctPrunable,
+    mlsag::{MlsagError, RingMatrix},
+    clsag::ClsagError,
+    bulletproofs::Bulletproofs,
+  },
+  transaction::{Input, Transaction},
+  rpc::{RpcError, RpcConnection, Rpc},
+};
+
+/// Errors returned when verifying a transaction.
+#[derive(Clone, PartialEq, Eq, Debug, Error)]
+pub enum VerifyError {
+  #[error("unsupported transaction version")]
+  UnsupportedVersion,
+  #[error("miner transaction")]
+  MinerTransaction,
+  #[error("invalid input")]
+  InvalidInput,
+  #[error("invalid key image")]
+  InvalidKeyImage,
+  #[error("duplicate key image")]
+  DuplicateKeyImage,
+  #[error("invalid output")]
+  InvalidOutput,
+  #[error("missing ring member ({0})")]
+  MissingRingMember(u64),
+  #[error("invalid ring")]
+  InvalidRing,
+  #[error("invalid ring signature")]
+  InvalidRingSignature,
+  #[error("invalid amount of ring signatures")]
+  InvalidAmountOfSignatures,
+  #[error("invalid amount of range proofs")]
+  InvalidAmountOfRangeProofs,
+  #[error("invalid range proof")]
+  InvalidRangeProof,
+  #[error("unbalanced transaction")]
+  Unbalanced,
+  #[error("invalid CLSAG ({0})")]
+  InvalidClsag(ClsagError),
+  #[error("invalid MLSAG ({0})")]
+  InvalidMlsag(MlsagError),
+  #[error("rpc error ({0})")]
+  RpcError(RpcError),
+}
+
+/// A provider of ring members, being the key and commitment of outputs by their index within the
+/// pool of outputs with their amount.
+///
+/// RingCT inputs spend from the zero-amount pool. Version 1 inputs spend from the pool for their
+/// amount, whose outputs have the commitment Monero assigns transparent amounts, with a mask of
+/// one.
+#[async_trait]
+pub trait RingProvider: Sync {
+  /// Get the specified ring members, in order.
+  async fn ring_members(
+    &self,
+    amount: u64,
+    indexes: &[u64],
+  ) -> Result<Vec<[EdwardsPoint; 2]>, VerifyError>;
+}
+
+#[async_trait]
+impl<R: RpcConnection> RingProvider for Rpc<R> {
+  async fn ring_members(
+    &self,
+    amount: u64,
+    indexes: &[u64],
+  ) -> Result<Vec<[EdwardsPoint; 2]>, VerifyError> {
+    self.get_outputs_of_amount(amount, indexes).await.map_err(VerifyError::RpcError)
+  }
+}
+
+/// Ring members supplied by the caller, keyed by their amount and index.
+#[async_trait]
+impl RingProvider for HashMap<(u64, u64), [EdwardsPoint; 2]> {
+  async fn ring_members(
+    &self,
+    amount: u64,
+    indexes: &[u64],
+  ) -> Result<Vec<[EdwardsPoint; 2]>, VerifyError> {
+    indexes
+      .iter()
+      .map(|index| {
+        self.get(&(amount, *index)).copied().ok_or(VerifyError::MissingRingMember(*index))
+      })
+      .collect()
+  }
+}
+
+// Key offsets are relative to the prior offset, with the first being absolute
+pub(crate) fn absolute_offsets(offsets: &[u64]) -> Result<Vec<u64>, VerifyError> {
+  let mut res = Vec::with_capacity(offsets.len());
+  let mut sum = 0u64;
+  for offset in offsets {
+    sum = sum.checked_add(*offset).ok_or(VerifyError::InvalidInput)?;
+    res.push(sum);
+  }
+  Ok(res)
+}
+
+/// Verify a transaction's ring signatures, key images, and balance, queueing its Bulletproofs
+/// into the provided BatchVerifier under the specified ID.
+///
+/// The Bulletproofs are only verified once the BatchVerifier is, which is left to the caller. This
+/// allows verifying the range proofs of many transactions at once. Ring signatures, MLSAGs, CLSAGs,
+/// and Borromean range proofs can't be batch verified, and are immediately verified.
+///
+/// Version 1 transactions have their ring signatures verified, and their outputs checked to not
+/// exceed their inputs, with the difference being the fee. Miner transactions are rejected, as
+/// their validity depends on the block they're in.
+pub async fn verify_transaction<R: RngCore + CryptoRng, P: RingProvider, ID: Copy + Zeroize>(
+  rng: &mut R,
+  provider: &P,
+  tx: &Transaction,
+  verifier: &mut BatchVerifier<ID, dalek_ff_group::EdwardsPoint>,
+  id: ID,
+) -> Result<(), VerifyError> {
+  let version_1 = match tx.prefix.version {
+    1 => true,
+    2 => false,
+    _ => Err(VerifyError::UnsupportedVersion)?,
+  };
+  if (!version_1) && matches!(tx.rct_signatures.prunable, RctPrunable::Null) {
+    Err(VerifyError::MinerTransaction)?;
+  }
+
+  let mut amounts = Vec::with_capacity(tx.prefix.inputs.len());
+  let mut key_images = Vec::with_capacity(tx.prefix.inputs.len());
+  let mut offsets = Vec::with_capacity(tx.prefix.inputs.len());
+  for input in &tx.prefix.inputs {
+    match input {
+      Input::Gen(_) => Err(VerifyError::MinerTransaction)?,
+      Input::ToKey { amount, key_offsets, key_image } => {
+        // Version 1 inputs have transparent amounts, while RingCT inputs hide them
+        if ((*amount == 0) == version_1) || key_offsets.is_empty() {
+          Err(VerifyError::InvalidInput)?;
+        }
+        if key_image.is_identity() || (!key_image.is_torsion_free()) {
+          Err(VerifyError::InvalidKeyImage)?;
+        }
+        amounts.push(*amount);
+        key_images.push(*key_image);
+        offsets.push(absolute_offsets(key_offsets)?);
+      }
+    }
+  }
+  if key_images.is_empty() {
+    Err(VerifyError::InvalidInput)?;
+  }
+  if key_images.iter().map(|image| image.compress().to_bytes()).collect::<HashSet<_>>().len() !=
+    key_images.len()
+  {
+    Err(VerifyError::DuplicateKeyImage)?;
+  }
+
+  let msg = tx.signature_hash();
+
+  if version_1 {
+    if tx.prefix.outputs.is_empty() || tx.prefix.outputs.iter().any(|output| output.amount == 0) {
+      Err(VerifyError::InvalidOutput)?;
+    }
+
+    // The outputs may not exceed the inputs, with the difference being the fee
+    let inputs = amounts
+      .iter()
+      .try_fold(0u64, |sum, amount| sum.checked_add(*amount))
+      .ok_or(VerifyError::InvalidInput)?;
+    let outputs = tx
+      .prefix
+      .outputs
+      .iter()
+      .try_fold(0u64, |sum, output| sum.checked_add(output.amount))
+      .ok_or(VerifyError::InvalidOutput)?;
+    if outputs > inputs {
+      Err(VerifyError::Unbalanced)?;
+    }
+
+    if tx.signatures.len() != key_images.len() {
+      Err(VerifyError::InvalidAmountOfSignatures)?;
+    }
+    // Each input spends from the pool for its amount, so each ring is fetched individually
+    for (((signature, amount), offsets), image) in
+      tx.signatures.iter().zip(&amounts).zip(&offsets).zip(&key_images)
+    {
+      let ring = provider.ring_members(*amount, offsets).await?;
+      if ring.len() != offsets.len() {
+        Err(VerifyError::InvalidRing)?;
+      }
+      let ring = ring.iter().map(|member| member[0]).collect::<Vec<_>>();
+      if !signature.verify(&msg, &ring, image) {
+        Err(VerifyError::InvalidRingSignature)?;
+      }
+    }
+
+    return Ok(());
+  }
+
+  let base = &tx.rct_signatures.base;
+  if tx.prefix.outputs.is_empty() ||
+    tx.prefix.outputs.iter().any(|output| output.amount != 0) ||
+    (base.commitments.len() != tx.prefix.outputs.len()) ||
+    (base.ecdh_info.len() != tx.prefix.outputs.len())
+  {
+    Err(VerifyError::InvalidOutput)?;
+  }
+
+  // Fetch every ring at once
+  let indexes = offsets.concat();
+  let members = provider.ring_members(0, &indexes).await?;
+  if members.len() != indexes.len() {
+    Err(VerifyError::InvalidRing)?;
+  }
+  let mut members = members.into_iter();
+  let rings = offsets
+    .iter()
+    .map(|offsets| members.by_ref().take(offsets.len()).collect::<Vec<_>>())
+    .collect::<Vec<_>>();
+
+  let balances = |pseudo_outs: &[EdwardsPoint]| {
+    pseudo_outs.iter().sum::<EdwardsPoint>() ==
+      (base.commitments.iter().sum::<EdwardsPoint>() + (Scalar::from(base.fee) * *H))
+  };
+  let mut batch_verify = |bulletproofs: &[Bulletproofs]| -> Result<(), VerifyError> {
+    // The original Bulletproofs type allowed any amount of proofs, so long as the outputs they
+    // prove sum to the transaction's outputs, yet every later type requires a single aggregate
+    // proof
+    if bulletproofs.is_empty() ||
+      (bulletproofs.len() > base.commitments.len()) ||
+      ((tx.rct_signatures.rct_type() != RctType::Bulletproofs) && (bulletproofs.len() != 1))
+    {
+      Err(VerifyError::InvalidAmountOfRangeProofs)?;
+    }
+
+    // Assign the commitments to each proof in order
+    // Each proof proves as many outputs as it was padded to, so long as every later proof is left
+    // at least one, and the final proof must prove every remaining output
+    let mut commitments = base.commitments.as_slice();
+    for (i, bulletproof) in bulletproofs.iter().enumerate() {
+      let padded_outputs = bulletproof.padded_outputs().ok_or(VerifyError::InvalidRangeProof)?;
+      let later = bulletproofs.len() - (i + 1);
+      let proven = if later == 0 {
+        commitments.len()
+      } else {
+        padded_outputs.min(commitments.len() - later)
+      };
+      if proven > padded_outputs {
+        Err(VerifyError::InvalidAmountOfRangeProofs)?;
+      }
+
+      let (these, rest) = commitments.split_at(proven);
+      if !bulletproof.batch_verify(rng, verifier, id, these) {
+        Err(VerifyError::InvalidRangeProof)?;
+      }
+      commitments = rest;
+    }
+    Ok(())
+  };
+
+  match &tx.rct_signatures.prunable {
+    RctPrunable::Null => unreachable!("verifying a miner transaction"),
+
+    RctPrunable::AggregateMlsagBorromean { borromean, mlsag } => {
+      if borromean.len() != base.commitments.len() {
+        Err(VerifyError::InvalidAmountOfRangeProofs)?;
+      }
+      for (range, commitment) in borromean.iter().zip(&base.commitments) {
+        if !range.verify(commitment) {
+          Err(VerifyError::InvalidRangeProof)?;
+        }
+      }
+
+      // The balance is proven by the MLSAG itself
+      let ring = RingMatrix::aggregate(&rings, &base.commitments, base.fee)
+        .map_err(VerifyError::InvalidMlsag)?;
+      mlsag.verify(&msg, &ring, &key_images).map_err(VerifyError::InvalidMlsag)?;
+    }
+
+    RctPrunable::MlsagBorromean { borromean, mlsags } => {
+      if borromean.len() != base.commitments.len() {
+        Err(VerifyError::InvalidAmountOfRangeProofs)?;
+      }
+      for (range, commitment) in borromean.iter().zip(&base.commitments) {
+        if !range.verify(commitment) {
+          Err(VerifyError::InvalidRangeProof)?;
+        }
+      }
+
+      if (mlsags.len() != rings.len()) || (base.pseudo_outs.len() != rings.len()) {
+        Err(VerifyError::InvalidAmountOfSignatures)?;
+      }
+      if !balances(&base.pseudo_outs) {
+        Err(VerifyError::Unbalanced)?;
+      }
+      for (((mlsag, ring), pseudo_out), image) in
+        mlsags.iter().zip(&rings).zip(&base.pseudo_outs).zip(&key_images)
+      {
+        let ring = RingMatrix::individual(ring, *pseudo_out).map_err(VerifyError::InvalidMlsag)?;
+        mlsag.verify(&msg, &ring, &[*image]).map_err(VerifyError::InvalidMlsag)?;
+      }
+    }
+
+    RctPrunable::MlsagBulletproofs { bulletproofs, mlsags, pseudo_outs } => {
+      batch_verify(bulletproofs)?;
+
+      if (mlsags.len() != rings.len()) || (pseudo_outs.len() != rings.len()) {
+        Err(VerifyError::InvalidAmountOfSignatures)?;
+      }
+      if !balances(pseudo_outs) {
+        Err(VerifyError::Unbalanced)?;
+      }
+      for (((mlsag, ring), pseudo_out), image) in
+        mlsags.iter().zip(&rings).zip(pseudo_outs).zip(&key_images)
+      {
+        let ring = RingMatrix::individual(ring, *pseudo_out).map_err(VerifyError::InvalidMlsag)?;
+        mlsag.verify(&msg, &ring, &[*image]).map_err(VerifyError::InvalidMlsag)?;
+      }
+    }
+
+    RctPrunable::Clsag { bulletproofs, clsags, pseudo_outs } => {
+      batch_verify(bulletproofs)?;
+
+      if (clsags.len() != rings.len()) || (pseudo_outs.len() != rings.len()) {
+        Err(VerifyError::InvalidAmountOfSignatures)?;
+      }
+      if !balances(pseudo_outs) {
+        Err(VerifyError::Unbalanced)?;
+      }
+      for (((clsag, ring), pseudo_out), image) in
+        clsags.iter().zip(&rings).zip(pseudo_outs).zip(&key_images)
+      {
+        clsag.verify(ring, image, pseudo_out, &msg).map_err(VerifyError::InvalidClsag)?;
+      }
+    }
+  }
+
+  Ok(())
+}
+
+/// Verify a batch of transactions, returning the index of the first invalid transaction found
+/// alongside the reason it's invalid.
+///
+/// As with the transactions within a block, no two transactions in the batch may spend the same
+/// key image.
+pub async fn verify_transactions<R: RngCore + CryptoRng, P: RingProvider>(
+  rng: &mut R,
+  provider: &P,
+  txs: &[Transaction],
+) -> Result<(), (usize, VerifyError)> {
+  let mut verifier = BatchVerifier::new(txs.len());
+  let mut key_images = HashSet::new();
+  for (i, tx) in txs.iter().enumerate() {
+    verify_transaction(rng, provider, tx, &mut verifier, i).await.map_err(|e| (i, e))?;
+
+    for input in &tx.prefix.inputs {
+      if let Input::ToKey { key_image, .. } = input {
+        if !key_images.insert(key_image.compress().to_bytes()) {
+          Err((i, VerifyError::DuplicateKeyImage))?;
+        }
+      }
+    }
+  }
+  verifier.verify_vartime_with_vartime_blame().map_err(|i| (i, VerifyError::InvalidRangeProof))
+}
@@ -1,10 +1,12 @@
-use std::collections::HashSet;
-
-use futures::lock::{Mutex, MutexGuard};
+use std::{
+  io::{self, Read, Write},
+  collections::HashSet,
+};
 
 use lazy_static::lazy_static;
 
-use rand_core::{RngCore, CryptoRng};
+use rand_core::{RngCore, CryptoRng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
 use rand_distr::{Distribution, Gamma};
 
 use zeroize::{Zeroize, ZeroizeOnDrop};
@@ -12,8 +14,9 @@ use zeroize::{Zeroize, ZeroizeOnDrop};
 use curve25519_dalek::edwards::EdwardsPoint;
 
 use crate::{
+  serialize::{read_byte, read_varint, read_point, read_vec, write_varint, write_point, write_vec},
   wallet::SpendableOutput,
-  rpc::{RpcError, Rpc},
+  rpc::{RpcError, RpcConnection, Rpc},
 };
 
 const LOCK_WINDOW: usize = 10;
@@ -25,16 +28,103 @@ const TIP_APPLICATION: f64 = (LOCK_WINDOW * BLOCK_TIME) as f64;
 
 lazy_static! {
   static ref GAMMA: Gamma<f64> = Gamma::new(19.28, 1.0 / 1.61).unwrap();
-  // TODO: Expose an API to reset this in case a reorg occurs/the RPC fails/returns garbage
-  // TODO: Update this when scanning a block, as possible
-  static ref DISTRIBUTION: Mutex<Vec<u64>> = Mutex::new(Vec::with_capacity(3000000));
+}
+
+/// A cache of the RingCT output distribution, being the cumulative amount of RingCT outputs as of
+/// each block.
+///
+/// Every `Rpc` has its own cache, which is refreshed as needed when selecting decoys. If blocks
+/// are reorganized, the cache should be rolled back to before them.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct OutputDistribution {
+  distribution: Vec<u64>,
+}
+
+impl OutputDistribution {
+  pub fn new() -> OutputDistribution {
+    OutputDistribution::default()
+  }
+
+  /// The amount of blocks whose distribution is cached.
+  pub fn len(&self) -> usize {
+    self.distribution.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.distribution.is_empty()
+  }
+
+  /// The cumulative amount of RingCT outputs as of each cached block.
+  pub fn distribution(&self) -> &[u64] {
+    &self.distribution
+  }
+
+  /// Remove the distribution for every block at or after the specified height.
+  pub fn rollback(&mut self, height: usize) {
+    self.distribution.truncate(height);
+  }
+
+  /// A snapshot of the distribution up to, and including, the specified height. Returns None if
+  /// the height isn't cached.
+  pub fn snapshot(&self, height: usize) -> Option<OutputDistribution> {
+    self
+      .distribution
+      .get(..= height)
+      .map(|distribution| OutputDistribution { distribution: distribution.to_vec() })
+  }
+
+  /// Refresh the cache to include every block up to, and including, the specified height.
+  ///
+  /// The last cached block is requested again. If its distribution changed, a reorganization
+  /// occurred and the entire cache is discarded before refreshing. If the RPC returns a
+  /// distribution which isn't non-decreasing, or of the wrong length, nothing is cached.
+  pub async fn refresh<RPC: RpcConnection>(
+    &mut self,
+    rpc: &Rpc<RPC>,
+    height: usize,
+  ) -> Result<(), RpcError> {
+    while self.distribution.len() <= height {
+      let from = self.distribution.len().saturating_sub(1);
+      let extension = rpc.get_output_distribution(from, height).await?;
+      if (extension.len() != (height + 1 - from)) ||
+        extension.windows(2).any(|window| window[0] > window[1])
+      {
+        Err(RpcError::InvalidNode)?;
+      }
+
+      match self.distribution.last() {
+        Some(last) if *last != extension[0] => self.distribution.clear(),
+        Some(_) => self.distribution.extend(&extension[1 ..]),
+        None => self.distribution = extension,
+      }
+    }
+    Ok(())
+  }
+
+  pub fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+    write_vec(write_varint, &self.distribution, w)
+  }
+
+  pub fn serialize(&self) -> Vec<u8> {
+    let mut serialized = vec![];
+    self.write(&mut serialized).unwrap();
+    serialized
+  }
+
+  pub fn read<R: Read>(r: &mut R) -> io::Result<OutputDistribution> {
+    let distribution = read_vec(read_varint, r)?;
+    if distribution.windows(2).any(|window| window[0] > window[1]) {
+      Err(io::Error::new(io::ErrorKind::Other, "output distribution was decreasing"))?;
+    }
+    Ok(OutputDistribution { distribution })
+  }
 }
 
 #[allow(clippy::too_many_arguments)]
-async fn select_n<'a, R: RngCore + CryptoRng>(
+async fn select_n<RPC: RpcConnection, R: RngCore + CryptoRng>(
   rng: &mut R,
-  rpc: &Rpc,
-  distribution: &MutexGuard<'a, Vec<u64>>,
+  rpc: &Rpc<RPC>,
+  distribution: &[u64],
   height: usize,
   high: u64,
   per_second: f64,
@@ -136,16 +226,111 @@ impl Decoys {
     self.offsets.len()
   }
 
+  pub fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+    w.write_all(&[self.i])?;
+    write_vec(write_varint, &self.offsets, w)?;
+    for member in &self.ring {
+      write_point(&member[0], w)?;
+      write_point(&member[1], w)?;
+    }
+    Ok(())
+  }
+
+  pub fn serialize(&self) -> Vec<u8> {
+    let mut serialized = vec![];
+    self.write(&mut serialized).unwrap();
+    serialized
+  }
+
+  pub fn read<R: Read>(r: &mut R) -> io::Result<Decoys> {
+    let i = read_byte(r)?;
+    let offsets = read_vec(read_varint, r)?;
+    if usize::from(i) >= offsets.len() {
+      Err(io::Error::new(io::ErrorKind::Other, "real spend wasn't in the ring"))?;
+    }
+    let ring =
+      offsets.iter().map(|_| Ok([read_point(r)?, read_point(r)?])).collect::<io::Result<_>>()?;
+    Ok(Decoys { i, offsets, ring })
+  }
+
   /// Select decoys using the same distribution as Monero.
-  pub async fn select<R: RngCore + CryptoRng>(
+  pub async fn select<RPC: RpcConnection, R: RngCore + CryptoRng>(
     rng: &mut R,
-    rpc: &Rpc,
+    rpc: &Rpc<RPC>,
     ring_len: usize,
     height: usize,
     inputs: &[SpendableOutput],
   ) -> Result<Vec<Decoys>, RpcError> {
-    let mut distribution = DISTRIBUTION.lock().await;
+    // The cache is locked while refreshing so concurrent selections don't request the same blocks
+    let mut cache = rpc.output_distribution().await;
+    cache.refresh(rpc, height).await?;
+    // Only the distribution up to the height is used, so copy it out and release the cache before
+    // the RPC calls to select decoys
+    // height is inclusive, and 0 is a valid height
+    let distribution = cache.distribution()[..= height].to_vec();
+    drop(cache);
+    Decoys::select_from(rng, rpc, &distribution, ring_len, height, inputs).await
+  }
 
+  /// Deterministically select decoys, using a snapshot of the output distribution and a seed
+  /// (such as one derived from a transcript).
+  ///
+  /// Given the same snapshot, seed, height, and inputs, and RPCs with the same view of the
+  /// blockchain, this will select the same decoys. This lets multiple parties independently
+  /// select the same rings.
+  ///
+  /// The RPC is still used to check the height is confirmed and to fetch the candidate decoys via
+  /// `get_unlocked_outputs`. Only outputs within the snapshot are requested, and their timelocks
+  /// are evaluated against the specified height, not the RPC's tip, so this agrees across nodes
+  /// until the blockchain is reorganized to before the height.
+  pub async fn select_deterministic<RPC: RpcConnection>(
+    seed: [u8; 32],
+    rpc: &Rpc<RPC>,
+    distribution: &OutputDistribution,
+    ring_len: usize,
+    height: usize,
+    inputs: &[SpendableOutput],
+  ) -> Result<Vec<Decoys>, RpcError> {
+    let distribution = distribution
+      .distribution()
+      .get(..= height)
+      .ok_or(RpcError::InternalError("output distribution snapshot didn't include the height"))?;
+    Decoys::select_from(
+      &mut ChaCha20Rng::from_seed(seed),
+      rpc,
+      distribution,
+      ring_len,
+      height,
+      inputs,
+    )
+    .await
+  }
+
+  /// Verify the proposed decoys are those `select_deterministic` selects for this seed and
+  /// snapshot.
+  pub async fn verify_deterministic<RPC: RpcConnection>(
+    seed: [u8; 32],
+    rpc: &Rpc<RPC>,
+    distribution: &OutputDistribution,
+    ring_len: usize,
+    height: usize,
+    inputs: &[SpendableOutput],
+    decoys: &[Decoys],
+  ) -> Result<bool, RpcError> {
+    Ok(
+      Decoys::select_deterministic(seed, rpc, distribution, ring_len, height, inputs).await? ==
+        decoys,
+    )
+  }
+
+  async fn select_from<RPC: RpcConnection, R: RngCore + CryptoRng>(
+    rng: &mut R,
+    rpc: &Rpc<RPC>,
+    distribution: &[u64],
+    ring_len: usize,
+    height: usize,
+    inputs: &[SpendableOutput],
+  ) -> Result<Vec<Decoys>, RpcError> {
     let decoy_count = ring_len - 1;
 
     // Convert the inputs in question to the raw output data
@@ -156,14 +341,6 @@ impl Decoys {
       outputs.push((real[real.len() - 1], [input.key(), input.commitment().calculate()]));
     }
 
-    if distribution.len() <= height {
-      let extension = rpc.get_output_distribution(distribution.len(), height).await?;
-      distribution.extend(extension);
-    }
-    // If asked to use an older height than previously asked, truncate to ensure accuracy
-    // Should never happen, yet risks desyncing if it did
-    distribution.truncate(height + 1); // height is inclusive, and 0 is a valid height
-
     let high = distribution[distribution.len() - 1];
     let per_second = {
       let blocks = distribution.len().min(BLOCKS_PER_YEAR);
@@ -187,7 +364,7 @@ impl Decoys {
     let mut decoys = select_n(
       rng,
       rpc,
-      &distribution,
+      distribution,
       height,
       high,
       per_second,
@@ -236,7 +413,7 @@ impl Decoys {
             select_n(
               rng,
               rpc,
-              &distribution,
+              distribution,
               height,
               high,
               per_second,
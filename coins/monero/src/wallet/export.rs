@@ -0,0 +1,362 @@
+use core::ops::Deref;
+use std::io::{self, Read, Write};
+
+use zeroize::Zeroizing;
+
+use rand_core::{RngCore, CryptoRng};
+
+use chacha20::{
+  cipher::{KeyIvInit, StreamCipher},
+  ChaCha20Legacy,
+};
+
+use curve25519_dalek::{constants::ED25519_BASEPOINT_TABLE, scalar::Scalar, edwards::EdwardsPoint};
+
+use crate::{
+  hash,
+  cryptonight::cryptonight,
+  serialize::{read_byte, read_varint, read_point, read_vec, write_varint, write_point, write_vec},
+  ring_signatures::{Signature, RingSignature},
+  ringct::hash_to_point,
+  transaction::Transaction,
+  wallet::{
+    shared_key,
+    address::SubaddressIndex,
+    extra::Extra,
+    proofs::{sign_schnorr, verify_schnorr},
+    ViewPair, ReceivedOutput, SpendableOutput,
+  },
+};
+
+/// A key image, with a signature proving it's the key image of an output.
+///
+/// This is the format wallet2 exports key images in, via `export_key_images`, and the format it
+/// expects them in for `import_key_images`. These allow a view-only wallet, such as one used to
+/// audit a multisig wallet, to detect when its outputs are spent.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SignedKeyImage {
+  pub key_image: EdwardsPoint,
+  // wallet2 signs key images with a ring signature whose ring is solely the output key, using the
+  // key image as the message
+  signature: Signature,
+}
+
+impl SignedKeyImage {
+  /// Calculate and sign the key image for an output, given the wallet's private spend key.
+  pub fn new<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    spend: &Zeroizing<Scalar>,
+    output: &ReceivedOutput,
+  ) -> SignedKeyImage {
+    let key = Zeroizing::new(spend.deref() + output.key_offset());
+    let key_image = key.deref() * hash_to_point(output.key());
+    let (mut signature, _) =
+      RingSignature::sign(rng, &key_image.compress().to_bytes(), &[output.key()], 0, &key);
+    SignedKeyImage { key_image, signature: signature.sigs.swap_remove(0) }
+  }
+
+  /// Verify this is the key image for the output with the specified key.
+  pub fn verify(&self, output_key: EdwardsPoint) -> bool {
+    // wallet2 requires key images be in the prime order subgroup
+    self.key_image.is_torsion_free() &&
+      RingSignature { sigs: vec![self.signature.clone()] }.verify(
+        &self.key_image.compress().to_bytes(),
+        &[output_key],
+        &self.key_image,
+      )
+  }
+
+  pub fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+    write_point(&self.key_image, w)?;
+    self.signature.write(w)
+  }
+
+  pub fn serialize(&self) -> Vec<u8> {
+    let mut serialized = Vec::with_capacity(96);
+    self.write(&mut serialized).unwrap();
+    serialized
+  }
+
+  /// Read a SignedKeyImage, as serialized by wallet2. The key image followed by its signature is
+  /// also what wallet2's RPC represents as the `key_image` and `signature` fields.
+  pub fn read<R: Read>(r: &mut R) -> io::Result<SignedKeyImage> {
+    Ok(SignedKeyImage { key_image: read_point(r)?, signature: Signature::read(r)? })
+  }
+}
+
+// The magic wallet2 prefixes its encrypted output exports with
+const OUTPUT_EXPORT_MAGIC: &[u8] = b"Monero output export\x03";
+
+// The ChaCha20 key wallet2 derives from the private view key, with its default of one KDF round
+fn view_key_cipher(view: &Zeroizing<Scalar>, iv: [u8; 8]) -> ChaCha20Legacy {
+  let key = Zeroizing::new(cryptonight(&view.to_bytes()));
+  ChaCha20Legacy::new(key.deref().into(), &iv.into())
+}
+
+// wallet2's encrypt_with_view_secret_key, authenticating the IV and ciphertext with a signature by
+// the view key
+fn encrypt_with_view_key<R: RngCore + CryptoRng>(
+  rng: &mut R,
+  view: &Zeroizing<Scalar>,
+  plaintext: &[u8],
+) -> Vec<u8> {
+  let mut iv = [0; 8];
+  rng.fill_bytes(&mut iv);
+
+  let mut res = iv.to_vec();
+  res.extend(plaintext);
+  view_key_cipher(view, iv).apply_keystream(&mut res[8 ..]);
+
+  sign_schnorr(rng, &hash(&res), view).write(&mut res).unwrap();
+  res
+}
+
+// wallet2's decrypt_with_view_secret_key
+fn decrypt_with_view_key(view: &Zeroizing<Scalar>, ciphertext: &[u8]) -> io::Result<Vec<u8>> {
+  if ciphertext.len() < (8 + 64) {
+    Err(io::Error::new(io::ErrorKind::Other, "ciphertext was too short"))?;
+  }
+  let (ciphertext, mut signature) = ciphertext.split_at(ciphertext.len() - 64);
+  let signature = Signature::read(&mut signature)?;
+  if !verify_schnorr(&hash(ciphertext), view.deref() * &ED25519_BASEPOINT_TABLE, &signature) {
+    Err(io::Error::new(io::ErrorKind::Other, "ciphertext wasn't authenticated"))?;
+  }
+
+  let mut plaintext = ciphertext[8 ..].to_vec();
+  view_key_cipher(view, ciphertext[.. 8].try_into().unwrap()).apply_keystream(&mut plaintext);
+  Ok(plaintext)
+}
+
+// The version of wallet2's exported_transfer_details
+const EXPORTED_OUTPUT_VERSION: u64 = 1;
+
+// The bits of exported_transfer_details' flags
+const SPENT: u8 = 1 << 0;
+const FROZEN: u8 = 1 << 1;
+const RCT: u8 = 1 << 2;
+const KEY_IMAGE_KNOWN: u8 = 1 << 3;
+const KEY_IMAGE_REQUEST: u8 = 1 << 4;
+const KEY_IMAGE_PARTIAL: u8 = 1 << 5;
+
+/// An output, as wallet2 exports it via `export_outputs` and imports it via `import_outputs`.
+///
+/// This is wallet2's `exported_transfer_details`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ExportedOutput {
+  pub key: EdwardsPoint,
+  /// The index of the output within its transaction.
+  pub index_in_transaction: u64,
+  pub global_index: u64,
+  /// The transaction key the output was derived with, unless it used an additional key.
+  pub tx_key: EdwardsPoint,
+  pub spent: bool,
+  pub frozen: bool,
+  pub rct: bool,
+  pub key_image_known: bool,
+  pub key_image_request: bool,
+  pub key_image_partial: bool,
+  pub amount: u64,
+  pub additional_tx_keys: Vec<EdwardsPoint>,
+  pub subaddress: Option<SubaddressIndex>,
+}
+
+impl ExportedOutput {
+  /// Export an output, given the transaction it was received in.
+  ///
+  /// The output's key image is marked as unknown, as it is when exported by a view-only wallet.
+  /// Returns None if the transaction isn't the one the output was received in.
+  pub fn new(output: &SpendableOutput, tx: &Transaction) -> Option<ExportedOutput> {
+    if tx.hash() != output.output.absolute.tx {
+      return None;
+    }
+    let (tx_key, additional) = Extra::read::<&[u8]>(&mut tx.prefix.extra.as_ref()).ok()?.keys()?;
+
+    Some(ExportedOutput {
+      key: output.key(),
+      index_in_transaction: output.output.absolute.o.into(),
+      global_index: output.global_index,
+      tx_key,
+      spent: false,
+      frozen: false,
+      rct: tx.prefix.version == 2,
+      key_image_known: false,
+      key_image_request: false,
+      key_image_partial: false,
+      amount: output.commitment().amount,
+      additional_tx_keys: additional.unwrap_or_default(),
+      subaddress: output.output.metadata.subaddress,
+    })
+  }
+
+  /// Check this output was received by the specified wallet, returning the offset from the
+  /// wallet's spend key to the output's key if so.
+  ///
+  /// wallet2 performs this check for every output it imports.
+  pub fn key_offset(&self, pair: &ViewPair) -> Option<Scalar> {
+    let o = usize::try_from(self.index_in_transaction).ok()?;
+    for tx_key in [Some(&self.tx_key), self.additional_tx_keys.get(o)].into_iter().flatten() {
+      let (_, mut key_offset, _) = shared_key(None, pair.view.deref() * tx_key, o);
+      if let Some(subaddress) = self.subaddress {
+        key_offset += pair.subaddress_derivation(subaddress);
+      }
+      if ((&key_offset * &ED25519_BASEPOINT_TABLE) + pair.spend) == self.key {
+        return Some(key_offset);
+      }
+    }
+    None
+  }
+
+  pub fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+    write_varint(&EXPORTED_OUTPUT_VERSION, w)?;
+    write_point(&self.key, w)?;
+    write_varint(&self.index_in_transaction, w)?;
+    write_varint(&self.global_index, w)?;
+    write_point(&self.tx_key, w)?;
+
+    let mut flags = 0;
+    for (flag, bit) in [
+      (self.spent, SPENT),
+      (self.frozen, FROZEN),
+      (self.rct, RCT),
+      (self.key_image_known, KEY_IMAGE_KNOWN),
+      (self.key_image_request, KEY_IMAGE_REQUEST),
+      (self.key_image_partial, KEY_IMAGE_PARTIAL),
+    ] {
+      if flag {
+        flags |= bit;
+      }
+    }
+    w.write_all(&[flags])?;
+
+    write_varint(&self.amount, w)?;
+    write_vec(write_point, &self.additional_tx_keys, w)?;
+    let (account, address) =
+      self.subaddress.map(|index| (index.account(), index.address())).unwrap_or((0, 0));
+    write_varint(&account.into(), w)?;
+    write_varint(&address.into(), w)
+  }
+
+  pub fn serialize(&self) -> Vec<u8> {
+    let mut serialized = Vec::with_capacity(1 + 32 + 1 + 4 + 32 + 1 + 8 + 1 + 1 + 1);
+    self.write(&mut serialized).unwrap();
+    serialized
+  }
+
+  pub fn read<R: Read>(r: &mut R) -> io::Result<ExportedOutput> {
+    if read_varint(r)? != EXPORTED_OUTPUT_VERSION {
+      Err(io::Error::new(io::ErrorKind::Other, "unsupported exported output version"))?;
+    }
+    let key = read_point(r)?;
+    let index_in_transaction = read_varint(r)?;
+    let global_index = read_varint(r)?;
+    let tx_key = read_point(r)?;
+
+    let flags = read_byte(r)?;
+    if flags >= (KEY_IMAGE_PARTIAL << 1) {
+      Err(io::Error::new(io::ErrorKind::Other, "unknown exported output flags"))?;
+    }
+
+    let amount = read_varint(r)?;
+    let additional_tx_keys = read_vec(read_point, r)?;
+    let (account, address) = (read_varint(r)?, read_varint(r)?);
+    let subaddress = SubaddressIndex::new(
+      u32::try_from(account)
+        .map_err(|_| io::Error::new(io::ErrorKind::Other, "invalid account"))?,
+      u32::try_from(address)
+        .map_err(|_| io::Error::new(io::ErrorKind::Other, "invalid address"))?,
+    );
+
+    Ok(ExportedOutput {
+      key,
+      index_in_transaction,
+      global_index,
+      tx_key,
+      spent: (flags & SPENT) != 0,
+      frozen: (flags & FROZEN) != 0,
+      rct: (flags & RCT) != 0,
+      key_image_known: (flags & KEY_IMAGE_KNOWN) != 0,
+      key_image_request: (flags & KEY_IMAGE_REQUEST) != 0,
+      key_image_partial: (flags & KEY_IMAGE_PARTIAL) != 0,
+      amount,
+      additional_tx_keys,
+      subaddress,
+    })
+  }
+}
+
+/// A wallet's outputs, as exported by wallet2's `export_outputs`.
+///
+/// `export` and `import` handle the encrypted form wallet2 exports and imports, which is the
+/// magic "Monero output export\x03" followed by the serialization encrypted with a key derived from
+/// the private view key. `write` and `read` handle the plaintext serialization.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ExportedOutputs {
+  /// The index of the first output within the wallet's outputs.
+  pub offset: u64,
+  /// The amount of outputs the wallet has.
+  pub total: u64,
+  pub outputs: Vec<ExportedOutput>,
+}
+
+impl ExportedOutputs {
+  /// Write the outputs, prefixed by the public keys of the wallet exporting them.
+  pub fn write<W: Write>(&self, pair: &ViewPair, w: &mut W) -> io::Result<()> {
+    write_point(&pair.spend(), w)?;
+    write_point(&pair.view(), w)?;
+
+    // The outputs are serialized as the tuple (offset, total, outputs)
+    write_varint(&3, w)?;
+    write_varint(&self.offset, w)?;
+    write_varint(&self.total, w)?;
+    write_vec(ExportedOutput::write, &self.outputs, w)
+  }
+
+  pub fn serialize(&self, pair: &ViewPair) -> Vec<u8> {
+    let mut serialized = vec![];
+    self.write(pair, &mut serialized).unwrap();
+    serialized
+  }
+
+  /// Read outputs exported by the specified wallet, erroring if they were exported by another.
+  ///
+  /// The outputs themselves aren't checked to be the wallet's, which `ExportedOutput::key_offset`
+  /// does.
+  pub fn read<R: Read>(pair: &ViewPair, r: &mut R) -> io::Result<ExportedOutputs> {
+    if (read_point(r)? != pair.spend()) || (read_point(r)? != pair.view()) {
+      Err(io::Error::new(io::ErrorKind::Other, "outputs were exported by another wallet"))?;
+    }
+
+    if read_varint(r)? != 3 {
+      Err(io::Error::new(io::ErrorKind::Other, "exported outputs weren't a 3-tuple"))?;
+    }
+    Ok(ExportedOutputs {
+      offset: read_varint(r)?,
+      total: read_varint(r)?,
+      outputs: read_vec(ExportedOutput::read, r)?,
+    })
+  }
+
+  /// Export the outputs as wallet2 does, encrypted to the wallet's private view key.
+  pub fn export<R: RngCore + CryptoRng>(&self, rng: &mut R, pair: &ViewPair) -> Vec<u8> {
+    let serialized = Zeroizing::new(self.serialize(pair));
+    let mut res = OUTPUT_EXPORT_MAGIC.to_vec();
+    res.extend(encrypt_with_view_key(rng, &pair.view, &serialized));
+    res
+  }
+
+  /// Import outputs exported by the specified wallet, as exported by wallet2.
+  ///
+  /// This errors if the outputs weren't encrypted by the wallet, or were exported by another.
+  pub fn import(pair: &ViewPair, exported: &[u8]) -> io::Result<ExportedOutputs> {
+    let ciphertext = exported
+      .strip_prefix(OUTPUT_EXPORT_MAGIC)
+      .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "exported outputs had invalid magic"))?;
+    let serialized = Zeroizing::new(decrypt_with_view_key(&pair.view, ciphertext)?);
+    let mut reader = serialized.as_slice();
+    let outputs = ExportedOutputs::read(pair, &mut reader)?;
+    if !reader.is_empty() {
+      Err(io::Error::new(io::ErrorKind::Other, "exported outputs had trailing bytes"))?;
+    }
+    Ok(outputs)
+  }
+}
@@ -0,0 +1,142 @@
+use core::ops::Deref;
+use std::{
+  io::{self, Read, Write},
+  collections::HashMap,
+};
+
+use rand_core::{RngCore, CryptoRng};
+
+use zeroize::Zeroize;
+
+use curve25519_dalek::{traits::Identity, scalar::Scalar, edwards::EdwardsPoint};
+
+use group::ff::Field;
+use dalek_ff_group as dfg;
+
+use frost::{curve::Ed25519, Participant, FrostError, ThresholdKeys, algorithm::WriteAddendum};
+
+use crate::{
+  ringct::{
+    hash_to_point,
+    clsag::{ClsagAddendum, add_key_image_share},
+  },
+  wallet::SpendableOutput,
+};
+
+/// A participant's shares of the key images for a set of outputs, with proofs of their validity.
+#[derive(Clone, PartialEq, Eq, Debug, Zeroize)]
+pub struct KeyImageShares(Vec<ClsagAddendum>);
+
+impl KeyImageShares {
+  pub fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+    for share in &self.0 {
+      share.write(w)?;
+    }
+    Ok(())
+  }
+
+  pub fn serialize(&self) -> Vec<u8> {
+    let mut serialized = vec![];
+    self.write(&mut serialized).unwrap();
+    serialized
+  }
+}
+
+/// Machine to calculate the key images of outputs held by a threshold multisig wallet, without
+/// signing a transaction.
+///
+/// Each participant publishes their shares of the key images, with DLEq proofs binding them to
+/// their verification shares. Once the shares of `t` participants have been received, the key
+/// images can be calculated and used to detect when these outputs are spent.
+pub struct KeyImageMachine {
+  keys: ThresholdKeys<Ed25519>,
+  // Hashed key and scalar offset
+  outputs: Vec<(EdwardsPoint, Scalar)>,
+}
+
+impl KeyImageMachine {
+  /// Create a machine to calculate the key images for the specified outputs.
+  ///
+  /// Returns None if any output isn't spendable by these keys.
+  pub fn new(keys: ThresholdKeys<Ed25519>, outputs: &[SpendableOutput]) -> Option<KeyImageMachine> {
+    let mut res = vec![];
+    for output in outputs {
+      // Check this is the right set of keys
+      if keys.offset(dfg::Scalar(output.key_offset())).group_key().0 != output.key() {
+        None?;
+      }
+
+      res.push((
+        hash_to_point(output.key()),
+        keys.current_offset().unwrap_or(dfg::Scalar::ZERO).0 + output.key_offset(),
+      ));
+    }
+    Some(KeyImageMachine { keys, outputs: res })
+  }
+
+  /// Create our shares of the key images, to be sent to the other participants.
+  pub fn shares<R: RngCore + CryptoRng>(&self, rng: &mut R) -> KeyImageShares {
+    KeyImageShares(
+      self.outputs.iter().map(|(image, _)| ClsagAddendum::new(rng, *image, &self.keys)).collect(),
+    )
+  }
+
+  /// Read another participant's shares of the key images.
+  pub fn read_shares<R: Read>(&self, reader: &mut R) -> io::Result<KeyImageShares> {
+    let mut shares = Vec::with_capacity(self.outputs.len());
+    for _ in 0 .. self.outputs.len() {
+      shares.push(ClsagAddendum::read(reader)?);
+    }
+    Ok(KeyImageShares(shares))
+  }
+
+  /// Calculate the key images from the other participants' shares, verifying each share.
+  ///
+  /// The key images are returned in the same order as the outputs this machine was created with.
+  pub fn complete(
+    self,
+    shares: HashMap<Participant, KeyImageShares>,
+  ) -> Result<Vec<EdwardsPoint>, FrostError> {
+    let params = self.keys.params();
+
+    let mut included = Vec::with_capacity(shares.len() + 1);
+    included.push(params.i());
+    for l in shares.keys() {
+      included.push(*l);
+    }
+    included.sort_unstable();
+
+    // Included < threshold
+    if included.len() < usize::from(params.t()) {
+      Err(FrostError::InvalidSigningSet("not enough participants"))?;
+    }
+    // OOB index
+    if u16::from(included[included.len() - 1]) > params.n() {
+      Err(FrostError::InvalidParticipant(params.n(), included[included.len() - 1]))?;
+    }
+    // Our own shares are calculated locally
+    if shares.contains_key(&params.i()) {
+      Err(FrostError::DuplicatedParticipant(params.i()))?;
+    }
+    let view = self.keys.view(included.clone()).unwrap();
+
+    let mut images = vec![EdwardsPoint::identity(); self.outputs.len()];
+    for l in &included {
+      for (c, (image, (hashed, offset))) in images.iter_mut().zip(&self.outputs).enumerate() {
+        let share = if *l == params.i() {
+          dfg::EdwardsPoint(*hashed) * self.keys.secret_share().deref()
+        } else {
+          let share = shares[l].0.get(c).ok_or(FrostError::InvalidPreprocess(*l))?;
+          if !share.verify(*hashed, view.original_verification_share(*l)) {
+            Err(FrostError::InvalidPreprocess(*l))?;
+          }
+          share.key_image
+        };
+
+        add_key_image_share(image, *hashed, *offset, &included, *l, share.0);
+      }
+    }
+
+    Ok(images)
+  }
+}
@@ -0,0 +1,71 @@
+use zeroize::Zeroize;
+
+use crate::transaction::{Input, Timelock, Transaction};
+
+// CRYPTONOTE_DEFAULT_TX_SPENDABLE_AGE
+pub(crate) const SPENDABLE_AGE: usize = 10;
+
+/// If an output can be spent, and if not, which lock prevents it from being spent.
+///
+/// If an output is under multiple locks, its timelock takes precedence over the minimum age of
+/// spent outputs.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Spendability {
+  /// The output can be spent.
+  Spendable,
+  /// The output is a miner output which won't have matured until the chain reaches the specified
+  /// height. Miner outputs mature 60 blocks after their inclusion, as encoded in their timelock.
+  Immature(usize),
+  /// The output won't have met the minimum age of spent outputs, 10 blocks, until the chain
+  /// reaches the specified height.
+  Young(usize),
+  /// The output's transaction has an unexpired timelock.
+  Timelocked(Timelock),
+}
+
+/// The locks on an output, as determined by the block it was included in and its transaction.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Zeroize)]
+pub struct OutputLock {
+  /// The number of the block the output was included in.
+  pub block: usize,
+  /// If the output was created by a miner transaction.
+  pub coinbase: bool,
+  /// The timelock of the output's transaction.
+  pub timelock: Timelock,
+}
+
+impl OutputLock {
+  /// The locks on the outputs of a transaction included in the specified block.
+  pub fn new(block: usize, tx: &Transaction) -> OutputLock {
+    OutputLock {
+      block,
+      coinbase: matches!(tx.prefix.inputs.first(), Some(Input::Gen(_))),
+      timelock: tx.prefix.timelock,
+    }
+  }
+
+  /// The spendability of the output by a transaction in the next block, when the chain has the
+  /// specified height (its amount of blocks) and its latest block has the specified timestamp.
+  ///
+  /// Monero evaluates time-based timelocks against a median of recent timestamps, not the latest
+  /// block's. Outputs with such timelocks may be considered spendable shortly before Monero does.
+  pub fn spendability(&self, height: usize, time: u64) -> Spendability {
+    let unlocked = match self.timelock {
+      Timelock::None => true,
+      Timelock::Block(block) => height >= block,
+      Timelock::Time(timelock) => time >= timelock,
+    };
+    if !unlocked {
+      return match (self.coinbase, self.timelock) {
+        (true, Timelock::Block(block)) => Spendability::Immature(block),
+        _ => Spendability::Timelocked(self.timelock),
+      };
+    }
+
+    if height < (self.block + SPENDABLE_AGE) {
+      return Spendability::Young(self.block + SPENDABLE_AGE);
+    }
+
+    Spendability::Spendable
+  }
+}
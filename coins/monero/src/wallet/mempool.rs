@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+
+use curve25519_dalek::edwards::{EdwardsPoint, CompressedEdwardsY};
+
+use crate::{
+  transaction::{Input, Transaction},
+  rpc::{RpcError, RpcConnection, Rpc},
+};
+
+/// A snapshot of a node's mempool, indexed by the key images its transactions spend.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Mempool {
+  txs: Vec<([u8; 32], Transaction)>,
+  key_images: HashMap<CompressedEdwardsY, Vec<[u8; 32]>>,
+}
+
+fn key_images(tx: &Transaction) -> impl Iterator<Item = &EdwardsPoint> {
+  tx.prefix.inputs.iter().filter_map(|input| match input {
+    Input::Gen(_) => None,
+    Input::ToKey { key_image, .. } => Some(key_image),
+  })
+}
+
+impl Mempool {
+  /// Create a snapshot of the mempool from its transactions.
+  pub fn new(txs: Vec<Transaction>) -> Mempool {
+    let mut key_images = HashMap::new();
+    let txs = txs
+      .into_iter()
+      .map(|tx| {
+        let hash = tx.hash();
+        for key_image in self::key_images(&tx) {
+          key_images.entry(key_image.compress()).or_insert_with(Vec::new).push(hash);
+        }
+        (hash, tx)
+      })
+      .collect();
+    Mempool { txs, key_images }
+  }
+
+  /// Fetch a snapshot of the node's mempool.
+  pub async fn fetch<RPC: RpcConnection>(rpc: &Rpc<RPC>) -> Result<Mempool, RpcError> {
+    Ok(Mempool::new(rpc.get_transaction_pool().await?))
+  }
+
+  /// The transactions in the mempool.
+  pub fn transactions(&self) -> impl Iterator<Item = &Transaction> {
+    self.txs.iter().map(|(_, tx)| tx)
+  }
+
+  /// If the transaction with the specified hash is in the mempool.
+  pub fn contains(&self, hash: [u8; 32]) -> bool {
+    self.txs.iter().any(|(tx, _)| *tx == hash)
+  }
+
+  /// The hashes of the transactions in the mempool spending the specified key image.
+  pub fn spent_by(&self, key_image: &EdwardsPoint) -> &[[u8; 32]] {
+    self.key_images.get(&key_image.compress()).map(Vec::as_slice).unwrap_or(&[])
+  }
+
+  /// The hashes of the transactions in the mempool which spend any of the key images spent by the
+  /// specified transaction, other than the transaction itself.
+  ///
+  /// A node won't accept a transaction double spending one already in its mempool. This detects
+  /// when a transaction we published wasn't accepted due to a conflicting transaction. It also
+  /// detects conflicts within the mempool, which are possible when a node returns transactions
+  /// from blocks reorganized out to its mempool.
+  pub fn double_spends(&self, tx: &Transaction) -> Vec<[u8; 32]> {
+    let hash = tx.hash();
+    let mut res = vec![];
+    for key_image in key_images(tx) {
+      for spender in self.spent_by(key_image) {
+        if (*spender != hash) && (!res.contains(spender)) {
+          res.push(*spender);
+        }
+      }
+    }
+    res
+  }
+}
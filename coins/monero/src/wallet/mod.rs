@@ -1,5 +1,8 @@
 use core::ops::Deref;
-use std::collections::{HashSet, HashMap};
+use std::{
+  io::{self, Read, Write},
+  collections::{HashSet, HashMap},
+};
 
 use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
 
@@ -9,7 +12,12 @@ use curve25519_dalek::{
   edwards::{EdwardsPoint, CompressedEdwardsY},
 };
 
-use crate::{hash, hash_to_scalar, serialize::write_varint, transaction::Input};
+use crate::{
+  Commitment, hash, hash_to_scalar,
+  serialize::{read_byte, read_u32, read_bytes, read_vec, write_varint, write_vec},
+  ringct::EncryptedAmount,
+  transaction::Input,
+};
 
 pub mod extra;
 pub(crate) use extra::{PaymentId, ExtraField, Extra};
@@ -22,14 +30,35 @@ pub mod address;
 use address::{Network, AddressType, SubaddressIndex, AddressSpec, AddressMeta, MoneroAddress};
 
 mod scan;
-pub use scan::{ReceivedOutput, SpendableOutput, Timelocked};
+pub use scan::{ReceivedOutput, SpendableOutput, UnconfirmedOutput, Timelocked};
+
+mod mempool;
+pub use mempool::Mempool;
+
+mod lock;
+pub use lock::{Spendability, OutputLock};
+
+mod state;
+pub use state::{OutputState, Balance, TrackedOutput, WalletState};
+
+mod export;
+pub use export::{SignedKeyImage, ExportedOutput, ExportedOutputs};
+
+/// Transaction, spend, and reserve proofs, as created and verified by wallet2.
+pub mod proofs;
+
+#[cfg(feature = "multisig")]
+mod key_images;
+#[cfg(feature = "multisig")]
+pub use key_images::{KeyImageShares, KeyImageMachine};
 
 pub(crate) mod decoys;
-pub(crate) use decoys::Decoys;
+pub use decoys::{Decoys, OutputDistribution};
 
 mod send;
 pub use send::{
-  Fee, TransactionError, Change, SignableTransaction, SignableTransactionBuilder, Eventuality,
+  Fee, TransactionError, Change, SignableTransaction, SignableTransactionBuilder,
+  UnsignedTransaction, Eventuality,
 };
 #[cfg(feature = "multisig")]
 pub(crate) use send::InternalPayment;
@@ -92,8 +121,27 @@ pub(crate) fn amount_encryption(amount: u64, key: Scalar) -> [u8; 8] {
   (amount ^ u64::from_le_bytes(hash(&amount_mask)[.. 8].try_into().unwrap())).to_le_bytes()
 }
 
-fn amount_decryption(amount: [u8; 8], key: Scalar) -> u64 {
-  u64::from_le_bytes(amount_encryption(u64::from_le_bytes(amount), key))
+// Decrypt an amount and its mask, returning None if the amount doesn't fit within a u64
+fn amount_decryption(amount: &EncryptedAmount, key: Scalar) -> Option<Commitment> {
+  match amount {
+    // Prior to the compact format, the mask was also encrypted, with both being offset by hashes
+    // of the shared key
+    EncryptedAmount::Original { mask, amount } => {
+      let mask_shared_sec = hash_to_scalar(key.as_bytes());
+      let mask = Scalar::from_bytes_mod_order(*mask) - mask_shared_sec;
+
+      let amount_shared_sec = hash_to_scalar(mask_shared_sec.as_bytes());
+      let amount = (Scalar::from_bytes_mod_order(*amount) - amount_shared_sec).to_bytes();
+      if amount[8 ..].iter().any(|byte| *byte != 0) {
+        None?;
+      }
+      Some(Commitment::new(mask, u64::from_le_bytes(amount[.. 8].try_into().unwrap())))
+    }
+    EncryptedAmount::Compact { amount } => Some(Commitment::new(
+      commitment_mask(key),
+      u64::from_le_bytes(amount_encryption(u64::from_le_bytes(*amount), key)),
+    )),
+  }
 }
 
 pub(crate) fn commitment_mask(shared_key: Scalar) -> Scalar {
@@ -171,16 +219,58 @@ impl ViewPair {
   }
 }
 
+/// A wallet2-style subaddress lookahead.
+///
+/// A Scanner with a lookahead registers every subaddress within `accounts` accounts of the highest
+/// account in use, and within `addresses` addresses of the highest address in use in each account.
+/// Whenever an output is received to a subaddress, that subaddress is considered in use and the
+/// window is extended accordingly.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Zeroize)]
+pub struct SubaddressLookahead {
+  accounts: u32,
+  addresses: u32,
+}
+
+impl Default for SubaddressLookahead {
+  // The defaults used by wallet2
+  fn default() -> SubaddressLookahead {
+    SubaddressLookahead { accounts: 50, addresses: 200 }
+  }
+}
+
+impl SubaddressLookahead {
+  pub const fn new(accounts: u32, addresses: u32) -> Option<SubaddressLookahead> {
+    if (accounts == 0) || (addresses == 0) {
+      return None;
+    }
+    Some(SubaddressLookahead { accounts, addresses })
+  }
+
+  pub fn accounts(&self) -> u32 {
+    self.accounts
+  }
+
+  pub fn addresses(&self) -> u32 {
+    self.addresses
+  }
+}
+
 /// Transaction scanner.
 /// This scanner is capable of generating subaddresses, additionally scanning for them once they've
-/// been explicitly generated. If the burning bug is attempted, any secondary outputs will be
-/// ignored.
+/// been explicitly generated or are within its subaddress lookahead. If the burning bug is
+/// attempted, any secondary outputs will be ignored.
 #[derive(Clone)]
 pub struct Scanner {
   pair: ViewPair,
   // Also contains the spend key as None
   pub(crate) subaddresses: HashMap<CompressedEdwardsY, Option<SubaddressIndex>>,
   pub(crate) burning_bug: Option<HashSet<CompressedEdwardsY>>,
+
+  lookahead: Option<SubaddressLookahead>,
+  // The amount of addresses in use, per account in use
+  in_use: Vec<u32>,
+  // The amount of addresses registered by the lookahead, per account
+  window: Vec<u32>,
 }
 
 impl Zeroize for Scanner {
@@ -197,6 +287,10 @@ impl Zeroize for Scanner {
         output.zeroize();
       }
     }
+
+    self.lookahead.zeroize();
+    self.in_use.zeroize();
+    self.window.zeroize();
   }
 }
 
@@ -215,10 +309,13 @@ impl Scanner {
   /// When a new scanner is created, ALL saved output keys must be passed in to be secure.
   /// If None is passed, a modified shared key derivation is used which is immune to the burning
   /// bug (specifically the Guaranteed feature from Featured Addresses).
+  ///
+  /// The created Scanner has no subaddress lookahead. One may be set with
+  /// `set_subaddress_lookahead`.
   pub fn from_view(pair: ViewPair, burning_bug: Option<HashSet<CompressedEdwardsY>>) -> Scanner {
     let mut subaddresses = HashMap::new();
     subaddresses.insert(pair.spend.compress(), None);
-    Scanner { pair, subaddresses, burning_bug }
+    Scanner { pair, subaddresses, burning_bug, lookahead: None, in_use: vec![1], window: vec![] }
   }
 
   /// Register a subaddress.
@@ -230,4 +327,191 @@ impl Scanner {
     let (spend, _) = self.pair.subaddress_keys(subaddress);
     self.subaddresses.insert(spend.compress(), Some(subaddress));
   }
+
+  /// Set the subaddress lookahead, immediately registering every subaddress within it.
+  ///
+  /// Reducing the lookahead will not unregister any subaddresses.
+  pub fn set_subaddress_lookahead(&mut self, lookahead: SubaddressLookahead) {
+    self.lookahead = Some(lookahead);
+    self.extend_lookahead();
+  }
+
+  /// The subaddress lookahead, if one was set.
+  pub fn subaddress_lookahead(&self) -> Option<SubaddressLookahead> {
+    self.lookahead
+  }
+
+  /// The amount of accounts in use.
+  pub fn accounts(&self) -> u32 {
+    self.in_use.len().try_into().unwrap()
+  }
+
+  /// The amount of addresses in use within the specified account, including its first address.
+  pub fn addresses(&self, account: u32) -> u32 {
+    usize::try_from(account).ok().and_then(|account| self.in_use.get(account)).copied().unwrap_or(0)
+  }
+
+  /// Create a new account, returning its index.
+  ///
+  /// The account's first address is immediately registered.
+  pub fn create_account(&mut self) -> u32 {
+    let account = self.accounts();
+    let index = SubaddressIndex::new(account, 0).unwrap();
+    self.use_subaddress(Some(index));
+    self.register_subaddress(index);
+    account
+  }
+
+  /// Create a new subaddress within the specified account, returning its index.
+  ///
+  /// Returns None if the account isn't in use.
+  pub fn create_subaddress(&mut self, account: u32) -> Option<SubaddressIndex> {
+    let address = self.addresses(account);
+    if address == 0 {
+      return None;
+    }
+
+    let index = SubaddressIndex::new(account, address).unwrap();
+    self.use_subaddress(Some(index));
+    self.register_subaddress(index);
+    Some(index)
+  }
+
+  // Mark a subaddress as in use, extending the lookahead if it's beyond what was previously in use
+  pub(crate) fn use_subaddress(&mut self, index: Option<SubaddressIndex>) {
+    let (account, address) = index.map(|index| (index.account, index.address)).unwrap_or((0, 0));
+    let account = usize::try_from(account).unwrap();
+    if self.in_use.len() <= account {
+      // Every account prior to this one is considered in use, as wallet2 does
+      self.in_use.resize(account + 1, 1);
+    }
+    self.in_use[account] = self.in_use[account].max(address.saturating_add(1));
+
+    self.extend_lookahead();
+  }
+
+  // Register every subaddress within the lookahead which isn't already registered
+  fn extend_lookahead(&mut self) {
+    let lookahead = if let Some(lookahead) = self.lookahead {
+      lookahead
+    } else {
+      return;
+    };
+
+    // wallet2 clamps the window to u32::MAX, which this mirrors by saturating
+    let accounts = usize::try_from(
+      u32::try_from(self.in_use.len() - 1).unwrap().saturating_add(lookahead.accounts),
+    )
+    .unwrap();
+    if self.window.len() < accounts {
+      self.window.resize(accounts, 0);
+    }
+
+    for account in 0 .. accounts {
+      let highest = self.in_use.get(account).map(|in_use| in_use - 1).unwrap_or(0);
+      let end = highest.saturating_add(lookahead.addresses);
+
+      let account_u32 = u32::try_from(account).unwrap();
+      for address in self.window[account] .. end {
+        // The primary address isn't a subaddress and is always registered
+        if let Some(index) = SubaddressIndex::new(account_u32, address) {
+          self.register_subaddress(index);
+        }
+      }
+      self.window[account] = self.window[account].max(end);
+    }
+  }
+
+  /// Write the Scanner's state, excluding its ViewPair.
+  pub fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+    fn write_u32<W: Write>(value: &u32, w: &mut W) -> io::Result<()> {
+      w.write_all(&value.to_le_bytes())
+    }
+
+    if let Some(lookahead) = self.lookahead {
+      w.write_all(&[1])?;
+      write_u32(&lookahead.accounts, w)?;
+      write_u32(&lookahead.addresses, w)?;
+    } else {
+      w.write_all(&[0])?;
+    }
+    write_vec(write_u32, &self.in_use, w)?;
+    write_vec(write_u32, &self.window, w)?;
+
+    // Sort the subaddresses so the serialization is deterministic
+    let mut subaddresses = self
+      .subaddresses
+      .iter()
+      .filter_map(|(key, index)| index.map(|index| (index, key)))
+      .collect::<Vec<_>>();
+    subaddresses.sort_by_key(|(index, _)| (index.account, index.address));
+    write_vec(
+      |(index, key), w| {
+        w.write_all(key.as_bytes())?;
+        write_u32(&index.account, w)?;
+        write_u32(&index.address, w)
+      },
+      &subaddresses,
+      w,
+    )?;
+
+    if let Some(burning_bug) = self.burning_bug.as_ref() {
+      w.write_all(&[1])?;
+      let mut burning_bug = burning_bug.iter().map(|key| key.to_bytes()).collect::<Vec<_>>();
+      burning_bug.sort();
+      write_vec(|key, w| w.write_all(key), &burning_bug, w)
+    } else {
+      w.write_all(&[0])
+    }
+  }
+
+  pub fn serialize(&self) -> Vec<u8> {
+    let mut serialized = vec![];
+    self.write(&mut serialized).unwrap();
+    serialized
+  }
+
+  /// Read a Scanner's state, as written by `write`.
+  ///
+  /// The registered subaddresses are read as written, without being re-derived. The ViewPair
+  /// provided MUST be the one the Scanner was originally created with.
+  pub fn read<R: Read>(pair: ViewPair, r: &mut R) -> io::Result<Scanner> {
+    let lookahead = match read_byte(r)? {
+      0 => None,
+      1 => Some(
+        SubaddressLookahead::new(read_u32(r)?, read_u32(r)?)
+          .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "invalid subaddress lookahead"))?,
+      ),
+      _ => Err(io::Error::new(io::ErrorKind::Other, "invalid subaddress lookahead flag"))?,
+    };
+
+    let in_use = read_vec(read_u32, r)?;
+    if in_use.is_empty() || in_use.contains(&0) {
+      Err(io::Error::new(io::ErrorKind::Other, "invalid subaddresses in use"))?;
+    }
+    let window = read_vec(read_u32, r)?;
+
+    let mut subaddresses = HashMap::new();
+    subaddresses.insert(pair.spend.compress(), None);
+    for (key, index) in read_vec(
+      |r| {
+        Ok((
+          CompressedEdwardsY(read_bytes(r)?),
+          SubaddressIndex::new(read_u32(r)?, read_u32(r)?)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "invalid subaddress"))?,
+        ))
+      },
+      r,
+    )? {
+      subaddresses.insert(key, Some(index));
+    }
+
+    let burning_bug = match read_byte(r)? {
+      0 => None,
+      1 => Some(read_vec(|r| Ok(CompressedEdwardsY(read_bytes(r)?)), r)?.into_iter().collect()),
+      _ => Err(io::Error::new(io::ErrorKind::Other, "invalid burning bug flag"))?,
+    };
+
+    Ok(Scanner { pair, subaddresses, burning_bug, lookahead, in_use, window })
+  }
 }
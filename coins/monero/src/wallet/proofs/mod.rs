@@ -0,0 +1,849 @@
+use core::{ops::Deref, fmt, str::FromStr};
+use std::{
+  io::{self, Read, Write},
+  collections::{HashSet, HashMap},
+};
+
+use thiserror::Error;
+
+use zeroize::Zeroizing;
+
+use rand_core::{RngCore, CryptoRng};
+
+use curve25519_dalek::{constants::ED25519_BASEPOINT_TABLE, scalar::Scalar, edwards::EdwardsPoint};
+
+use base58_monero::base58::{encode, decode};
+
+use crate::{
+  hash, hash_to_scalar, random_scalar,
+  serialize::*,
+  ring_signatures::{Signature, RingSignature},
+  ringct::hash_to_point,
+  transaction::{Input, Transaction},
+  verify::{VerifyError, RingProvider, absolute_offsets},
+  wallet::{
+    address::{SubaddressIndex, MoneroAddress},
+    extra::Extra,
+    ViewPair, ReceivedOutput, shared_key, amount_decryption,
+  },
+};
+
+#[cfg(feature = "multisig")]
+mod multisig;
+#[cfg(feature = "multisig")]
+pub use multisig::{ProofMachine, ProofSignMachine, ProofSignatureMachine};
+
+const OUT_PROOF_HEADER: &str = "OutProofV2";
+const IN_PROOF_HEADER: &str = "InProofV2";
+const SPEND_PROOF_HEADER: &str = "SpendProofV1";
+const RESERVE_PROOF_HEADER: &str = "ReserveProofV2";
+
+// Base58 encodes each 8-byte block as 11 characters
+const POINT_BASE58_LEN: usize = 44;
+const SIGNATURE_BASE58_LEN: usize = 88;
+
+/// Errors returned when creating or verifying proofs.
+#[derive(Clone, PartialEq, Eq, Debug, Error)]
+pub enum ProofError {
+  #[error("invalid proof encoding")]
+  InvalidEncoding,
+  #[error("transaction has no keys")]
+  MissingTransactionKeys,
+  #[error("transaction not provided ({0:?})")]
+  MissingTransaction([u8; 32]),
+  #[error("output not found in its transaction")]
+  OutputNotFound,
+  #[error("invalid input")]
+  InvalidInput,
+  #[error("wrong spend private key")]
+  WrongPrivateKey,
+  #[error("invalid key image")]
+  InvalidKeyImage,
+  #[error("ring error ({0})")]
+  RingError(VerifyError),
+}
+
+fn base58_chunks(data: &str, len: usize) -> Result<Vec<Vec<u8>>, ProofError> {
+  if data.is_empty() || ((data.len() % len) != 0) {
+    Err(ProofError::InvalidEncoding)?;
+  }
+  (0 .. (data.len() / len))
+    .map(|i| {
+      data
+        .get((i * len) .. ((i + 1) * len))
+        .and_then(|chunk| decode(chunk).ok())
+        .ok_or(ProofError::InvalidEncoding)
+    })
+    .collect()
+}
+
+// The message signed by transaction and spend proofs
+fn tx_proof_message(tx: [u8; 32], message: &[u8]) -> [u8; 32] {
+  hash(&[tx.as_ref(), message].concat())
+}
+
+// The challenge for a proof D = xA, and R = xB (or xG if B is None), as done by wallet2's
+// generate_tx_proof (version 2)
+#[allow(non_snake_case)]
+fn tx_proof_challenge(
+  msg: &[u8; 32],
+  D: EdwardsPoint,
+  X: EdwardsPoint,
+  Y: EdwardsPoint,
+  R: EdwardsPoint,
+  A: EdwardsPoint,
+  B: Option<EdwardsPoint>,
+) -> Scalar {
+  let mut transcript = Vec::with_capacity(32 * 8);
+  transcript.extend(msg);
+  transcript.extend(D.compress().to_bytes());
+  transcript.extend(X.compress().to_bytes());
+  transcript.extend(Y.compress().to_bytes());
+  transcript.extend(hash(b"TXPROOF_V2"));
+  transcript.extend(R.compress().to_bytes());
+  transcript.extend(A.compress().to_bytes());
+  transcript.extend(B.map(|B| B.compress().to_bytes()).unwrap_or([0; 32]));
+  hash_to_scalar(&transcript)
+}
+
+#[allow(non_snake_case)]
+fn sign_tx_proof<Rng: RngCore + CryptoRng>(
+  rng: &mut Rng,
+  msg: &[u8; 32],
+  R: EdwardsPoint,
+  A: EdwardsPoint,
+  B: Option<EdwardsPoint>,
+  D: EdwardsPoint,
+  key: &Zeroizing<Scalar>,
+) -> Signature {
+  let nonce = Zeroizing::new(random_scalar(rng));
+  let X = match B {
+    Some(B) => nonce.deref() * B,
+    None => nonce.deref() * &ED25519_BASEPOINT_TABLE,
+  };
+  let Y = nonce.deref() * A;
+  let c = tx_proof_challenge(msg, D, X, Y, R, A, B);
+  Signature { c, r: nonce.deref() - (c * key.deref()) }
+}
+
+#[allow(non_snake_case)]
+fn verify_tx_proof(
+  msg: &[u8; 32],
+  R: EdwardsPoint,
+  A: EdwardsPoint,
+  B: Option<EdwardsPoint>,
+  D: EdwardsPoint,
+  sig: &Signature,
+) -> bool {
+  let X = match B {
+    Some(B) => (sig.c * R) + (sig.r * B),
+    None => EdwardsPoint::vartime_double_scalar_mul_basepoint(&sig.c, &R, &sig.r),
+  };
+  let Y = (sig.c * D) + (sig.r * A);
+  tx_proof_challenge(msg, D, X, Y, R, A, B) == sig.c
+}
+
+// wallet2's generate_signature, a Schnorr signature with the challenge Hs(msg || key || R)
+#[allow(non_snake_case)]
+fn schnorr_challenge(msg: &[u8; 32], key: EdwardsPoint, R: EdwardsPoint) -> Scalar {
+  hash_to_scalar(&[msg.as_ref(), &key.compress().to_bytes(), &R.compress().to_bytes()].concat())
+}
+
+pub(crate) fn sign_schnorr<Rng: RngCore + CryptoRng>(
+  rng: &mut Rng,
+  msg: &[u8; 32],
+  key: &Zeroizing<Scalar>,
+) -> Signature {
+  let nonce = Zeroizing::new(random_scalar(rng));
+  let c = schnorr_challenge(
+    msg,
+    key.deref() * &ED25519_BASEPOINT_TABLE,
+    nonce.deref() * &ED25519_BASEPOINT_TABLE,
+  );
+  Signature { c, r: nonce.deref() - (c * key.deref()) }
+}
+
+#[allow(non_snake_case)]
+pub(crate) fn verify_schnorr(msg: &[u8; 32], key: EdwardsPoint, sig: &Signature) -> bool {
+  let R = EdwardsPoint::vartime_double_scalar_mul_basepoint(&sig.c, &key, &sig.r);
+  schnorr_challenge(msg, key, R) == sig.c
+}
+
+// The transaction's main key, followed by its additional keys
+fn tx_keys(tx: &Transaction) -> Result<Vec<EdwardsPoint>, ProofError> {
+  let (key, additional) = Extra::read::<&[u8]>(&mut tx.prefix.extra.as_ref())
+    .ok()
+    .and_then(|extra| extra.keys())
+    .ok_or(ProofError::MissingTransactionKeys)?;
+  let mut keys = vec![key];
+  keys.extend(additional.unwrap_or(vec![]));
+  Ok(keys)
+}
+
+// The amount of the specified output, given its shared key, if its commitment is valid
+fn output_amount(tx: &Transaction, o: usize, shared_key: Scalar) -> Option<u64> {
+  let output = tx.prefix.outputs.get(o)?;
+  // Miner transaction
+  if output.amount != 0 {
+    return Some(output.amount);
+  }
+
+  let commitment = amount_decryption(tx.rct_signatures.base.ecdh_info.get(o)?, shared_key)?;
+  if Some(&commitment.calculate()) != tx.rct_signatures.base.commitments.get(o) {
+    None?;
+  }
+  Some(commitment.amount)
+}
+
+/// Whether a transaction proof was created by the sender, with the transaction's private keys, or
+/// by the recipient, with their private view key.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TxProofType {
+  Out,
+  In,
+}
+
+/// A proof a transaction paid an address, compatible with wallet2's `get_tx_proof` and
+/// `check_tx_proof` (OutProofV2 and InProofV2).
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TxProof {
+  kind: TxProofType,
+  // The shared secret for each of the transaction's keys, with its signature
+  shared_secrets: Vec<(EdwardsPoint, Signature)>,
+}
+
+impl TxProof {
+  /// Prove a transaction paid an address, using the transaction's private keys.
+  ///
+  /// The additional keys are only present if the transaction has additional public keys.
+  pub fn out_proof<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    tx: &Transaction,
+    address: &MoneroAddress,
+    key: &Zeroizing<Scalar>,
+    additional_keys: &[Zeroizing<Scalar>],
+    message: &[u8],
+  ) -> TxProof {
+    let msg = tx_proof_message(tx.hash(), message);
+    let spend = if address.is_subaddress() { Some(address.spend) } else { None };
+
+    let shared_secrets = [key]
+      .into_iter()
+      .chain(additional_keys)
+      .map(|key| {
+        let tx_key = match spend {
+          Some(spend) => key.deref() * spend,
+          None => key.deref() * &ED25519_BASEPOINT_TABLE,
+        };
+        let shared_secret = key.deref() * address.view;
+        (shared_secret, sign_tx_proof(rng, &msg, tx_key, address.view, spend, shared_secret, key))
+      })
+      .collect();
+    TxProof { kind: TxProofType::Out, shared_secrets }
+  }
+
+  /// Prove a transaction paid one of a wallet's addresses, using its private view key.
+  pub fn in_proof<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    tx: &Transaction,
+    pair: &ViewPair,
+    subaddress: Option<SubaddressIndex>,
+    message: &[u8],
+  ) -> Result<TxProof, ProofError> {
+    let msg = tx_proof_message(tx.hash(), message);
+    let (spend, view) = match subaddress {
+      Some(index) => {
+        let (spend, view) = pair.subaddress_keys(index);
+        (Some(spend), view)
+      }
+      None => (None, pair.view()),
+    };
+
+    let shared_secrets = tx_keys(tx)?
+      .into_iter()
+      .map(|tx_key| {
+        let shared_secret = pair.view.deref() * tx_key;
+        (shared_secret, sign_tx_proof(rng, &msg, view, tx_key, spend, shared_secret, &pair.view))
+      })
+      .collect();
+    Ok(TxProof { kind: TxProofType::In, shared_secrets })
+  }
+
+  pub fn kind(&self) -> TxProofType {
+    self.kind
+  }
+
+  /// Verify this proof, returning the amount the address received in the transaction.
+  ///
+  /// As with wallet2, only one of the transaction's shared secrets has to be validly signed. Unlike
+  /// wallet2, only validly signed shared secrets are used to find the outputs received.
+  pub fn verify(&self, tx: &Transaction, address: &MoneroAddress, message: &[u8]) -> Option<u64> {
+    let keys = tx_keys(tx).ok()?;
+    if keys.len() != self.shared_secrets.len() {
+      None?;
+    }
+
+    let msg = tx_proof_message(tx.hash(), message);
+    let spend = if address.is_subaddress() { Some(address.spend) } else { None };
+    let valid = keys
+      .iter()
+      .zip(&self.shared_secrets)
+      .map(|(tx_key, (shared_secret, sig))| match self.kind {
+        TxProofType::Out => {
+          verify_tx_proof(&msg, *tx_key, address.view, spend, *shared_secret, sig)
+        }
+        TxProofType::In => verify_tx_proof(&msg, address.view, *tx_key, spend, *shared_secret, sig),
+      })
+      .collect::<Vec<_>>();
+    if !valid.iter().any(|valid| *valid) {
+      None?;
+    }
+
+    let mut received = 0u64;
+    for (o, output) in tx.prefix.outputs.iter().enumerate() {
+      // Try the main key, then this output's additional key
+      for i in [0, o + 1] {
+        if !valid.get(i).copied().unwrap_or(false) {
+          continue;
+        }
+
+        let (_, shared_key, _) = shared_key(None, self.shared_secrets[i].0, o);
+        if output.key != ((&shared_key * &ED25519_BASEPOINT_TABLE) + address.spend).compress() {
+          continue;
+        }
+        received = received.saturating_add(output_amount(tx, o, shared_key).unwrap_or(0));
+        break;
+      }
+    }
+    Some(received)
+  }
+}
+
+impl fmt::Display for TxProof {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "{}",
+      match self.kind {
+        TxProofType::Out => OUT_PROOF_HEADER,
+        TxProofType::In => IN_PROOF_HEADER,
+      }
+    )?;
+    for (shared_secret, sig) in &self.shared_secrets {
+      let mut sig_bytes = Vec::with_capacity(64);
+      sig.write(&mut sig_bytes).unwrap();
+      write!(
+        f,
+        "{}{}",
+        encode(&shared_secret.compress().to_bytes()).unwrap(),
+        encode(&sig_bytes).unwrap()
+      )?;
+    }
+    Ok(())
+  }
+}
+
+impl FromStr for TxProof {
+  type Err = ProofError;
+  fn from_str(s: &str) -> Result<TxProof, ProofError> {
+    let (kind, data) = if let Some(data) = s.strip_prefix(OUT_PROOF_HEADER) {
+      (TxProofType::Out, data)
+    } else if let Some(data) = s.strip_prefix(IN_PROOF_HEADER) {
+      (TxProofType::In, data)
+    } else {
+      Err(ProofError::InvalidEncoding)?
+    };
+
+    let chunks = base58_chunks(data, POINT_BASE58_LEN + SIGNATURE_BASE58_LEN)?;
+    let shared_secrets = chunks
+      .iter()
+      .map(|chunk| {
+        let mut chunk = chunk.as_slice();
+        Ok((read_point(&mut chunk)?, Signature::read(&mut chunk)?))
+      })
+      .collect::<io::Result<_>>()
+      .map_err(|_| ProofError::InvalidEncoding)?;
+    Ok(TxProof { kind, shared_secrets })
+  }
+}
+
+// The rings and key images of a transaction's inputs
+async fn rings<P: RingProvider>(
+  provider: &P,
+  tx: &Transaction,
+) -> Result<Vec<(Vec<EdwardsPoint>, EdwardsPoint)>, ProofError> {
+  let mut offsets = Vec::with_capacity(tx.prefix.inputs.len());
+  let mut key_images = Vec::with_capacity(tx.prefix.inputs.len());
+  for input in &tx.prefix.inputs {
+    match input {
+      Input::ToKey { amount: 0, key_offsets, key_image } => {
+        offsets.push(absolute_offsets(key_offsets).map_err(ProofError::RingError)?);
+        key_images.push(*key_image);
+      }
+      _ => Err(ProofError::InvalidInput)?,
+    }
+  }
+
+  let indexes = offsets.concat();
+  let members = provider.ring_members(0, &indexes).await.map_err(ProofError::RingError)?;
+  if members.len() != indexes.len() {
+    Err(ProofError::RingError(VerifyError::InvalidRing))?;
+  }
+  let mut members = members.into_iter();
+  Ok(
+    offsets
+      .iter()
+      .map(|offsets| {
+        members.by_ref().take(offsets.len()).map(|member| member[0]).collect::<Vec<_>>()
+      })
+      .zip(key_images)
+      .collect(),
+  )
+}
+
+/// A proof a transaction was sent by a wallet, proving knowledge of the private keys for the
+/// key images of its inputs, compatible with wallet2's `get_spend_proof` and `check_spend_proof`
+/// (SpendProofV1).
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SpendProof {
+  // The signatures for every ring member of every input, in order
+  signatures: Vec<Signature>,
+}
+
+impl SpendProof {
+  /// Prove a transaction was sent by a wallet, given its private spend key and the outputs spent,
+  /// in the order of the transaction's inputs.
+  pub async fn new<R: RngCore + CryptoRng, P: RingProvider>(
+    rng: &mut R,
+    provider: &P,
+    tx: &Transaction,
+    spend: &Zeroizing<Scalar>,
+    outputs: &[ReceivedOutput],
+    message: &[u8],
+  ) -> Result<SpendProof, ProofError> {
+    let rings = rings(provider, tx).await?;
+    if rings.len() != outputs.len() {
+      Err(ProofError::InvalidInput)?;
+    }
+
+    let msg = tx_proof_message(tx.hash(), message);
+    let mut signatures = vec![];
+    for ((ring, key_image), output) in rings.iter().zip(outputs) {
+      let i =
+        ring.iter().position(|member| *member == output.key()).ok_or(ProofError::InvalidInput)?;
+      let (signature, image) = RingSignature::sign(
+        rng,
+        &msg,
+        ring,
+        i,
+        &Zeroizing::new(spend.deref() + output.key_offset()),
+      );
+      if image != *key_image {
+        Err(ProofError::WrongPrivateKey)?;
+      }
+      signatures.extend(signature.sigs);
+    }
+    Ok(SpendProof { signatures })
+  }
+
+  /// Verify this proof for the specified transaction.
+  pub async fn verify<P: RingProvider>(
+    &self,
+    provider: &P,
+    tx: &Transaction,
+    message: &[u8],
+  ) -> Result<bool, ProofError> {
+    let rings = rings(provider, tx).await?;
+    if rings.iter().map(|(ring, _)| ring.len()).sum::<usize>() != self.signatures.len() {
+      return Ok(false);
+    }
+
+    let msg = tx_proof_message(tx.hash(), message);
+    let mut signatures = self.signatures.iter();
+    for (ring, key_image) in rings {
+      let signature =
+        RingSignature { sigs: signatures.by_ref().take(ring.len()).cloned().collect() };
+      if !signature.verify(&msg, &ring, &key_image) {
+        return Ok(false);
+      }
+    }
+    Ok(true)
+  }
+}
+
+impl fmt::Display for SpendProof {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{SPEND_PROOF_HEADER}")?;
+    for sig in &self.signatures {
+      let mut sig_bytes = Vec::with_capacity(64);
+      sig.write(&mut sig_bytes).unwrap();
+      write!(f, "{}", encode(&sig_bytes).unwrap())?;
+    }
+    Ok(())
+  }
+}
+
+impl FromStr for SpendProof {
+  type Err = ProofError;
+  fn from_str(s: &str) -> Result<SpendProof, ProofError> {
+    let data = s.strip_prefix(SPEND_PROOF_HEADER).ok_or(ProofError::InvalidEncoding)?;
+    let signatures = base58_chunks(data, SIGNATURE_BASE58_LEN)?
+      .iter()
+      .map(|chunk| Signature::read::<&[u8]>(&mut chunk.as_ref()))
+      .collect::<io::Result<_>>()
+      .map_err(|_| ProofError::InvalidEncoding)?;
+    Ok(SpendProof { signatures })
+  }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct ReserveProofEntry {
+  tx: [u8; 32],
+  o: u64,
+  shared_secret: EdwardsPoint,
+  key_image: EdwardsPoint,
+  shared_secret_signature: Signature,
+  key_image_signature: Signature,
+}
+
+impl ReserveProofEntry {
+  fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+    // Version
+    write_varint(&0, w)?;
+    w.write_all(&self.tx)?;
+    write_varint(&self.o, w)?;
+    write_point(&self.shared_secret, w)?;
+    write_point(&self.key_image, w)?;
+    self.shared_secret_signature.write(w)?;
+    self.key_image_signature.write(w)
+  }
+
+  fn read<R: Read>(r: &mut R) -> io::Result<ReserveProofEntry> {
+    if read_varint(r)? != 0 {
+      Err(io::Error::new(io::ErrorKind::Other, "unknown reserve proof entry version"))?;
+    }
+    Ok(ReserveProofEntry {
+      tx: read_bytes(r)?,
+      o: read_varint(r)?,
+      shared_secret: read_point(r)?,
+      key_image: read_point(r)?,
+      shared_secret_signature: Signature::read(r)?,
+      key_image_signature: Signature::read(r)?,
+    })
+  }
+}
+
+// An output to include in a reserve proof, with the transaction key used to derive it
+#[derive(Clone)]
+struct ReserveOutput {
+  output: ReceivedOutput,
+  tx_key: EdwardsPoint,
+}
+
+impl ReserveOutput {
+  fn new(
+    pair: &ViewPair,
+    txs: &[Transaction],
+    outputs: &[ReceivedOutput],
+  ) -> Result<Vec<ReserveOutput>, ProofError> {
+    let txs = txs.iter().map(|tx| (tx.hash(), tx)).collect::<HashMap<_, _>>();
+
+    let mut res = Vec::with_capacity(outputs.len());
+    for output in outputs {
+      let tx =
+        txs.get(&output.absolute.tx).ok_or(ProofError::MissingTransaction(output.absolute.tx))?;
+      let o = usize::from(output.absolute.o);
+      let spend = match output.metadata.subaddress {
+        Some(index) => pair.subaddress_keys(index).0,
+        None => pair.spend,
+      };
+
+      // Find the transaction key this output was derived with, as wallet2 does
+      // Outputs derived with this library's uniqueness aren't considered, as wallet2 can't verify
+      // them
+      let keys = tx_keys(tx)?;
+      // Additional keys are only used if there's one for every output
+      let additional =
+        if (keys.len() - 1) == tx.prefix.outputs.len() { keys.get(o + 1) } else { None };
+      let tx_key = [keys.first(), additional]
+        .into_iter()
+        .flatten()
+        .find(|tx_key| {
+          let (_, shared_key, _) = shared_key(None, pair.view.deref() * *tx_key, o);
+          ((&shared_key * &ED25519_BASEPOINT_TABLE) + spend) == output.key()
+        })
+        .ok_or(ProofError::OutputNotFound)?;
+      res.push(ReserveOutput { output: output.clone(), tx_key: *tx_key });
+    }
+    Ok(res)
+  }
+}
+
+// The message signed by reserve proofs
+fn reserve_proof_message(message: &[u8], pair: &ViewPair, key_images: &[EdwardsPoint]) -> [u8; 32] {
+  let mut msg = message.to_vec();
+  msg.extend(pair.spend.compress().to_bytes());
+  msg.extend(pair.view().compress().to_bytes());
+  for key_image in key_images {
+    msg.extend(key_image.compress().to_bytes());
+  }
+  hash(&msg)
+}
+
+/// A proof a wallet holds outputs, and their key images, compatible with wallet2's
+/// `get_reserve_proof` and `check_reserve_proof` (ReserveProofV2).
+///
+/// Checking if these outputs have been spent, by their key images, is left to the verifier.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ReserveProof {
+  entries: Vec<ReserveProofEntry>,
+  // The spend keys of the addresses which received the outputs, with signatures proving knowledge
+  // of their private keys
+  spend_keys: Vec<(EdwardsPoint, Signature)>,
+}
+
+impl ReserveProof {
+  // Create a reserve proof, without the signatures which require the private spend key
+  fn unsigned<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    pair: &ViewPair,
+    outputs: &[ReserveOutput],
+    key_images: &[EdwardsPoint],
+    message: &[u8],
+  ) -> (ReserveProof, [u8; 32], Vec<Option<SubaddressIndex>>) {
+    let msg = reserve_proof_message(message, pair, key_images);
+    let placeholder = Signature { c: Scalar::zero(), r: Scalar::zero() };
+
+    let entries = outputs
+      .iter()
+      .zip(key_images)
+      .map(|(output, key_image)| {
+        let shared_secret = pair.view.deref() * output.tx_key;
+        ReserveProofEntry {
+          tx: output.output.absolute.tx,
+          o: output.output.absolute.o.into(),
+          shared_secret,
+          key_image: *key_image,
+          shared_secret_signature: sign_tx_proof(
+            rng,
+            &msg,
+            pair.view(),
+            output.tx_key,
+            None,
+            shared_secret,
+            &pair.view,
+          ),
+          key_image_signature: placeholder.clone(),
+        }
+      })
+      .collect();
+
+    // wallet2 always includes the primary address
+    let mut subaddresses = vec![None];
+    for output in outputs {
+      if !subaddresses.contains(&output.output.metadata.subaddress) {
+        subaddresses.push(output.output.metadata.subaddress);
+      }
+    }
+    let spend_keys = subaddresses
+      .iter()
+      .map(|index| {
+        (
+          index.map(|index| pair.subaddress_keys(index).0).unwrap_or(pair.spend),
+          placeholder.clone(),
+        )
+      })
+      .collect();
+
+    (ReserveProof { entries, spend_keys }, msg, subaddresses)
+  }
+
+  /// Prove a wallet holds the specified outputs, given its private spend key and the transactions
+  /// the outputs were received in.
+  pub fn new<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    spend: &Zeroizing<Scalar>,
+    pair: &ViewPair,
+    txs: &[Transaction],
+    outputs: &[ReceivedOutput],
+    message: &[u8],
+  ) -> Result<ReserveProof, ProofError> {
+    if (spend.deref() * &ED25519_BASEPOINT_TABLE) != pair.spend {
+      Err(ProofError::WrongPrivateKey)?;
+    }
+
+    let outputs = ReserveOutput::new(pair, txs, outputs)?;
+    let keys = outputs
+      .iter()
+      .map(|output| Zeroizing::new(spend.deref() + output.output.key_offset()))
+      .collect::<Vec<_>>();
+    let key_images = outputs
+      .iter()
+      .zip(&keys)
+      .map(|(output, key)| key.deref() * hash_to_point(output.output.key()))
+      .collect::<Vec<_>>();
+
+    let (mut proof, msg, subaddresses) =
+      ReserveProof::unsigned(rng, pair, &outputs, &key_images, message);
+    for ((entry, output), key) in proof.entries.iter_mut().zip(&outputs).zip(&keys) {
+      let (mut signature, _) = RingSignature::sign(rng, &msg, &[output.output.key()], 0, key);
+      entry.key_image_signature = signature.sigs.swap_remove(0);
+    }
+    for ((_, signature), index) in proof.spend_keys.iter_mut().zip(subaddresses) {
+      let mut key = Zeroizing::new(*spend.deref());
+      if let Some(index) = index {
+        *key += pair.subaddress_derivation(index);
+      }
+      *signature = sign_schnorr(rng, &msg, &key);
+    }
+    Ok(proof)
+  }
+
+  /// The hashes of the transactions which must be provided to `verify`.
+  pub fn transactions(&self) -> Vec<[u8; 32]> {
+    self.entries.iter().map(|entry| entry.tx).collect()
+  }
+
+  /// Verify this proof for the specified address, returning the key image and amount of each
+  /// output proven.
+  ///
+  /// The transactions which contain the outputs, as returned by `transactions`, must be provided.
+  /// The sum of these amounts, which includes outputs which have been spent, is the amount
+  /// `check_reserve_proof` reports as the total. Whether the key images have been spent is left to
+  /// the caller.
+  pub fn verify(
+    &self,
+    address: &MoneroAddress,
+    message: &[u8],
+    txs: &[Transaction],
+  ) -> Option<Vec<(EdwardsPoint, u64)>> {
+    // wallet2 only creates reserve proofs for the primary address
+    if address.is_subaddress() {
+      None?;
+    }
+
+    let key_images = self.entries.iter().map(|entry| entry.key_image).collect::<Vec<_>>();
+    // Don't let the same output be counted multiple times
+    if key_images.iter().map(|image| image.compress()).collect::<HashSet<_>>().len() !=
+      key_images.len()
+    {
+      None?;
+    }
+
+    let mut msg = message.to_vec();
+    msg.extend(address.spend.compress().to_bytes());
+    msg.extend(address.view.compress().to_bytes());
+    for key_image in &key_images {
+      msg.extend(key_image.compress().to_bytes());
+    }
+    let msg = hash(&msg);
+
+    let spend_keys = self.spend_keys.iter().map(|(key, _)| key.compress()).collect::<HashSet<_>>();
+    if !self.spend_keys.iter().all(|(key, sig)| verify_schnorr(&msg, *key, sig)) {
+      None?;
+    }
+    // wallet2 always proves the address's own spend key, even if no outputs were received by it
+    if !spend_keys.contains(&address.spend.compress()) {
+      None?;
+    }
+
+    let txs = txs.iter().map(|tx| (tx.hash(), tx)).collect::<HashMap<_, _>>();
+    let mut res = Vec::with_capacity(self.entries.len());
+    for entry in &self.entries {
+      let tx = txs.get(&entry.tx)?;
+      let o = usize::try_from(entry.o).ok()?;
+      let output_key = tx.prefix.outputs.get(o)?.key.decompress()?;
+
+      let keys = tx_keys(tx).ok()?;
+      let shared_secret_valid = |tx_key| {
+        verify_tx_proof(
+          &msg,
+          address.view,
+          tx_key,
+          None,
+          entry.shared_secret,
+          &entry.shared_secret_signature,
+        )
+      };
+      if !(shared_secret_valid(keys[0]) ||
+        (((keys.len() - 1) == tx.prefix.outputs.len()) && shared_secret_valid(keys[o + 1])))
+      {
+        None?;
+      }
+
+      if !(entry.key_image.is_torsion_free() &&
+        RingSignature { sigs: vec![entry.key_image_signature.clone()] }.verify(
+          &msg,
+          &[output_key],
+          &entry.key_image,
+        ))
+      {
+        None?;
+      }
+
+      // Check the output was received by one of the proven spend keys
+      let (_, shared_key, _) = shared_key(None, entry.shared_secret, o);
+      if !spend_keys.contains(&(output_key - (&shared_key * &ED25519_BASEPOINT_TABLE)).compress()) {
+        None?;
+      }
+
+      res.push((entry.key_image, output_amount(tx, o, shared_key)?));
+    }
+    Some(res)
+  }
+
+  pub fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+    write_vec(ReserveProofEntry::write, &self.entries, w)?;
+    write_vec(
+      |(key, sig): &(EdwardsPoint, Signature), w: &mut W| {
+        // Each pair is serialized as an array of two elements
+        write_varint(&2, w)?;
+        write_point(key, w)?;
+        sig.write(w)
+      },
+      &self.spend_keys,
+      w,
+    )
+  }
+
+  pub fn serialize(&self) -> Vec<u8> {
+    let mut serialized = vec![];
+    self.write(&mut serialized).unwrap();
+    serialized
+  }
+
+  pub fn read<R: Read>(r: &mut R) -> io::Result<ReserveProof> {
+    Ok(ReserveProof {
+      entries: read_vec(ReserveProofEntry::read, r)?,
+      spend_keys: read_vec(
+        |r| {
+          if read_varint(r)? != 2 {
+            Err(io::Error::new(io::ErrorKind::Other, "invalid pair"))?;
+          }
+          Ok((read_point(r)?, Signature::read(r)?))
+        },
+        r,
+      )?,
+    })
+  }
+}
+
+impl fmt::Display for ReserveProof {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{RESERVE_PROOF_HEADER}{}", encode(&self.serialize()).unwrap())
+  }
+}
+
+impl FromStr for ReserveProof {
+  type Err = ProofError;
+  fn from_str(s: &str) -> Result<ReserveProof, ProofError> {
+    let data = s.strip_prefix(RESERVE_PROOF_HEADER).ok_or(ProofError::InvalidEncoding)?;
+    let data = decode(data).map_err(|_| ProofError::InvalidEncoding)?;
+    let mut reader = data.as_slice();
+    let proof = ReserveProof::read(&mut reader).map_err(|_| ProofError::InvalidEncoding)?;
+    if !reader.is_empty() {
+      Err(ProofError::InvalidEncoding)?;
+    }
+    Ok(proof)
+  }
+}
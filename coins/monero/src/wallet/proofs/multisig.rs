@@ -0,0 +1,465 @@
+use core::ops::Deref;
+use std::{
+  io::{self, Read},
+  collections::HashMap,
+};
+
+use zeroize::Zeroizing;
+
+use rand_core::{RngCore, CryptoRng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+
+use group::{ff::Field, Group};
+use curve25519_dalek::{traits::IsIdentity, scalar::Scalar, edwards::EdwardsPoint};
+use dalek_ff_group as dfg;
+
+use transcript::{Transcript, RecommendedTranscript};
+use frost::{
+  curve::Ed25519,
+  Participant, FrostError, ThresholdKeys, ThresholdView,
+  algorithm::Algorithm,
+  sign::{
+    Preprocess, CachedPreprocess, SignatureShare, PreprocessMachine, SignMachine, SignatureMachine,
+    AlgorithmMachine, AlgorithmSignMachine, AlgorithmSignatureMachine,
+  },
+};
+
+use crate::{
+  ring_signatures::{Signature, RingSignature},
+  ringct::hash_to_point,
+  transaction::Transaction,
+  verify::RingProvider,
+  wallet::{
+    ViewPair, ReceivedOutput,
+    proofs::{
+      ProofError, SpendProof, ReserveProof, ReserveOutput, tx_proof_message, rings,
+      schnorr_challenge, verify_schnorr,
+    },
+  },
+};
+
+mod sealed {
+  use crate::ring_signatures::Signature;
+
+  /// A proof which can be produced by a threshold multisig wallet.
+  pub trait ThresholdProof: Clone + PartialEq + core::fmt::Debug + Send + Sync {
+    /// Fill in the signatures produced by the multisig, in the order they were requested.
+    fn complete(self, signatures: Vec<Vec<Signature>>) -> Self;
+  }
+}
+use sealed::ThresholdProof;
+
+impl ThresholdProof for SpendProof {
+  fn complete(self, signatures: Vec<Vec<Signature>>) -> SpendProof {
+    SpendProof { signatures: signatures.concat() }
+  }
+}
+
+impl ThresholdProof for ReserveProof {
+  fn complete(mut self, signatures: Vec<Vec<Signature>>) -> ReserveProof {
+    let mut signatures = signatures.into_iter();
+    for entry in self.entries.iter_mut() {
+      entry.key_image_signature = signatures.next().unwrap().swap_remove(0);
+    }
+    for (_, signature) in self.spend_keys.iter_mut() {
+      *signature = signatures.next().unwrap().swap_remove(0);
+    }
+    self
+  }
+}
+
+#[derive(Clone, Debug)]
+enum Statement {
+  // A ring signature, where the multisig's key is the ring member at index i
+  Ring { ring: Vec<EdwardsPoint>, i: usize, key_image: EdwardsPoint },
+  // A signature by the multisig's key, as created by wallet2's generate_signature
+  Schnorr,
+}
+
+/// FROST algorithm for producing a signature within a proof.
+#[derive(Clone, Debug)]
+struct ProofSignatureMultisig {
+  transcript: RecommendedTranscript,
+  statement: Statement,
+
+  msg: Option<[u8; 32]>,
+  // The signature, without the response for the multisig's key, and the challenge for it
+  interim: Option<(Vec<Signature>, Scalar)>,
+}
+
+impl ProofSignatureMultisig {
+  fn new(mut transcript: RecommendedTranscript, statement: Statement) -> ProofSignatureMultisig {
+    transcript.domain_separate(b"monero_proof_signature");
+    match &statement {
+      Statement::Ring { ring, i, key_image } => {
+        transcript.append_message(b"statement", b"ring");
+        transcript.append_message(b"real_spend", u64::try_from(*i).unwrap().to_le_bytes());
+        for member in ring {
+          transcript.append_message(b"member", member.compress().to_bytes());
+        }
+        transcript.append_message(b"key_image", key_image.compress().to_bytes());
+      }
+      Statement::Schnorr => transcript.append_message(b"statement", b"schnorr"),
+    }
+    ProofSignatureMultisig { transcript, statement, msg: None, interim: None }
+  }
+
+  // Index of the multisig's signature within the resulting signatures
+  fn i(&self) -> usize {
+    match self.statement {
+      Statement::Ring { i, .. } => i,
+      Statement::Schnorr => 0,
+    }
+  }
+}
+
+impl Algorithm<Ed25519> for ProofSignatureMultisig {
+  type Transcript = RecommendedTranscript;
+  type Addendum = ();
+  type Signature = Vec<Signature>;
+
+  fn nonces(&self) -> Vec<Vec<dfg::EdwardsPoint>> {
+    match &self.statement {
+      Statement::Ring { ring, i, .. } => {
+        vec![vec![dfg::EdwardsPoint::generator(), dfg::EdwardsPoint(hash_to_point(ring[*i]))]]
+      }
+      Statement::Schnorr => vec![vec![dfg::EdwardsPoint::generator()]],
+    }
+  }
+
+  fn preprocess_addendum<R: RngCore + CryptoRng>(&mut self, _: &mut R, _: &ThresholdKeys<Ed25519>) {
+  }
+
+  fn read_addendum<R: Read>(&self, _: &mut R) -> io::Result<()> {
+    Ok(())
+  }
+
+  fn process_addendum(
+    &mut self,
+    _: &ThresholdView<Ed25519>,
+    _: Participant,
+    _: (),
+  ) -> Result<(), FrostError> {
+    Ok(())
+  }
+
+  fn transcript(&mut self) -> &mut Self::Transcript {
+    &mut self.transcript
+  }
+
+  fn sign_share(
+    &mut self,
+    view: &ThresholdView<Ed25519>,
+    nonce_sums: &[Vec<dfg::EdwardsPoint>],
+    nonces: Vec<Zeroizing<dfg::Scalar>>,
+    msg: &[u8],
+  ) -> dfg::Scalar {
+    self.msg = Some(msg.try_into().expect("proof message should be 32-bytes"));
+    let msg = self.msg.as_ref().unwrap();
+
+    let (signatures, c) = match &self.statement {
+      Statement::Ring { ring, i, key_image } => {
+        // As with CLSAG, the transcript is used to seed the responses for the decoys
+        let mut rng = ChaCha20Rng::from_seed(self.transcript.rng_seed(b"decoy_responses"));
+        let (signature, c) = RingSignature::sign_core(
+          &mut rng,
+          msg,
+          ring,
+          *i,
+          key_image,
+          nonce_sums[0][0].0,
+          nonce_sums[0][1].0,
+        );
+        (signature.sigs, c)
+      }
+      Statement::Schnorr => {
+        let c = schnorr_challenge(msg, view.group_key().0, nonce_sums[0][0].0);
+        (vec![Signature { c, r: Scalar::zero() }], c)
+      }
+    };
+    self.interim = Some((signatures, c));
+
+    (-(dfg::Scalar(c) * view.secret_share().deref())) + nonces[0].deref()
+  }
+
+  #[must_use]
+  fn verify(
+    &self,
+    group_key: dfg::EdwardsPoint,
+    _: &[Vec<dfg::EdwardsPoint>],
+    sum: dfg::Scalar,
+  ) -> Option<Self::Signature> {
+    let msg = self.msg.as_ref().unwrap();
+    let mut signatures = self.interim.as_ref().unwrap().0.clone();
+    signatures[self.i()].r = sum.0;
+
+    let valid = match &self.statement {
+      Statement::Ring { ring, key_image, .. } => {
+        RingSignature { sigs: signatures.clone() }.verify(msg, ring, key_image)
+      }
+      Statement::Schnorr => verify_schnorr(msg, group_key.0, &signatures[0]),
+    };
+    Some(signatures).filter(|_| valid)
+  }
+
+  fn verify_share(
+    &self,
+    verification_share: dfg::EdwardsPoint,
+    nonces: &[Vec<dfg::EdwardsPoint>],
+    share: dfg::Scalar,
+  ) -> Result<Vec<(dfg::Scalar, dfg::EdwardsPoint)>, ()> {
+    let c = self.interim.as_ref().unwrap().1;
+    Ok(vec![
+      (share, dfg::EdwardsPoint::generator()),
+      (dfg::Scalar(c), verification_share),
+      (-dfg::Scalar::ONE, nonces[0][0]),
+    ])
+  }
+}
+
+/// FROST signing machine to produce a proof.
+pub struct ProofMachine<P: ThresholdProof> {
+  proof: P,
+  msg: [u8; 32],
+  i: Participant,
+  signatures: Vec<AlgorithmMachine<Ed25519, ProofSignatureMultisig>>,
+}
+
+pub struct ProofSignMachine<P: ThresholdProof> {
+  proof: P,
+  msg: [u8; 32],
+  i: Participant,
+  signatures: Vec<AlgorithmSignMachine<Ed25519, ProofSignatureMultisig>>,
+}
+
+pub struct ProofSignatureMachine<P: ThresholdProof> {
+  proof: P,
+  signatures: Vec<AlgorithmSignatureMachine<Ed25519, ProofSignatureMultisig>>,
+}
+
+impl<P: ThresholdProof> PreprocessMachine for ProofMachine<P> {
+  type Preprocess = Vec<Preprocess<Ed25519, ()>>;
+  type Signature = P;
+  type SignMachine = ProofSignMachine<P>;
+
+  fn preprocess<R: RngCore + CryptoRng>(
+    mut self,
+    rng: &mut R,
+  ) -> (ProofSignMachine<P>, Self::Preprocess) {
+    let mut preprocesses = Vec::with_capacity(self.signatures.len());
+    let signatures = self
+      .signatures
+      .drain(..)
+      .map(|signature| {
+        let (signature, preprocess) = signature.preprocess(rng);
+        preprocesses.push(preprocess);
+        signature
+      })
+      .collect();
+
+    (ProofSignMachine { proof: self.proof, msg: self.msg, i: self.i, signatures }, preprocesses)
+  }
+}
+
+impl<P: ThresholdProof> SignMachine<P> for ProofSignMachine<P> {
+  type Params = ();
+  type Keys = ThresholdKeys<Ed25519>;
+  type Preprocess = Vec<Preprocess<Ed25519, ()>>;
+  type SignatureShare = Vec<SignatureShare<Ed25519>>;
+  type SignatureMachine = ProofSignatureMachine<P>;
+
+  fn cache(self) -> CachedPreprocess {
+    unimplemented!(
+      "Monero proofs don't support caching their preprocesses due to {}",
+      "being already bound to a specific proof"
+    );
+  }
+
+  fn from_cache(_: (), _: ThresholdKeys<Ed25519>, _: CachedPreprocess) -> Result<Self, FrostError> {
+    unimplemented!(
+      "Monero proofs don't support caching their preprocesses due to {}",
+      "being already bound to a specific proof"
+    );
+  }
+
+  fn read_preprocess<R: Read>(&self, reader: &mut R) -> io::Result<Self::Preprocess> {
+    self.signatures.iter().map(|signature| signature.read_preprocess(reader)).collect()
+  }
+
+  fn sign(
+    mut self,
+    mut commitments: HashMap<Participant, Self::Preprocess>,
+    msg: &[u8],
+  ) -> Result<(ProofSignatureMachine<P>, Self::SignatureShare), FrostError> {
+    if !msg.is_empty() {
+      panic!("message was passed to the ProofMachine when it generates its own");
+    }
+    commitments.remove(&self.i);
+
+    let mut shares = Vec::with_capacity(self.signatures.len());
+    let signatures = self
+      .signatures
+      .drain(..)
+      .enumerate()
+      .map(|(c, signature)| {
+        let (signature, share) = signature.sign(
+          commitments
+            .iter()
+            .map(|(l, preprocesses)| {
+              Ok((*l, preprocesses.get(c).ok_or(FrostError::InvalidPreprocess(*l))?.clone()))
+            })
+            .collect::<Result<_, _>>()?,
+          &self.msg,
+        )?;
+        shares.push(share);
+        Ok(signature)
+      })
+      .collect::<Result<_, _>>()?;
+
+    Ok((ProofSignatureMachine { proof: self.proof, signatures }, shares))
+  }
+}
+
+impl<P: ThresholdProof> SignatureMachine<P> for ProofSignatureMachine<P> {
+  type SignatureShare = Vec<SignatureShare<Ed25519>>;
+
+  fn read_share<R: Read>(&self, reader: &mut R) -> io::Result<Self::SignatureShare> {
+    self.signatures.iter().map(|signature| signature.read_share(reader)).collect()
+  }
+
+  fn complete(
+    mut self,
+    shares: HashMap<Participant, Self::SignatureShare>,
+  ) -> Result<P, FrostError> {
+    let signatures = self
+      .signatures
+      .drain(..)
+      .enumerate()
+      .map(|(c, signature)| {
+        signature.complete(
+          shares
+            .iter()
+            .map(|(l, shares)| Ok((*l, shares.get(c).ok_or(FrostError::InvalidShare(*l))?.clone())))
+            .collect::<Result<_, _>>()?,
+        )
+      })
+      .collect::<Result<_, _>>()?;
+    Ok(self.proof.complete(signatures))
+  }
+}
+
+impl SpendProof {
+  /// Create a FROST signing machine to prove a transaction was sent by a multisig wallet, given
+  /// the outputs spent, in the order of the transaction's inputs.
+  pub async fn multisig<P: RingProvider>(
+    provider: &P,
+    keys: ThresholdKeys<Ed25519>,
+    mut transcript: RecommendedTranscript,
+    tx: &Transaction,
+    outputs: &[ReceivedOutput],
+    message: &[u8],
+  ) -> Result<ProofMachine<SpendProof>, ProofError> {
+    let rings = rings(provider, tx).await?;
+    if rings.len() != outputs.len() {
+      Err(ProofError::InvalidInput)?;
+    }
+
+    let msg = tx_proof_message(tx.hash(), message);
+    transcript.domain_separate(b"monero_spend_proof");
+    transcript.append_message(b"message", msg);
+
+    let i = keys.params().i();
+    let mut signatures = Vec::with_capacity(rings.len());
+    for ((ring, key_image), output) in rings.into_iter().zip(outputs) {
+      let i =
+        ring.iter().position(|member| *member == output.key()).ok_or(ProofError::InvalidInput)?;
+      let offset = keys.offset(dfg::Scalar(output.key_offset()));
+      if offset.group_key().0 != output.key() {
+        Err(ProofError::WrongPrivateKey)?;
+      }
+
+      signatures.push(AlgorithmMachine::new(
+        ProofSignatureMultisig::new(transcript.clone(), Statement::Ring { ring, i, key_image }),
+        offset,
+      ));
+    }
+
+    Ok(ProofMachine { proof: SpendProof { signatures: vec![] }, msg, i, signatures })
+  }
+}
+
+impl ReserveProof {
+  /// Create a FROST signing machine to prove a multisig wallet holds the specified outputs.
+  ///
+  /// The key images must be those calculated for these outputs by a `KeyImageMachine`.
+  pub fn multisig(
+    keys: ThresholdKeys<Ed25519>,
+    mut transcript: RecommendedTranscript,
+    pair: &ViewPair,
+    txs: &[Transaction],
+    outputs: &[ReceivedOutput],
+    key_images: &[EdwardsPoint],
+    message: &[u8],
+  ) -> Result<ProofMachine<ReserveProof>, ProofError> {
+    if keys.group_key().0 != pair.spend {
+      Err(ProofError::WrongPrivateKey)?;
+    }
+    if (key_images.len() != outputs.len()) ||
+      key_images.iter().any(|image| image.is_identity() || (!image.is_torsion_free()))
+    {
+      Err(ProofError::InvalidKeyImage)?;
+    }
+
+    let outputs = ReserveOutput::new(pair, txs, outputs)?;
+
+    transcript.domain_separate(b"monero_reserve_proof");
+    // The signatures for the shared secrets are created by every participant with the view key
+    // Seeding their nonces with it, along with the rest of the proof, keeps them deterministic
+    // without letting anyone lacking the view key recreate the nonces
+    transcript.append_message(b"view_key", Zeroizing::new(pair.view.to_bytes()));
+    transcript.append_message(b"message", message);
+    for (output, key_image) in outputs.iter().zip(key_images) {
+      transcript.append_message(b"output_hash", output.output.absolute.tx);
+      transcript.append_message(b"output_index", [output.output.absolute.o]);
+      transcript.append_message(b"key_image", key_image.compress().to_bytes());
+    }
+
+    let (proof, msg, subaddresses) = ReserveProof::unsigned(
+      &mut ChaCha20Rng::from_seed(transcript.rng_seed(b"shared_secret_signatures")),
+      pair,
+      &outputs,
+      key_images,
+      message,
+    );
+    transcript.append_message(b"proof", proof.serialize());
+
+    let i = keys.params().i();
+    let mut signatures = Vec::with_capacity(outputs.len() + subaddresses.len());
+    for (output, key_image) in outputs.iter().zip(key_images) {
+      let offset = keys.offset(dfg::Scalar(output.output.key_offset()));
+      if offset.group_key().0 != output.output.key() {
+        Err(ProofError::WrongPrivateKey)?;
+      }
+
+      signatures.push(AlgorithmMachine::new(
+        ProofSignatureMultisig::new(
+          transcript.clone(),
+          Statement::Ring { ring: vec![output.output.key()], i: 0, key_image: *key_image },
+        ),
+        offset,
+      ));
+    }
+    for index in subaddresses {
+      let offset = match index {
+        Some(index) => keys.offset(dfg::Scalar(pair.subaddress_derivation(index))),
+        None => keys.clone(),
+      };
+      signatures.push(AlgorithmMachine::new(
+        ProofSignatureMultisig::new(transcript.clone(), Statement::Schnorr),
+        offset,
+      ));
+    }
+
+    Ok(ProofMachine { proof, msg, i, signatures })
+  }
+}
@@ -8,12 +8,12 @@ use curve25519_dalek::{constants::ED25519_BASEPOINT_TABLE, scalar::Scalar, edwar
 use crate::{
   Commitment,
   serialize::{read_byte, read_u32, read_u64, read_bytes, read_scalar, read_point, read_raw_vec},
-  transaction::{Input, Timelock, Transaction},
+  transaction::{Timelock, Transaction},
   block::Block,
-  rpc::{Rpc, RpcError},
+  rpc::{RpcError, RpcConnection, Rpc},
   wallet::{
-    PaymentId, Extra, address::SubaddressIndex, Scanner, uniqueness, shared_key, amount_decryption,
-    commitment_mask,
+    PaymentId, Extra, address::SubaddressIndex, Scanner, Mempool, uniqueness, shared_key,
+    amount_decryption,
   },
 };
 
@@ -195,13 +195,19 @@ pub struct SpendableOutput {
 impl SpendableOutput {
   /// Update the spendable output's global index. This is intended to be called if a
   /// re-organization occurred.
-  pub async fn refresh_global_index(&mut self, rpc: &Rpc) -> Result<(), RpcError> {
+  pub async fn refresh_global_index<RPC: RpcConnection>(
+    &mut self,
+    rpc: &Rpc<RPC>,
+  ) -> Result<(), RpcError> {
     self.global_index =
       rpc.get_o_indexes(self.output.absolute.tx).await?[usize::from(self.output.absolute.o)];
     Ok(())
   }
 
-  pub async fn from(rpc: &Rpc, output: ReceivedOutput) -> Result<SpendableOutput, RpcError> {
+  pub async fn from<RPC: RpcConnection>(
+    rpc: &Rpc<RPC>,
+    output: ReceivedOutput,
+  ) -> Result<SpendableOutput, RpcError> {
     let mut output = SpendableOutput { output, global_index: 0 };
     output.refresh_global_index(rpc).await?;
     Ok(output)
@@ -239,7 +245,20 @@ impl SpendableOutput {
   }
 }
 
-/// A collection of timelocked outputs, either received or spendable.
+/// A received output whose transaction is in the mempool, and has yet to be confirmed.
+///
+/// Until its transaction is included in a block, this output has no global index and can't be
+/// spent. Its transaction may also never be confirmed, if it's double spent or dropped from the
+/// mempool, so it should solely be used to inform of pending payments.
+#[derive(Clone, PartialEq, Eq, Debug, Zeroize, ZeroizeOnDrop)]
+pub struct UnconfirmedOutput {
+  pub output: ReceivedOutput,
+  /// If another transaction in the mempool spends a key image also spent by this output's
+  /// transaction, making it impossible for both to be confirmed.
+  pub double_spent: bool,
+}
+
+/// A collection of timelocked outputs, either received, spendable, or unconfirmed.
 #[derive(Zeroize)]
 pub struct Timelocked<O: Clone + Zeroize>(Timelock, Vec<O>);
 impl<O: Clone + Zeroize> Drop for Timelocked<O> {
@@ -365,15 +384,17 @@ impl Scanner {
           commitment.amount = output.amount;
         // Regular transaction
         } else {
-          let amount = match tx.rct_signatures.base.ecdh_info.get(o) {
-            Some(amount) => amount_decryption(*amount, shared_key),
+          commitment = match tx.rct_signatures.base.ecdh_info.get(o) {
+            Some(amount) => match amount_decryption(amount, shared_key) {
+              Some(commitment) => commitment,
+              None => break,
+            },
             // This should never happen, yet it may be possible with miner transactions?
             // Using get just decreases the possibility of a panic and lets us move on in that case
             None => break,
           };
 
-          // Rebuild the commitment to verify it
-          commitment = Commitment::new(commitment_mask(shared_key), amount);
+          // Verify the rebuilt commitment
           // If this is a malicious commitment, move to the next output
           // Any other R value will calculate to a different spend key and are therefore ignorable
           if Some(&commitment.calculate()) != tx.rct_signatures.base.commitments.get(o) {
@@ -393,6 +414,9 @@ impl Scanner {
           if let Some(burning_bug) = self.burning_bug.as_mut() {
             burning_bug.insert(output.key);
           }
+
+          // Extend the lookahead, if this subaddress was beyond those previously in use
+          self.use_subaddress(subaddress);
         }
         // Break to prevent public keys from being included multiple times, triggering multiple
         // inclusions of the same output
@@ -406,17 +430,34 @@ impl Scanner {
   /// Scan a block to obtain its spendable outputs. Its the presence in a block giving these
   /// transactions their global index, and this must be batched as asking for the index of specific
   /// transactions is a dead giveaway for which transactions you successfully scanned. This
-  /// function obtains the output indexes for the miner transaction, incrementing from there
-  /// instead.
-  pub async fn scan(
+  /// function obtains the output indexes for the first RingCT transaction with outputs,
+  /// incrementing from there instead.
+  ///
+  /// Outputs of version 1 transactions are indexed per amount, not with the RingCT outputs, and
+  /// are accordingly skipped.
+  pub async fn scan<RPC: RpcConnection>(
     &mut self,
-    rpc: &Rpc,
+    rpc: &Rpc<RPC>,
     block: &Block,
   ) -> Result<Vec<Timelocked<SpendableOutput>>, RpcError> {
-    let mut index = rpc.get_o_indexes(block.miner_tx.hash()).await?[0];
     let mut txs = vec![block.miner_tx.clone()];
     txs.extend(rpc.get_transactions(&block.txs).await?);
 
+    // No RingCT outputs are present in this block
+    let index = if let Some(index) = first_output_index(rpc, &txs).await? {
+      index
+    } else {
+      return Ok(vec![]);
+    };
+    Ok(self.scan_transactions(&txs, index))
+  }
+
+  // Scan a block's transactions, where index is the global index of the first RingCT output
+  pub(crate) fn scan_transactions(
+    &mut self,
+    txs: &[Transaction],
+    mut index: u64,
+  ) -> Vec<Timelocked<SpendableOutput>> {
     let map = |mut timelock: Timelocked<ReceivedOutput>, index| {
       if timelock.1.is_empty() {
         None
@@ -436,23 +477,69 @@ impl Scanner {
     };
 
     let mut res = vec![];
-    for tx in txs.drain(..) {
-      if let Some(timelock) = map(self.scan_transaction(&tx), index) {
+    for tx in txs {
+      if tx.prefix.version != 2 {
+        continue;
+      }
+
+      if let Some(timelock) = map(self.scan_transaction(tx), index) {
         res.push(timelock);
       }
-      index += u64::try_from(
-        tx.prefix
-          .outputs
-          .iter()
-          // Filter to miner TX outputs/0-amount outputs since we're tacking the 0-amount index
-          // This will fail to scan blocks containing pre-RingCT miner TXs
-          .filter(|output| {
-            matches!(tx.prefix.inputs.get(0), Some(Input::Gen(..))) || (output.amount == 0)
-          })
-          .count(),
-      )
-      .unwrap()
+      // Every output of a RingCT transaction, including a RingCT miner transaction's, is tracked
+      // by the 0-amount index
+      index += u64::try_from(tx.prefix.outputs.len()).unwrap();
+    }
+    res
+  }
+
+  /// Scan the transactions in the mempool to discover outputs which have yet to be confirmed.
+  ///
+  /// Unlike scanning a block, this doesn't mark outputs found as received for the purposes of the
+  /// burning bug protection, so they'll still be found when their transaction is confirmed. Nor
+  /// does it extend the subaddress lookahead past what's in use on-chain, though outputs to
+  /// subaddresses within the lookahead of other outputs in the mempool are found.
+  pub fn scan_mempool(&mut self, mempool: &Mempool) -> Vec<Timelocked<UnconfirmedOutput>> {
+    let burning_bug = self.burning_bug.clone();
+    let (subaddresses, in_use, window) =
+      (self.subaddresses.clone(), self.in_use.clone(), self.window.clone());
+
+    let mut res = vec![];
+    for tx in mempool.transactions() {
+      if tx.prefix.version != 2 {
+        continue;
+      }
+
+      let mut outputs = self.scan_transaction(tx);
+      if outputs.1.is_empty() {
+        continue;
+      }
+      let double_spent = !mempool.double_spends(tx).is_empty();
+      res.push(Timelocked(
+        outputs.0,
+        outputs.1.drain(..).map(|output| UnconfirmedOutput { output, double_spent }).collect(),
+      ));
+    }
+
+    // Restore the burning bug protection, which was still used to deduplicate outputs within the
+    // mempool
+    self.burning_bug = burning_bug;
+    // Restore the lookahead, unregistering any subaddresses it was extended to
+    self.subaddresses = subaddresses;
+    self.in_use = in_use;
+    self.window = window;
+    res
+  }
+}
+
+// Get the global index of the first RingCT output within a block's transactions
+pub(crate) async fn first_output_index<RPC: RpcConnection>(
+  rpc: &Rpc<RPC>,
+  txs: &[Transaction],
+) -> Result<Option<u64>, RpcError> {
+  for tx in txs {
+    if (tx.prefix.version == 2) && (!tx.prefix.outputs.is_empty()) {
+      return Ok(Some(rpc.get_o_indexes(tx.hash()).await?[0]));
     }
-    Ok(res)
   }
+  Ok(None)
 }
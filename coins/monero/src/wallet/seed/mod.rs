@@ -8,6 +8,9 @@ use thiserror::Error;
 pub(crate) mod classic;
 use classic::{CLASSIC_SEED_LENGTH, CLASSIC_SEED_LENGTH_WITH_CHECKSUM, ClassicSeed};
 
+pub mod polyseed;
+use polyseed::{POLYSEED_LENGTH, Polyseed};
+
 /// Error when decoding a seed.
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Error)]
 pub enum SeedError {
@@ -21,6 +24,12 @@ pub enum SeedError {
   EnglishOldWithChecksum,
   #[error("invalid seed")]
   InvalidSeed,
+  #[error("unsupported features")]
+  UnsupportedFeatures,
+  #[error("polyseed is encrypted")]
+  EncryptedPolyseed,
+  #[error("polyseed isn't encrypted")]
+  UnencryptedPolyseed,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
@@ -41,16 +50,17 @@ pub enum Language {
 }
 
 /// A Monero seed.
-// TODO: Add polyseed to enum
 #[derive(Clone, PartialEq, Eq, Zeroize, ZeroizeOnDrop)]
 pub enum Seed {
   Classic(ClassicSeed),
+  Polyseed(Polyseed),
 }
 
 impl fmt::Debug for Seed {
   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
     match self {
       Seed::Classic(_) => f.debug_struct("Seed::Classic").finish_non_exhaustive(),
+      Seed::Polyseed(seed) => f.debug_tuple("Seed::Polyseed").field(seed).finish(),
     }
   }
 }
@@ -61,17 +71,25 @@ impl Seed {
     Seed::Classic(ClassicSeed::new(rng, lang))
   }
 
+  /// Create a new Polyseed.
+  pub fn new_polyseed<R: RngCore + CryptoRng>(rng: &mut R, lang: polyseed::Language) -> Seed {
+    Seed::Polyseed(Polyseed::new(rng, lang))
+  }
+
   /// Parse a seed from a String.
+  ///
+  /// Encrypted Polyseeds must be parsed with `Polyseed::from_encrypted_string`.
   pub fn from_string(words: Zeroizing<String>) -> Result<Seed, SeedError> {
     match words.split_whitespace().count() {
       CLASSIC_SEED_LENGTH | CLASSIC_SEED_LENGTH_WITH_CHECKSUM => {
         ClassicSeed::from_string(words).map(Seed::Classic)
       }
+      POLYSEED_LENGTH => Polyseed::from_string(words).map(Seed::Polyseed),
       _ => Err(SeedError::InvalidSeedLength)?,
     }
   }
 
-  /// Create a Seed from entropy.
+  /// Create a classic Seed from entropy.
   pub fn from_entropy(lang: Language, entropy: Zeroizing<[u8; 32]>) -> Option<Seed> {
     ClassicSeed::from_entropy(lang, entropy).map(Seed::Classic)
   }
@@ -80,6 +98,7 @@ impl Seed {
   pub fn to_string(&self) -> Zeroizing<String> {
     match self {
       Seed::Classic(seed) => seed.to_string(),
+      Seed::Polyseed(seed) => seed.to_string(),
     }
   }
 
@@ -87,6 +106,17 @@ impl Seed {
   pub fn entropy(&self) -> Zeroizing<[u8; 32]> {
     match self {
       Seed::Classic(seed) => seed.entropy(),
+      Seed::Polyseed(seed) => seed.entropy(),
+    }
+  }
+
+  /// Return the private spend key for this seed.
+  ///
+  /// Classic seeds directly use their entropy as the key, while Polyseeds derive it.
+  pub fn key(&self) -> Zeroizing<[u8; 32]> {
+    match self {
+      Seed::Classic(seed) => seed.entropy(),
+      Seed::Polyseed(seed) => seed.key(),
     }
   }
 }
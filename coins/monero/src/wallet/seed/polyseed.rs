@@ -0,0 +1,411 @@
+use core::fmt;
+use std::{
+  time::{SystemTime, UNIX_EPOCH},
+  collections::HashMap,
+};
+
+use lazy_static::lazy_static;
+
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+use rand_core::{RngCore, CryptoRng};
+
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+use sha2::Sha256;
+use pbkdf2::pbkdf2_hmac;
+
+use curve25519_dalek::scalar::Scalar;
+
+use crate::wallet::seed::SeedError;
+
+// Polyseed is specified at https://github.com/tevador/polyseed
+
+pub(crate) const POLYSEED_LENGTH: usize = 16;
+
+// The 150-bit secret, stored big-endian across 19 bytes, where the last byte only uses 6 bits
+const SECRET_BITS: usize = 150;
+const SECRET_SIZE: usize = (SECRET_BITS + 7) / 8;
+const LAST_BYTE_SECRET_BITS: usize = SECRET_BITS % 8;
+const CLEAR_MASK: u8 = (1 << LAST_BYTE_SECRET_BITS) - 1;
+
+// Each word encodes 11 bits, the first being a checksum and the rest being 10 bits of the secret
+// followed by one bit of the features/birthday
+const BITS_PER_WORD: usize = 11;
+const SECRET_BITS_PER_WORD: usize = 10;
+const POLY_NUM_CHECK_DIGITS: usize = 1;
+const DATA_WORDS: usize = POLYSEED_LENGTH - POLY_NUM_CHECK_DIGITS;
+
+// The birthday is encoded as the amount of time steps (roughly a month) since the epoch
+const POLYSEED_EPOCH: u64 = 1635768000; // 1st November 2021 12:00 UTC
+const TIME_STEP: u64 = 2629746; // 30.436875 days = 1/12 of the Gregorian year
+const DATE_BITS: usize = 10;
+const DATE_MASK: u16 = (1 << DATE_BITS) - 1;
+
+const FEATURE_BITS: usize = 5;
+const FEATURE_MASK: u8 = (1 << FEATURE_BITS) - 1;
+const ENCRYPTED_MASK: u8 = 1 << 4;
+// Only encryption is supported. The user features are reserved until a use is defined for them
+const RESERVED_FEATURES: u8 = FEATURE_MASK ^ ENCRYPTED_MASK;
+
+const KDF_ITERATIONS: u32 = 10000;
+const KEY_SALT: &[u8; 16] = b"POLYSEED key\0\xff\xff\xff";
+const MASK_SALT: &[u8; 16] = b"POLYSEED mask\0\xff\xff";
+
+// The coin is XOR'd into the checksummed data, so a seed for one coin isn't valid for another
+const MONERO_COIN: u16 = 0;
+
+/// The languages a Polyseed may be written in, which use the BIP-39 word lists.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum Language {
+  English,
+  Japanese,
+  Korean,
+  Spanish,
+  French,
+  Italian,
+  Czech,
+  Portuguese,
+  ChineseSimplified,
+  ChineseTraditional,
+}
+
+struct WordList {
+  words: Vec<String>,
+  // The key used for each word when looking up words
+  word_map: HashMap<String, usize>,
+  // If words may be shortened to their first 4 characters
+  has_prefix: bool,
+  // If accents may be omitted
+  has_accent: bool,
+  // If the phrase should be composed (NFC) when output
+  compose: bool,
+  separator: &'static str,
+}
+
+impl WordList {
+  fn new(
+    words: &'static str,
+    has_prefix: bool,
+    has_accent: bool,
+    compose: bool,
+    separator: &'static str,
+  ) -> WordList {
+    let mut list = WordList {
+      words: serde_json::from_str(words).unwrap(),
+      word_map: HashMap::new(),
+      has_prefix,
+      has_accent,
+      compose,
+      separator,
+    };
+
+    for (i, word) in list.words.iter().enumerate() {
+      list.word_map.insert(list.key(word).to_string(), i);
+    }
+    assert_eq!(list.word_map.len(), 1 << BITS_PER_WORD);
+
+    list
+  }
+
+  // The key to find a word by, which is its decomposed form without accents (if the language
+  // allows omitting them), trimmed to its prefix (if the language allows shortening words)
+  fn key(&self, word: &str) -> Zeroizing<String> {
+    let mut word = Zeroizing::new(word.nfkd().collect::<String>());
+    if self.has_accent {
+      word = Zeroizing::new(word.chars().filter(|c| !is_combining_mark(*c)).collect());
+    }
+    if self.has_prefix {
+      word = Zeroizing::new(word.chars().take(4).collect());
+    }
+    word
+  }
+}
+
+lazy_static! {
+  // Ordered as the reference implementation orders them, which is the order used to detect the
+  // language of a seed
+  static ref LANGUAGES: Vec<(Language, WordList)> = vec![
+    (Language::English, WordList::new(include_str!("./polyseed/en.json"), true, false, false, " ")),
+    (
+      Language::Japanese,
+      WordList::new(include_str!("./polyseed/ja.json"), false, false, true, "\u{3000}")
+    ),
+    (Language::Korean, WordList::new(include_str!("./polyseed/ko.json"), false, false, true, " ")),
+    (Language::Spanish, WordList::new(include_str!("./polyseed/es.json"), true, true, true, " ")),
+    (Language::French, WordList::new(include_str!("./polyseed/fr.json"), true, true, true, " ")),
+    (Language::Italian, WordList::new(include_str!("./polyseed/it.json"), true, false, false, " ")),
+    (Language::Czech, WordList::new(include_str!("./polyseed/cs.json"), true, false, false, " ")),
+    (
+      Language::Portuguese,
+      WordList::new(include_str!("./polyseed/pt.json"), true, false, false, " ")
+    ),
+    (
+      Language::ChineseSimplified,
+      WordList::new(include_str!("./polyseed/zh_simplified.json"), false, false, false, " ")
+    ),
+    (
+      Language::ChineseTraditional,
+      WordList::new(include_str!("./polyseed/zh_traditional.json"), false, false, false, " ")
+    ),
+  ];
+}
+
+fn word_list(lang: Language) -> &'static WordList {
+  &LANGUAGES.iter().find(|(list_lang, _)| *list_lang == lang).unwrap().1
+}
+
+/// Encode a timestamp as a birthday.
+fn birthday_encode(time: u64) -> u16 {
+  if time < POLYSEED_EPOCH {
+    return 0;
+  }
+  u16::try_from(((time - POLYSEED_EPOCH) / TIME_STEP) & u64::from(DATE_MASK)).unwrap()
+}
+
+/// Decode a birthday into a timestamp.
+fn birthday_decode(birthday: u16) -> u64 {
+  POLYSEED_EPOCH + (u64::from(birthday) * TIME_STEP)
+}
+
+// Multiply by 2 within GF(2^11)
+fn mul2(x: u16) -> u16 {
+  if (x & (1 << (BITS_PER_WORD - 1))) != 0 {
+    (x << 1) ^ 0x805
+  } else {
+    x << 1
+  }
+}
+
+// Evaluate the polynomial formed by the words at 2, which is 0 for a valid seed
+fn poly_eval(coeffs: &[u16; POLYSEED_LENGTH]) -> u16 {
+  let mut res = coeffs[POLYSEED_LENGTH - 1];
+  for coeff in coeffs[.. (POLYSEED_LENGTH - 1)].iter().rev() {
+    res = mul2(res) ^ coeff;
+  }
+  res
+}
+
+// The position of a bit of the secret within its byte, as bits are read big-endian and the last
+// byte only has 6 bits
+fn secret_bit_shift(bit: usize) -> usize {
+  let bits_in_byte = if (bit / 8) == (SECRET_SIZE - 1) { LAST_BYTE_SECRET_BITS } else { 8 };
+  bits_in_byte - 1 - (bit % 8)
+}
+
+/// A Polyseed, a 16-word seed which encodes its birthday and has a checksum word.
+#[derive(Clone, PartialEq, Eq, Zeroize, ZeroizeOnDrop)]
+pub struct Polyseed {
+  #[zeroize(skip)]
+  language: Language,
+  features: u8,
+  birthday: u16,
+  entropy: Zeroizing<[u8; 32]>,
+}
+
+impl fmt::Debug for Polyseed {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.debug_struct("Polyseed")
+      .field("language", &self.language)
+      .field("birthday", &self.birthday())
+      .finish_non_exhaustive()
+  }
+}
+
+impl Polyseed {
+  /// Create a new Polyseed, with a birthday of the current time.
+  pub fn new<R: RngCore + CryptoRng>(rng: &mut R, language: Language) -> Polyseed {
+    let mut entropy = Zeroizing::new([0; 32]);
+    rng.fill_bytes(&mut entropy[.. SECRET_SIZE]);
+    entropy[SECRET_SIZE - 1] &= CLEAR_MASK;
+
+    let time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    Polyseed::from(language, time, entropy).unwrap()
+  }
+
+  /// Create a Polyseed from its components.
+  ///
+  /// The birthday is a UNIX timestamp, which is rounded down to the nearest time step (roughly a
+  /// month). Returns an error if the entropy exceeds 150 bits.
+  pub fn from(
+    language: Language,
+    birthday: u64,
+    entropy: Zeroizing<[u8; 32]>,
+  ) -> Result<Polyseed, SeedError> {
+    if ((entropy[SECRET_SIZE - 1] & !CLEAR_MASK) != 0) ||
+      entropy[SECRET_SIZE ..].iter().any(|byte| *byte != 0)
+    {
+      Err(SeedError::InvalidSeed)?;
+    }
+
+    Ok(Polyseed { language, features: 0, birthday: birthday_encode(birthday), entropy })
+  }
+
+  fn decode(seed: &str) -> Result<Polyseed, SeedError> {
+    let words = seed.split_whitespace().collect::<Vec<_>>();
+    if words.len() != POLYSEED_LENGTH {
+      Err(SeedError::InvalidSeedLength)?;
+    }
+
+    // Find the first language which has every word, per the reference implementation
+    let mut language = None;
+    let mut coeffs = Zeroizing::new([0; POLYSEED_LENGTH]);
+    'language: for (lang, list) in LANGUAGES.iter() {
+      for (i, word) in words.iter().enumerate() {
+        if let Some(index) = list.word_map.get(list.key(word).as_str()) {
+          coeffs[i] = u16::try_from(*index).unwrap();
+        } else {
+          continue 'language;
+        }
+      }
+      language = Some(*lang);
+      break;
+    }
+    let language = language.ok_or(SeedError::UnknownLanguage)?;
+
+    coeffs[POLY_NUM_CHECK_DIGITS] ^= MONERO_COIN;
+    if poly_eval(&coeffs) != 0 {
+      Err(SeedError::InvalidChecksum)?;
+    }
+
+    let mut entropy = Zeroizing::new([0; 32]);
+    let mut extra = 0;
+    for (i, coeff) in coeffs[POLY_NUM_CHECK_DIGITS ..].iter().enumerate() {
+      extra = (extra << 1) | (coeff & 1);
+      let secret = coeff >> 1;
+      for b in 0 .. SECRET_BITS_PER_WORD {
+        let bit = (i * SECRET_BITS_PER_WORD) + b;
+        let value = u8::try_from((secret >> (SECRET_BITS_PER_WORD - 1 - b)) & 1).unwrap();
+        entropy[bit / 8] |= value << secret_bit_shift(bit);
+      }
+    }
+
+    Ok(Polyseed {
+      language,
+      features: u8::try_from(extra >> DATE_BITS).unwrap(),
+      birthday: extra & DATE_MASK,
+      entropy,
+    })
+  }
+
+  /// Parse an unencrypted Polyseed from a String.
+  pub fn from_string(seed: Zeroizing<String>) -> Result<Polyseed, SeedError> {
+    let seed = Self::decode(&seed)?;
+    if seed.is_encrypted() {
+      Err(SeedError::EncryptedPolyseed)?;
+    }
+    if (seed.features & RESERVED_FEATURES) != 0 {
+      Err(SeedError::UnsupportedFeatures)?;
+    }
+    Ok(seed)
+  }
+
+  /// Parse an encrypted Polyseed from a String, decrypting it with the passphrase.
+  ///
+  /// An incorrect passphrase will produce a distinct seed, not an error.
+  pub fn from_encrypted_string(
+    seed: Zeroizing<String>,
+    passphrase: &str,
+  ) -> Result<Polyseed, SeedError> {
+    let mut seed = Self::decode(&seed)?;
+    if !seed.is_encrypted() {
+      Err(SeedError::UnencryptedPolyseed)?;
+    }
+    if (seed.features & RESERVED_FEATURES) != 0 {
+      Err(SeedError::UnsupportedFeatures)?;
+    }
+    seed.crypt(passphrase);
+    Ok(seed)
+  }
+
+  fn is_encrypted(&self) -> bool {
+    (self.features & ENCRYPTED_MASK) != 0
+  }
+
+  // Encrypt or decrypt the secret, toggling the encrypted flag
+  fn crypt(&mut self, passphrase: &str) {
+    let passphrase = Zeroizing::new(passphrase.nfkd().collect::<String>());
+    let mut mask = Zeroizing::new([0; 32]);
+    pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), MASK_SALT, KDF_ITERATIONS, mask.as_mut());
+
+    for (byte, mask) in self.entropy[.. SECRET_SIZE].iter_mut().zip(mask.iter()) {
+      *byte ^= mask;
+    }
+    self.entropy[SECRET_SIZE - 1] &= CLEAR_MASK;
+    self.features ^= ENCRYPTED_MASK;
+  }
+
+  fn encode(&self) -> Zeroizing<String> {
+    let extra = (u16::from(self.features) << DATE_BITS) | self.birthday;
+
+    let mut coeffs = Zeroizing::new([0; POLYSEED_LENGTH]);
+    for i in 0 .. DATA_WORDS {
+      let mut word = 0;
+      for b in 0 .. SECRET_BITS_PER_WORD {
+        let bit = (i * SECRET_BITS_PER_WORD) + b;
+        word = (word << 1) | u16::from((self.entropy[bit / 8] >> secret_bit_shift(bit)) & 1);
+      }
+      word = (word << 1) | ((extra >> (DATA_WORDS - 1 - i)) & 1);
+      coeffs[POLY_NUM_CHECK_DIGITS + i] = word;
+    }
+    coeffs[0] = poly_eval(&coeffs);
+    coeffs[POLY_NUM_CHECK_DIGITS] ^= MONERO_COIN;
+
+    let list = word_list(self.language);
+    let mut res = Zeroizing::new(String::new());
+    for (i, coeff) in coeffs.iter().enumerate() {
+      if i != 0 {
+        *res += list.separator;
+      }
+      *res += &list.words[usize::from(*coeff)];
+    }
+    if list.compose {
+      res = Zeroizing::new(res.nfc().collect());
+    }
+    res
+  }
+
+  /// Convert this seed to a String.
+  pub fn to_string(&self) -> Zeroizing<String> {
+    self.encode()
+  }
+
+  /// Convert this seed to a String, encrypted with the passphrase.
+  pub fn to_encrypted_string(&self, passphrase: &str) -> Zeroizing<String> {
+    let mut encrypted = self.clone();
+    encrypted.crypt(passphrase);
+    encrypted.encode()
+  }
+
+  /// The language this seed is written in.
+  pub fn language(&self) -> Language {
+    self.language
+  }
+
+  /// The birthday of this seed, as a UNIX timestamp. This is the start of the time step the seed
+  /// was created within, and accordingly may be up to a month prior to its actual creation.
+  pub fn birthday(&self) -> u64 {
+    birthday_decode(self.birthday)
+  }
+
+  /// The 150 bits of entropy within this seed.
+  pub fn entropy(&self) -> Zeroizing<[u8; 32]> {
+    self.entropy.clone()
+  }
+
+  /// The private spend key derived from this seed.
+  pub fn key(&self) -> Zeroizing<[u8; 32]> {
+    let mut salt = [0; 32];
+    salt[.. 16].copy_from_slice(KEY_SALT);
+    salt[16 .. 20].copy_from_slice(&u32::from(MONERO_COIN).to_le_bytes());
+    salt[20 .. 24].copy_from_slice(&u32::from(self.birthday).to_le_bytes());
+    salt[24 .. 28].copy_from_slice(&u32::from(self.features).to_le_bytes());
+
+    let mut key = Zeroizing::new([0; 32]);
+    pbkdf2_hmac::<Sha256>(self.entropy.as_ref(), &salt, KDF_ITERATIONS, key.as_mut());
+
+    // Monero reduces the derived key to obtain a valid scalar
+    let scalar = Zeroizing::new(Scalar::from_bytes_mod_order(*key));
+    *key = scalar.to_bytes();
+    key
+  }
+}
@@ -129,6 +129,46 @@ impl SignableTransactionBuilder {
     Ok(self.shallow_copy())
   }
 
+  /// Build transactions sweeping every input to the specified address.
+  ///
+  /// A change address must have been specified, as Monero requires two outputs. Each transaction
+  /// sends a zero-amount output to it. No payments may have been added.
+  pub fn sweep(self, dest: MoneroAddress) -> Result<Vec<SignableTransaction>, TransactionError> {
+    self.sweep_internal(dest, |_| true)
+  }
+
+  /// Build transactions sweeping every input worth less than the threshold to the specified
+  /// address, ignoring all other inputs.
+  ///
+  /// This has the same requirements as `sweep`.
+  pub fn sweep_below(
+    self,
+    dest: MoneroAddress,
+    threshold: u64,
+  ) -> Result<Vec<SignableTransaction>, TransactionError> {
+    self.sweep_internal(dest, |input| input.commitment().amount < threshold)
+  }
+
+  fn sweep_internal(
+    self,
+    dest: MoneroAddress,
+    include: impl Fn(&SpendableOutput) -> bool,
+  ) -> Result<Vec<SignableTransaction>, TransactionError> {
+    let read = self.0.read().unwrap();
+    if !read.payments.is_empty() {
+      Err(TransactionError::PaymentsInSweep)?;
+    }
+    SignableTransaction::sweep(
+      read.protocol,
+      read.r_seed.clone(),
+      read.inputs.iter().filter(|input| include(input)).cloned().collect(),
+      dest,
+      read.change_address.clone().ok_or(TransactionError::NoChange)?,
+      read.data.clone(),
+      read.fee,
+    )
+  }
+
   pub fn build(self) -> Result<SignableTransaction, TransactionError> {
     let read = self.0.read().unwrap();
     SignableTransaction::new(
@@ -1,4 +1,4 @@
-use core::{ops::Deref, fmt};
+use core::{ops::Deref, cmp::Reverse, fmt};
 use std::io;
 
 use thiserror::Error;
@@ -23,17 +23,17 @@ use frost::FrostError;
 use crate::{
   Protocol, Commitment, hash, random_scalar,
   serialize::{
-    read_byte, read_bytes, read_u64, read_scalar, read_point, read_vec, write_byte, write_scalar,
-    write_point, write_raw_vec, write_vec,
+    varint_len, read_byte, read_bytes, read_u64, read_scalar, read_point, read_vec, write_byte,
+    write_scalar, write_point, write_raw_vec, write_vec,
   },
   ringct::{
     generate_key_image,
     clsag::{ClsagError, ClsagInput, Clsag},
     bulletproofs::{MAX_OUTPUTS, Bulletproofs},
-    RctBase, RctPrunable, RctSignatures,
+    RctBase, RctPrunable, RctSignatures, EncryptedAmount,
   },
   transaction::{Input, Output, Timelock, TransactionPrefix, Transaction},
-  rpc::{Rpc, RpcError},
+  rpc::{RpcError, RpcConnection, Rpc},
   wallet::{
     address::{Network, AddressSpec, MoneroAddress},
     ViewPair, SpendableOutput, Decoys, PaymentId, ExtraField, Extra, key_image_sort, uniqueness,
@@ -45,6 +45,9 @@ use crate::{
 mod builder;
 pub use builder::SignableTransactionBuilder;
 
+mod unsigned;
+pub use unsigned::UnsignedTransaction;
+
 #[cfg(feature = "multisig")]
 mod multisig;
 #[cfg(feature = "multisig")]
@@ -116,6 +119,13 @@ impl SendOutput {
   }
 }
 
+// https://github.com/monero-project/monero/pull/8733
+const MAX_EXTRA_SIZE: usize = 1060;
+
+// The actual limit is half the block size, and for the minimum block size of 300k, that'd be 150k
+// wallet2 will only create transactions up to 100k bytes however
+const MAX_TX_SIZE: usize = 100_000;
+
 #[derive(Clone, PartialEq, Eq, Debug, Error)]
 pub enum TransactionError {
   #[error("multiple addresses with payment IDs")]
@@ -126,12 +136,14 @@ pub enum TransactionError {
   NoOutputs,
   #[error("only one output and no change address")]
   NoChange,
+  #[error("payments were specified for a sweep")]
+  PaymentsInSweep,
   #[error("too many outputs")]
   TooManyOutputs,
   #[error("too much data")]
   TooMuchData,
-  #[error("too many inputs/too much arbitrary data")]
-  TooLargeTransaction,
+  #[error("too large transaction (weight {0}, limit {})", MAX_TX_SIZE)]
+  TooLargeTransaction(usize),
   #[error("not enough funds (in {0}, out {1})")]
   NotEnoughFunds(u64, u64),
   #[error("wrong spend private key")]
@@ -147,26 +159,24 @@ pub enum TransactionError {
   FrostError(FrostError),
 }
 
-async fn prepare_inputs<R: RngCore + CryptoRng>(
-  rng: &mut R,
-  rpc: &Rpc,
+fn prepare_inputs(
   ring_len: usize,
   inputs: &[SpendableOutput],
+  decoys: &[Decoys],
   spend: &Zeroizing<Scalar>,
   tx: &mut Transaction,
 ) -> Result<Vec<(Zeroizing<Scalar>, EdwardsPoint, ClsagInput)>, TransactionError> {
   let mut signable = Vec::with_capacity(inputs.len());
 
-  // Select decoys
-  let decoys = Decoys::select(
-    rng,
-    rpc,
-    ring_len,
-    rpc.get_height().await.map_err(TransactionError::RpcError)? - 1,
-    inputs,
-  )
-  .await
-  .map_err(TransactionError::RpcError)?;
+  // The decoys may have been selected by another party, so make sure they're usable
+  if decoys.len() != inputs.len() {
+    Err(TransactionError::ClsagError(ClsagError::InvalidRing))?;
+  }
+  for (input, decoys) in inputs.iter().zip(decoys) {
+    if (decoys.len() != ring_len) || (decoys.ring[usize::from(decoys.i)][0] != input.key()) {
+      Err(TransactionError::ClsagError(ClsagError::InvalidRing))?;
+    }
+  }
 
   for (i, input) in inputs.iter().enumerate() {
     let input_spend = Zeroizing::new(input.key_offset() + spend.deref());
@@ -216,6 +226,61 @@ pub(crate) enum InternalPayment {
   Change(Change, u64),
 }
 
+impl InternalPayment {
+  fn address(&self) -> MoneroAddress {
+    match self {
+      InternalPayment::Payment(payment) => payment.0,
+      InternalPayment::Change(change, _) => change.address,
+    }
+  }
+
+  fn write<W: io::Write>(&self, w: &mut W) -> io::Result<()> {
+    match self {
+      InternalPayment::Payment(payment) => {
+        w.write_all(&[0])?;
+        write_vec(write_byte, payment.0.to_string().as_bytes(), w)?;
+        w.write_all(&payment.1.to_le_bytes())
+      }
+      InternalPayment::Change(change, amount) => {
+        w.write_all(&[1])?;
+        write_vec(write_byte, change.address.to_string().as_bytes(), w)?;
+        if let Some(view) = change.view.as_ref() {
+          w.write_all(&[1])?;
+          write_scalar(view, w)?;
+        } else {
+          w.write_all(&[0])?;
+        }
+        w.write_all(&amount.to_le_bytes())
+      }
+    }
+  }
+
+  fn read<R: io::Read>(r: &mut R) -> io::Result<InternalPayment> {
+    fn read_address<R: io::Read>(r: &mut R) -> io::Result<MoneroAddress> {
+      String::from_utf8(read_vec(read_byte, r)?)
+        .ok()
+        .and_then(|str| MoneroAddress::from_str_raw(&str).ok())
+        .ok_or(io::Error::new(io::ErrorKind::Other, "invalid address"))
+    }
+
+    Ok(match read_byte(r)? {
+      0 => InternalPayment::Payment((read_address(r)?, read_u64(r)?)),
+      1 => InternalPayment::Change(
+        Change {
+          address: read_address(r)?,
+          view: match read_byte(r)? {
+            0 => None,
+            1 => Some(Zeroizing::new(read_scalar(r)?)),
+            _ => Err(io::Error::new(io::ErrorKind::Other, "invalid change payment"))?,
+          },
+        },
+        read_u64(r)?,
+      ),
+      _ => Err(io::Error::new(io::ErrorKind::Other, "invalid payment"))?,
+    })
+  }
+}
+
 /// The eventual output of a SignableTransaction.
 ///
 /// If the SignableTransaction has a Change with a view key, this will also have the view key.
@@ -295,99 +360,263 @@ impl SignableTransaction {
     data: Vec<Vec<u8>>,
     fee_rate: Fee,
   ) -> Result<SignableTransaction, TransactionError> {
+    let mut payments = payments.drain(..).map(InternalPayment::Payment).collect::<Vec<_>>();
+    // The change output's amount is set once the fee is known
+    if let Some(change) = change_address {
+      payments.push(InternalPayment::Change(change, 0));
+    }
+
+    let (_, fee) = Self::weight_and_fee(protocol, inputs.len(), &payments, &data, fee_rate)?;
+
+    // Make sure we have enough funds
+    let in_amount = inputs.iter().map(|input| input.commitment().amount).sum::<u64>();
+    let out_amount = payments
+      .iter()
+      .map(|payment| match payment {
+        InternalPayment::Payment(payment) => payment.1,
+        InternalPayment::Change(_, _) => 0,
+      })
+      .sum::<u64>() +
+      fee;
+    if in_amount < out_amount {
+      Err(TransactionError::NotEnoughFunds(in_amount, out_amount))?;
+    }
+
+    if let Some(InternalPayment::Change(_, amount)) = payments.last_mut() {
+      *amount = in_amount - out_amount;
+    }
+
+    Ok(SignableTransaction { protocol, r_seed, inputs, payments, data, fee })
+  }
+
+  /// Calculate the fee a transaction will pay, as `new` would, without its inputs or decoys.
+  ///
+  /// Only the amount of inputs is needed, as their amounts and rings don't affect the fee. The
+  /// payments are solely the addresses paid, as their amounts don't affect the fee either.
+  pub fn necessary_fee(
+    protocol: Protocol,
+    inputs: usize,
+    payments: &[MoneroAddress],
+    change_address: Option<&Change>,
+    data: &[Vec<u8>],
+    fee_rate: Fee,
+  ) -> Result<u64, TransactionError> {
+    let mut payments =
+      payments.iter().map(|address| InternalPayment::Payment((*address, 0))).collect::<Vec<_>>();
+    if let Some(change) = change_address {
+      payments.push(InternalPayment::Change(change.clone(), 0));
+    }
+    Ok(Self::weight_and_fee(protocol, inputs, &payments, data, fee_rate)?.1)
+  }
+
+  // Check a transaction with the specified payments and data is valid, returning its weight and
+  // the fee it'll pay
+  // The amounts of the payments, including the change output, are ignored
+  fn weight_and_fee(
+    protocol: Protocol,
+    inputs: usize,
+    payments: &[InternalPayment],
+    data: &[Vec<u8>],
+    fee_rate: Fee,
+  ) -> Result<(usize, u64), TransactionError> {
     // Make sure there's only one payment ID
-    let mut has_payment_id = {
-      let mut payment_ids = 0;
-      let mut count = |addr: MoneroAddress| {
-        if addr.payment_id().is_some() {
-          payment_ids += 1
-        }
-      };
-      for payment in &payments {
-        count(payment.0);
-      }
-      if let Some(change) = change_address.as_ref() {
-        count(change.address);
-      }
-      if payment_ids > 1 {
-        Err(TransactionError::MultiplePaymentIds)?;
-      }
-      payment_ids == 1
-    };
+    if payments.iter().filter(|payment| payment.address().payment_id().is_some()).count() > 1 {
+      Err(TransactionError::MultiplePaymentIds)?;
+    }
 
-    if inputs.is_empty() {
+    if inputs == 0 {
       Err(TransactionError::NoInputs)?;
     }
-    if payments.is_empty() {
+    if !payments.iter().any(|payment| matches!(payment, InternalPayment::Payment(_))) {
       Err(TransactionError::NoOutputs)?;
     }
 
-    for part in &data {
+    for part in data {
       if part.len() > MAX_ARBITRARY_DATA_SIZE {
         Err(TransactionError::TooMuchData)?;
       }
     }
 
     // If we don't have two outputs, as required by Monero, error
-    if (payments.len() == 1) && change_address.is_none() {
+    if payments.len() == 1 {
       Err(TransactionError::NoChange)?;
     }
-    let outputs = payments.len() + usize::from(change_address.is_some());
-    // Add a dummy payment ID if there's only 2 payments
-    has_payment_id |= outputs == 2;
-
-    // Calculate the extra length
-    // Assume additional keys are needed in order to cause a worst-case estimation
-    let extra = Extra::fee_weight(outputs, true, has_payment_id, data.as_ref());
+    if payments.len() > MAX_OUTPUTS {
+      Err(TransactionError::TooManyOutputs)?;
+    }
 
-    // https://github.com/monero-project/monero/pull/8733
-    const MAX_EXTRA_SIZE: usize = 1060;
-    if extra > MAX_EXTRA_SIZE {
+    if Self::extra_weight(payments, data) > MAX_EXTRA_SIZE {
       Err(TransactionError::TooMuchData)?;
     }
 
-    // This is a extremely heavy fee weight estimation which can only be trusted for two things
-    // 1) Ensuring we have enough for whatever fee we end up using
-    // 2) Ensuring we aren't over the max size
-    let estimated_tx_size = Transaction::fee_weight(protocol, inputs.len(), outputs, extra);
+    // The fee is encoded as a VarInt, so the weight depends on the fee
+    // As a longer fee can only increase the fee, this converges within a few iterations
+    let mut fee = 0;
+    loop {
+      let weight = Self::calculate_weight(protocol, inputs, payments, data, fee);
+      let necessary = fee_rate.calculate(weight);
+      if varint_len(usize::try_from(necessary).unwrap()) ==
+        varint_len(usize::try_from(fee).unwrap())
+      {
+        if weight >= MAX_TX_SIZE {
+          Err(TransactionError::TooLargeTransaction(weight))?;
+        }
+        return Ok((weight, necessary));
+      }
+      fee = necessary;
+    }
+  }
 
-    // The actual limit is half the block size, and for the minimum block size of 300k, that'd be
-    // 150k
-    // wallet2 will only create transactions up to 100k bytes however
-    const MAX_TX_SIZE: usize = 100_000;
+  // The length of the TX extra for the specified payments and data
+  fn extra_weight(payments: &[InternalPayment], data: &[Vec<u8>]) -> usize {
+    // A dummy payment ID is added if there's only two outputs
+    let payment_id = (payments.len() == 2) ||
+      payments.iter().any(|payment| payment.address().payment_id().is_some());
+    Extra::fee_weight(payments.len(), Self::subaddresses(payments).1, payment_id, data)
+  }
 
-    // This uses the weight (estimated_tx_size) despite the BP clawback
-    // The clawback *increases* the weight, so this will over-estimate, yet it's still safe
-    if estimated_tx_size >= MAX_TX_SIZE {
-      Err(TransactionError::TooLargeTransaction)?;
-    }
+  // The weight of a transaction with the specified structure
+  // This is exact except for the key offsets of the inputs, which are assumed to be their maximum
+  // lengths
+  fn calculate_weight(
+    protocol: Protocol,
+    inputs: usize,
+    payments: &[InternalPayment],
+    data: &[Vec<u8>],
+    fee: u64,
+  ) -> usize {
+    Transaction::fee_weight(
+      protocol,
+      inputs,
+      payments.len(),
+      Self::extra_weight(payments, data),
+      fee,
+    )
+  }
 
-    // Calculate the fee.
-    let fee = fee_rate.calculate(estimated_tx_size);
+  // Returns if any payments are to subaddresses, and if additional keys are needed for them
+  fn subaddresses(payments: &[InternalPayment]) -> (bool, bool) {
+    // If any of these outputs are to a subaddress, we need keys distinct to them
+    // The only time this *does not* force having additional keys is when the only other output
+    // is a change output we have the view key for, enabling rewriting rA to aR
+    let mut has_change_view = false;
+    let subaddresses = payments
+      .iter()
+      .filter(|payment| match *payment {
+        InternalPayment::Payment(payment) => payment.0.is_subaddress(),
+        InternalPayment::Change(change, _) => {
+          if change.view.is_some() {
+            has_change_view = true;
+            // It should not be possible to construct a change specification to a subaddress with a
+            // view key
+            debug_assert!(!change.address.is_subaddress());
+          }
+          change.address.is_subaddress()
+        }
+      })
+      .count() !=
+      0;
 
-    // Make sure we have enough funds
-    let in_amount = inputs.iter().map(|input| input.commitment().amount).sum::<u64>();
-    let out_amount = payments.iter().map(|payment| payment.1).sum::<u64>() + fee;
-    if in_amount < out_amount {
-      Err(TransactionError::NotEnoughFunds(in_amount, out_amount))?;
+    // We need additional keys if we have any subaddresses
+    let mut additional = subaddresses;
+    // Unless the above change view key path is taken
+    if (payments.len() == 2) && has_change_view {
+      additional = false;
     }
+    (subaddresses, additional)
+  }
 
-    if outputs > MAX_OUTPUTS {
-      Err(TransactionError::TooManyOutputs)?;
+  /// Create signable transactions sweeping the specified inputs to an address.
+  ///
+  /// Each transaction sends the value of its inputs, minus its fee, to the destination. As Monero
+  /// requires two outputs, each also has a zero-amount output to the change address. Inputs are
+  /// split across as few transactions as possible while remaining under the size limit, with the
+  /// inputs evenly distributed, by both count and value, across them.
+  ///
+  /// The data is included in every transaction.
+  pub fn sweep(
+    protocol: Protocol,
+    r_seed: Option<Zeroizing<[u8; 32]>>,
+    mut inputs: Vec<SpendableOutput>,
+    dest: MoneroAddress,
+    change_address: Change,
+    data: Vec<Vec<u8>>,
+    fee_rate: Fee,
+  ) -> Result<Vec<SignableTransaction>, TransactionError> {
+    let payments =
+      [InternalPayment::Payment((dest, 0)), InternalPayment::Change(change_address.clone(), 0)];
+
+    // Find the most inputs a transaction can have while remaining under the size limit
+    let mut max_inputs = inputs.len();
+    loop {
+      match Self::weight_and_fee(protocol, max_inputs, &payments, &data, fee_rate) {
+        Ok(_) => break,
+        // If not even a single input fits, this will eventually error with NoInputs
+        Err(TransactionError::TooLargeTransaction(_)) => max_inputs -= 1,
+        Err(e) => Err(e)?,
+      }
     }
 
-    let mut payments = payments.drain(..).map(InternalPayment::Payment).collect::<Vec<_>>();
-    if let Some(change) = change_address {
-      payments.push(InternalPayment::Change(change, in_amount - out_amount));
+    // Splitting the inputs into chunks of max_inputs could leave a final transaction with only a
+    // few inputs, which may not be able to pay for the fee's fixed portion
+    // Instead, deal the inputs, from most to least valuable, across the transactions
+    let tx_count = (inputs.len() + (max_inputs - 1)) / max_inputs;
+    inputs.sort_by_key(|input| Reverse(input.commitment().amount));
+    let mut chunks = vec![Vec::with_capacity(max_inputs); tx_count];
+    for (i, input) in inputs.into_iter().enumerate() {
+      chunks[i % tx_count].push(input);
     }
 
-    Ok(SignableTransaction { protocol, r_seed, inputs, payments, data, fee })
+    let mut txs = vec![];
+    for inputs in chunks {
+      // This is the same fee SignableTransaction::new will calculate, leaving no change
+      let (_, fee) = Self::weight_and_fee(protocol, inputs.len(), &payments, &data, fee_rate)?;
+      let in_amount = inputs.iter().map(|input| input.commitment().amount).sum::<u64>();
+      if in_amount <= fee {
+        Err(TransactionError::NotEnoughFunds(in_amount, fee))?;
+      }
+
+      txs.push(SignableTransaction::new(
+        protocol,
+        r_seed.clone(),
+        inputs,
+        vec![(dest, in_amount - fee)],
+        Some(change_address.clone()),
+        data.clone(),
+        fee_rate,
+      )?);
+    }
+    Ok(txs)
   }
 
   pub fn fee(&self) -> u64 {
     self.fee
   }
 
+  /// The weight of this transaction once signed, which its fee pays for.
+  ///
+  /// This is an upper bound, as the length of each input's key offsets depends on its decoys. All
+  /// other fields, including the Bulletproofs clawback, are exactly accounted for.
+  pub fn weight(&self) -> usize {
+    Self::calculate_weight(self.protocol, self.inputs.len(), &self.payments, &self.data, self.fee)
+  }
+
+  pub fn inputs(&self) -> &[SpendableOutput] {
+    &self.inputs
+  }
+
+  /// The payments this transaction makes, including the change output if present.
+  pub fn payments(&self) -> Vec<(MoneroAddress, u64)> {
+    self
+      .payments
+      .iter()
+      .map(|payment| match payment {
+        InternalPayment::Payment(payment) => *payment,
+        InternalPayment::Change(change, amount) => (change.address, *amount),
+      })
+      .collect()
+  }
+
   #[allow(clippy::type_complexity)]
   fn prepare_payments(
     seed: &Zeroizing<[u8; 32]>,
@@ -416,33 +645,7 @@ impl SignableTransaction {
     let tx_key = Zeroizing::new(random_scalar(&mut rng));
     let mut tx_public_key = tx_key.deref() * &ED25519_BASEPOINT_TABLE;
 
-    // If any of these outputs are to a subaddress, we need keys distinct to them
-    // The only time this *does not* force having additional keys is when the only other output
-    // is a change output we have the view key for, enabling rewriting rA to aR
-    let mut has_change_view = false;
-    let subaddresses = payments
-      .iter()
-      .filter(|payment| match *payment {
-        InternalPayment::Payment(payment) => payment.0.is_subaddress(),
-        InternalPayment::Change(change, _) => {
-          if change.view.is_some() {
-            has_change_view = true;
-            // It should not be possible to construct a change specification to a subaddress with a
-            // view key
-            debug_assert!(!change.address.is_subaddress());
-          }
-          change.address.is_subaddress()
-        }
-      })
-      .count() !=
-      0;
-
-    // We need additional keys if we have any subaddresses
-    let mut additional = subaddresses;
-    // Unless the above change view key path is taken
-    if (payments.len() == 2) && has_change_view {
-      additional = false;
-    }
+    let (subaddresses, additional) = Self::subaddresses(payments);
     let modified_change_ecdh = subaddresses && (!additional);
 
     // If we're using the aR rewrite, update tx_public_key from rG to rB
@@ -630,9 +833,9 @@ impl SignableTransaction {
       tx_outputs.push(Output {
         amount: 0,
         key: output.dest.compress(),
-        view_tag: Some(output.view_tag).filter(|_| matches!(self.protocol, Protocol::v16)),
+        view_tag: Some(output.view_tag).filter(|_| self.protocol.view_tags()),
       });
-      ecdh_info.push(output.amount);
+      ecdh_info.push(EncryptedAmount::Compact { amount: output.amount });
     }
 
     (
@@ -648,6 +851,7 @@ impl SignableTransaction {
         rct_signatures: RctSignatures {
           base: RctBase {
             fee,
+            pseudo_outs: vec![],
             ecdh_info,
             commitments: commitments.iter().map(|commitment| commitment.calculate()).collect(),
           },
@@ -662,11 +866,39 @@ impl SignableTransaction {
     )
   }
 
+  // Select decoys for this transaction's inputs
+  async fn select_decoys<RPC: RpcConnection, R: RngCore + CryptoRng>(
+    &self,
+    rng: &mut R,
+    rpc: &Rpc<RPC>,
+  ) -> Result<Vec<Decoys>, TransactionError> {
+    Decoys::select(
+      rng,
+      rpc,
+      self.protocol.ring_len(),
+      rpc.get_height().await.map_err(TransactionError::RpcError)? - 1,
+      &self.inputs,
+    )
+    .await
+    .map_err(TransactionError::RpcError)
+  }
+
   /// Sign this transaction.
-  pub async fn sign<R: RngCore + CryptoRng>(
+  pub async fn sign<RPC: RpcConnection, R: RngCore + CryptoRng>(
+    self,
+    rng: &mut R,
+    rpc: &Rpc<RPC>,
+    spend: &Zeroizing<Scalar>,
+  ) -> Result<Transaction, TransactionError> {
+    let decoys = self.select_decoys(rng, rpc).await?;
+    self.sign_with_decoys(rng, &decoys, spend)
+  }
+
+  // Sign this transaction, using the specified decoys for its inputs
+  fn sign_with_decoys<R: RngCore + CryptoRng>(
     mut self,
     rng: &mut R,
-    rpc: &Rpc,
+    decoys: &[Decoys],
     spend: &Zeroizing<Scalar>,
   ) -> Result<Transaction, TransactionError> {
     let mut images = Vec::with_capacity(self.inputs.len());
@@ -691,16 +923,15 @@ impl SignableTransaction {
       ),
     );
 
-    let signable =
-      prepare_inputs(rng, rpc, self.protocol.ring_len(), &self.inputs, spend, &mut tx).await?;
+    let signable = prepare_inputs(self.protocol.ring_len(), &self.inputs, decoys, spend, &mut tx)?;
 
     let clsag_pairs = Clsag::sign(rng, signable, mask_sum, tx.signature_hash());
     match tx.rct_signatures.prunable {
-      RctPrunable::Null => panic!("Signing for RctPrunable::Null"),
       RctPrunable::Clsag { ref mut clsags, ref mut pseudo_outs, .. } => {
         clsags.append(&mut clsag_pairs.iter().map(|clsag| clsag.0.clone()).collect::<Vec<_>>());
         pseudo_outs.append(&mut clsag_pairs.iter().map(|clsag| clsag.1).collect::<Vec<_>>());
       }
+      _ => unreachable!("attempted to sign a TX which wasn't CLSAG"),
     }
     Ok(tx)
   }
@@ -747,10 +978,11 @@ impl Eventuality {
       if (&Output {
         amount: 0,
         key: expected.dest.compress(),
-        view_tag: Some(expected.view_tag).filter(|_| matches!(self.protocol, Protocol::v16)),
+        view_tag: Some(expected.view_tag).filter(|_| self.protocol.view_tags()),
       } != actual) ||
         (Some(&expected.commitment.calculate()) != tx.rct_signatures.base.commitments.get(o)) ||
-        (Some(&expected.amount) != tx.rct_signatures.base.ecdh_info.get(o))
+        (Some(&EncryptedAmount::Compact { amount: expected.amount }) !=
+          tx.rct_signatures.base.ecdh_info.get(o))
       {
         return false;
       }
@@ -763,28 +995,7 @@ impl Eventuality {
     self.protocol.write(w)?;
     write_raw_vec(write_byte, self.r_seed.as_ref(), w)?;
     write_vec(write_point, &self.inputs, w)?;
-
-    fn write_payment<W: io::Write>(payment: &InternalPayment, w: &mut W) -> io::Result<()> {
-      match payment {
-        InternalPayment::Payment(payment) => {
-          w.write_all(&[0])?;
-          write_vec(write_byte, payment.0.to_string().as_bytes(), w)?;
-          w.write_all(&payment.1.to_le_bytes())
-        }
-        InternalPayment::Change(change, amount) => {
-          w.write_all(&[1])?;
-          write_vec(write_byte, change.address.to_string().as_bytes(), w)?;
-          if let Some(view) = change.view.as_ref() {
-            w.write_all(&[1])?;
-            write_scalar(view, w)?;
-          } else {
-            w.write_all(&[0])?;
-          }
-          w.write_all(&amount.to_le_bytes())
-        }
-      }
-    }
-    write_vec(write_payment, &self.payments, w)?;
+    write_vec(InternalPayment::write, &self.payments, w)?;
 
     write_vec(write_byte, &self.extra, w)
   }
@@ -796,36 +1007,11 @@ impl Eventuality {
   }
 
   pub fn read<R: io::Read>(r: &mut R) -> io::Result<Eventuality> {
-    fn read_address<R: io::Read>(r: &mut R) -> io::Result<MoneroAddress> {
-      String::from_utf8(read_vec(read_byte, r)?)
-        .ok()
-        .and_then(|str| MoneroAddress::from_str_raw(&str).ok())
-        .ok_or(io::Error::new(io::ErrorKind::Other, "invalid address"))
-    }
-
-    fn read_payment<R: io::Read>(r: &mut R) -> io::Result<InternalPayment> {
-      Ok(match read_byte(r)? {
-        0 => InternalPayment::Payment((read_address(r)?, read_u64(r)?)),
-        1 => InternalPayment::Change(
-          Change {
-            address: read_address(r)?,
-            view: match read_byte(r)? {
-              0 => None,
-              1 => Some(Zeroizing::new(read_scalar(r)?)),
-              _ => Err(io::Error::new(io::ErrorKind::Other, "invalid change payment"))?,
-            },
-          },
-          read_u64(r)?,
-        ),
-        _ => Err(io::Error::new(io::ErrorKind::Other, "invalid payment"))?,
-      })
-    }
-
     Ok(Eventuality {
       protocol: Protocol::read(r)?,
       r_seed: Zeroizing::new(read_bytes::<_, 32>(r)?),
       inputs: read_vec(read_point, r)?,
-      payments: read_vec(read_payment, r)?,
+      payments: read_vec(InternalPayment::read, r)?,
       extra: read_vec(read_byte, r)?,
     })
   }
@@ -24,13 +24,13 @@ use frost::{
 };
 
 use crate::{
-  random_scalar,
+  hash, random_scalar,
   ringct::{
     clsag::{ClsagInput, ClsagDetails, ClsagAddendum, ClsagMultisig, add_key_image_share},
     RctPrunable,
   },
   transaction::{Input, Transaction},
-  rpc::Rpc,
+  rpc::{RpcConnection, Rpc},
   wallet::{
     TransactionError, InternalPayment, SignableTransaction, Decoys, key_image_sort, uniqueness,
   },
@@ -74,9 +74,9 @@ pub struct TransactionSignatureMachine {
 impl SignableTransaction {
   /// Create a FROST signing machine out of this signable transaction.
   /// The height is the Monero blockchain height to synchronize around.
-  pub async fn multisig(
+  pub async fn multisig<RPC: RpcConnection>(
     self,
-    rpc: &Rpc,
+    rpc: &Rpc<RPC>,
     keys: ThresholdKeys<Ed25519>,
     mut transcript: RecommendedTranscript,
     height: usize,
@@ -137,6 +137,15 @@ impl SignableTransaction {
       }
     }
 
+    // Snapshot the output distribution used to select decoys, committing to it so signers with
+    // differing views of the blockchain don't silently select differing decoys
+    let distribution = {
+      let mut cache = rpc.output_distribution().await;
+      cache.refresh(rpc, height).await.map_err(TransactionError::RpcError)?;
+      cache.snapshot(height).unwrap()
+    };
+    transcript.append_message(b"decoy_distribution", hash(&distribution.serialize()));
+
     let mut key_images = vec![];
     for (i, input) in self.inputs.iter().enumerate() {
       // Check this the right set of keys
@@ -158,11 +167,12 @@ impl SignableTransaction {
     // to be async which isn't preferable. This should be suitably competent though
     // While this inability means we can immediately create the input, moving it out of the
     // Arc RwLock, keeping it within an Arc RwLock keeps our options flexible
-    let decoys = Decoys::select(
-      // Using a seeded RNG with a specific height, committed to above, should make these decoys
+    let decoys = Decoys::select_deterministic(
+      // Using a seed and distribution, both committed to above, should make these decoys
       // committed to. They'll also be committed to later via the TX message as a whole
-      &mut ChaCha20Rng::from_seed(transcript.rng_seed(b"decoys")),
+      transcript.rng_seed(b"decoys"),
       rpc,
+      &distribution,
       self.protocol.ring_len(),
       height,
       &self.inputs,
@@ -419,7 +429,6 @@ impl SignatureMachine<Transaction> for TransactionSignatureMachine {
   ) -> Result<Transaction, FrostError> {
     let mut tx = self.tx;
     match tx.rct_signatures.prunable {
-      RctPrunable::Null => panic!("Signing for RctPrunable::Null"),
       RctPrunable::Clsag { ref mut clsags, ref mut pseudo_outs, .. } => {
         for (c, clsag) in self.clsags.drain(..).enumerate() {
           let (clsag, pseudo_out) = clsag.complete(
@@ -429,6 +438,7 @@ impl SignatureMachine<Transaction> for TransactionSignatureMachine {
           pseudo_outs.push(pseudo_out);
         }
       }
+      _ => unreachable!("attempted to sign a TX which wasn't CLSAG"),
     }
     Ok(tx)
   }
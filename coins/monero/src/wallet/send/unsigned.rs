@@ -0,0 +1,142 @@
+use std::io;
+
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+use rand_core::{RngCore, CryptoRng};
+
+use curve25519_dalek::scalar::Scalar;
+
+use crate::{
+  Protocol,
+  serialize::{read_byte, read_bytes, read_u64, read_vec, write_byte, write_vec},
+  transaction::Transaction,
+  rpc::{RpcConnection, Rpc},
+  ringct::bulletproofs::MAX_OUTPUTS,
+  wallet::{
+    address::MoneroAddress, send::InternalPayment, SpendableOutput, Decoys, TransactionError,
+    SignableTransaction, Eventuality,
+  },
+};
+
+/// A transaction whose rings have been selected, able to be signed without a RPC.
+///
+/// This enables cold signing. A watch-only wallet, with a RPC, creates an UnsignedTransaction and
+/// transfers it to an offline machine holding the spend key. The offline machine signs it and
+/// returns the signed transaction for the watch-only wallet to publish.
+///
+/// The offline machine has no way to check the inputs being spent actually exist, or their
+/// amounts. The payments and fee should be reviewed before signing.
+#[derive(Clone, PartialEq, Eq, Debug, Zeroize, ZeroizeOnDrop)]
+pub struct UnsignedTransaction {
+  signable: SignableTransaction,
+  decoys: Vec<Decoys>,
+}
+
+impl SignableTransaction {
+  /// Select the rings for this transaction, producing a transaction which can be signed offline.
+  ///
+  /// If no `r_seed` was specified, one will be generated so the transaction's Eventuality is
+  /// known before it's signed.
+  pub async fn unsigned<RPC: RpcConnection, R: RngCore + CryptoRng>(
+    mut self,
+    rng: &mut R,
+    rpc: &Rpc<RPC>,
+  ) -> Result<UnsignedTransaction, TransactionError> {
+    if self.r_seed.is_none() {
+      let mut r_seed = Zeroizing::new([0; 32]);
+      rng.fill_bytes(r_seed.as_mut());
+      self.r_seed = Some(r_seed);
+    }
+
+    let decoys = self.select_decoys(rng, rpc).await?;
+    Ok(UnsignedTransaction { signable: self, decoys })
+  }
+}
+
+impl UnsignedTransaction {
+  #[cfg(test)]
+  pub(crate) fn new(signable: SignableTransaction, decoys: Vec<Decoys>) -> UnsignedTransaction {
+    UnsignedTransaction { signable, decoys }
+  }
+
+  /// The fee this transaction pays, which includes any funds not sent to a payment.
+  pub fn fee(&self) -> u64 {
+    let inputs = self.signable.inputs.iter().map(|input| input.commitment().amount).sum::<u64>();
+    inputs - self.payments().iter().map(|payment| payment.1).sum::<u64>()
+  }
+
+  /// The payments this transaction makes, including the change output if present.
+  pub fn payments(&self) -> Vec<(MoneroAddress, u64)> {
+    self.signable.payments()
+  }
+
+  /// The Eventuality of this transaction, which can be used to track it once published.
+  pub fn eventuality(&self) -> Eventuality {
+    self.signable.eventuality().unwrap()
+  }
+
+  /// Sign this transaction with the spend key, without a RPC.
+  pub fn sign<R: RngCore + CryptoRng>(
+    &self,
+    rng: &mut R,
+    spend: &Zeroizing<Scalar>,
+  ) -> Result<Transaction, TransactionError> {
+    self.signable.clone().sign_with_decoys(rng, &self.decoys, spend)
+  }
+
+  pub fn write<W: io::Write>(&self, w: &mut W) -> io::Result<()> {
+    let signable = &self.signable;
+    signable.protocol.write(w)?;
+    w.write_all(signable.r_seed.as_ref().unwrap().as_ref())?;
+    write_vec(SpendableOutput::write, &signable.inputs, w)?;
+    write_vec(InternalPayment::write, &signable.payments, w)?;
+    write_vec(|data: &Vec<u8>, w| write_vec(write_byte, data, w), &signable.data, w)?;
+    w.write_all(&signable.fee.to_le_bytes())?;
+    write_vec(Decoys::write, &self.decoys, w)
+  }
+
+  pub fn serialize(&self) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(2048);
+    self.write(&mut buf).unwrap();
+    buf
+  }
+
+  pub fn read<R: io::Read>(r: &mut R) -> io::Result<UnsignedTransaction> {
+    let signable = SignableTransaction {
+      protocol: Protocol::read(r)?,
+      r_seed: Some(Zeroizing::new(read_bytes(r)?)),
+      inputs: read_vec(SpendableOutput::read, r)?,
+      payments: read_vec(InternalPayment::read, r)?,
+      data: read_vec(|r| read_vec(read_byte, r), r)?,
+      fee: read_u64(r)?,
+    };
+    let decoys = read_vec(Decoys::read, r)?;
+
+    if signable.inputs.is_empty() || (decoys.len() != signable.inputs.len()) {
+      Err(io::Error::new(io::ErrorKind::Other, "invalid inputs"))?;
+    }
+    // Monero requires two outputs, which SignableTransaction::new enforces with NoChange
+    if (signable.payments.len() < 2) || (signable.payments.len() > MAX_OUTPUTS) {
+      Err(io::Error::new(io::ErrorKind::Other, "invalid payments"))?;
+    }
+    let unsigned = UnsignedTransaction { signable, decoys };
+
+    // Make sure the payments don't exceed the inputs, which would cause signing to underflow
+    let inputs = unsigned
+      .signable
+      .inputs
+      .iter()
+      .map(|input| u128::from(input.commitment().amount))
+      .sum::<u128>();
+    let payments = unsigned.payments().iter().map(|payment| u128::from(payment.1)).sum::<u128>();
+    if (inputs > u128::from(u64::MAX)) || (payments > inputs) {
+      Err(io::Error::new(io::ErrorKind::Other, "payments exceeded inputs"))?;
+    }
+    // Any funds not sent to a payment would be burnt, as the fee signed for is the specified one
+    if u128::from(unsigned.signable.fee) != (inputs - payments) {
+      Err(io::Error::new(io::ErrorKind::Other, "fee wasn't the inputs less the payments"))?;
+    }
+
+    Ok(unsigned)
+  }
+}
@@ -0,0 +1,374 @@
+use core::ops::Deref;
+use std::collections::{HashMap, BTreeMap};
+
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+use curve25519_dalek::{
+  constants::ED25519_BASEPOINT_TABLE,
+  scalar::Scalar,
+  edwards::{EdwardsPoint, CompressedEdwardsY},
+};
+
+use crate::{
+  ringct::hash_to_point,
+  transaction::{Input, Timelock, Transaction},
+  block::Block,
+  rpc::{RpcError, RpcConnection, Rpc},
+  wallet::{
+    Scanner, SpendableOutput, SignedKeyImage, Mempool, Spendability, OutputLock,
+    scan::first_output_index,
+  },
+};
+
+/// The state of an output held by a WalletState.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum OutputState {
+  /// The output is unspent, yet can't be spent due to its timelock or the minimum age of spent
+  /// outputs.
+  Locked,
+  /// The output is unspent and can be spent.
+  Unspent,
+  /// The output was spent by the transaction with this hash.
+  Spent([u8; 32]),
+}
+
+/// The balance of a WalletState, in atomic units.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Balance {
+  pub unspent: u64,
+  pub locked: u64,
+  pub spent: u64,
+}
+
+/// An output held by a WalletState.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TrackedOutput {
+  output: SpendableOutput,
+  lock: OutputLock,
+  key_image: Option<EdwardsPoint>,
+  // The height of the block the output was spent in, and the hash of the spending transaction
+  spent: Option<(usize, [u8; 32])>,
+}
+
+impl TrackedOutput {
+  pub fn output(&self) -> &SpendableOutput {
+    &self.output
+  }
+
+  /// The number of the block this output was included in.
+  pub fn height(&self) -> usize {
+    self.lock.block
+  }
+
+  /// The timelock of the transaction this output was included in.
+  pub fn timelock(&self) -> Timelock {
+    self.lock.timelock
+  }
+
+  /// The locks on this output.
+  pub fn lock(&self) -> OutputLock {
+    self.lock
+  }
+
+  pub fn key_image(&self) -> Option<EdwardsPoint> {
+    self.key_image
+  }
+
+  /// The hash of the transaction which spent this output, if it's been spent.
+  pub fn spent(&self) -> Option<[u8; 32]> {
+    self.spent.map(|(_, tx)| tx)
+  }
+}
+
+/// A wallet's outputs, and their spent states, as of the latest block scanned.
+///
+/// Spends are detected via the key images of the inputs of scanned transactions. These key images
+/// are either calculated from the spend key, if it's known, or must be provided via
+/// `set_key_image`. Outputs can't be spent until they're 10 blocks old, so as long as key images
+/// are provided within 9 blocks of an output being received, no spends will be missed.
+///
+/// Outputs with `Timelock::Time` timelocks are considered locked until the timestamp of the
+/// latest block scanned reaches their timelock.
+#[derive(Clone)]
+pub struct WalletState {
+  scanner: Scanner,
+  spend: Option<Zeroizing<Scalar>>,
+
+  // The amount of blocks scanned, and the timestamp of the latest one
+  height: usize,
+  time: u64,
+
+  // Outputs by their global index
+  outputs: BTreeMap<u64, TrackedOutput>,
+  key_images: HashMap<CompressedEdwardsY, u64>,
+}
+
+impl Zeroize for WalletState {
+  fn zeroize(&mut self) {
+    self.scanner.zeroize();
+    self.spend.zeroize();
+    self.height.zeroize();
+    self.time.zeroize();
+
+    // These may not be effective, unfortunately
+    for (mut index, mut output) in std::mem::take(&mut self.outputs) {
+      index.zeroize();
+      output.output.zeroize();
+      output.lock.zeroize();
+      output.key_image.zeroize();
+    }
+    for (mut key_image, mut index) in self.key_images.drain() {
+      key_image.zeroize();
+      index.zeroize();
+    }
+  }
+}
+
+impl Drop for WalletState {
+  fn drop(&mut self) {
+    self.zeroize();
+  }
+}
+
+impl ZeroizeOnDrop for WalletState {}
+
+impl WalletState {
+  /// Create a WalletState which will scan from the specified block onwards.
+  ///
+  /// If the spend key is provided, key images will be calculated for every output received.
+  /// Returns None if the spend key isn't the Scanner's.
+  pub fn new(
+    scanner: Scanner,
+    spend: Option<Zeroizing<Scalar>>,
+    height: usize,
+  ) -> Option<WalletState> {
+    if let Some(spend) = spend.as_ref() {
+      if (spend.deref() * &ED25519_BASEPOINT_TABLE) != scanner.pair.spend {
+        return None;
+      }
+    }
+
+    Some(WalletState {
+      scanner,
+      spend,
+      height,
+      time: 0,
+      outputs: BTreeMap::new(),
+      key_images: HashMap::new(),
+    })
+  }
+
+  /// The Scanner used, which may be used to register additional subaddresses.
+  pub fn scanner(&mut self) -> &mut Scanner {
+    &mut self.scanner
+  }
+
+  /// The amount of blocks scanned, which is the number of the next block to scan.
+  pub fn height(&self) -> usize {
+    self.height
+  }
+
+  /// Scan the next block, which must have the number `height()`.
+  pub async fn scan<RPC: RpcConnection>(
+    &mut self,
+    rpc: &Rpc<RPC>,
+    block: &Block,
+  ) -> Result<(), RpcError> {
+    if block.number() != self.height {
+      Err(RpcError::InternalError("scanned block wasn't the next block"))?;
+    }
+
+    let mut txs = vec![block.miner_tx.clone()];
+    txs.extend(rpc.get_transactions(&block.txs).await?);
+    let index = first_output_index(rpc, &txs).await?;
+    self.process(block.header.timestamp, &txs, index);
+    Ok(())
+  }
+
+  // Process the next block's transactions, where index is the global index of the first RingCT
+  // output within them
+  pub(crate) fn process(&mut self, time: u64, txs: &[Transaction], index: Option<u64>) {
+    let height = self.height;
+
+    if let Some(index) = index {
+      let locks =
+        txs.iter().map(|tx| (tx.hash(), OutputLock::new(height, tx))).collect::<HashMap<_, _>>();
+      for timelocked in self.scanner.scan_transactions(txs, index) {
+        for output in timelocked.ignore_timelock() {
+          let key_image = self.spend.as_ref().map(|spend| {
+            Zeroizing::new(spend.deref() + output.key_offset()).deref() *
+              hash_to_point(output.key())
+          });
+          if let Some(key_image) = key_image {
+            self.key_images.insert(key_image.compress(), output.global_index);
+          }
+
+          self.outputs.insert(
+            output.global_index,
+            TrackedOutput {
+              lock: locks[&output.output.absolute.tx],
+              output,
+              key_image,
+              spent: None,
+            },
+          );
+        }
+      }
+    }
+
+    for tx in txs {
+      for input in &tx.prefix.inputs {
+        if let Input::ToKey { key_image, .. } = input {
+          if let Some(index) = self.key_images.get(&key_image.compress()) {
+            self.outputs.get_mut(index).unwrap().spent = Some((height, tx.hash()));
+          }
+        }
+      }
+    }
+
+    self.height += 1;
+    self.time = time;
+  }
+
+  /// Roll back to the specified height, as if no block from that height onwards had been scanned.
+  /// This is intended to be called if a re-organization occurred.
+  ///
+  /// `time` is the timestamp of the block before `height`, which becomes the latest block scanned.
+  pub fn rollback(&mut self, height: usize, time: u64) {
+    if height >= self.height {
+      return;
+    }
+    self.height = height;
+    self.time = time;
+
+    let mut removed = vec![];
+    self.outputs.retain(|_, output| {
+      if output.lock.block < height {
+        if output.spent.map(|(spent, _)| spent >= height).unwrap_or(false) {
+          output.spent = None;
+        }
+        true
+      } else {
+        removed.push((output.key_image, output.output.key().compress()));
+        false
+      }
+    });
+
+    for (key_image, key) in removed {
+      if let Some(key_image) = key_image {
+        self.key_images.remove(&key_image.compress());
+      }
+      // Allow the output to be scanned again
+      if let Some(burning_bug) = self.scanner.burning_bug.as_mut() {
+        burning_bug.remove(&key);
+      }
+    }
+  }
+
+  /// Set the key image for the output with the specified global index.
+  ///
+  /// This is required to detect spends of outputs when the spend key isn't known, such as with
+  /// multisig wallets. The key image is not verified. Returns false if no such output is held.
+  pub fn set_key_image(&mut self, global_index: u64, key_image: EdwardsPoint) -> bool {
+    if let Some(output) = self.outputs.get_mut(&global_index) {
+      if let Some(existing) = output.key_image {
+        self.key_images.remove(&existing.compress());
+      }
+      output.key_image = Some(key_image);
+      self.key_images.insert(key_image.compress(), global_index);
+      true
+    } else {
+      false
+    }
+  }
+
+  /// Import a key image signed by wallet2's `export_key_images`, or `SignedKeyImage::new`, for
+  /// the output with the specified global index.
+  ///
+  /// Returns false if no such output is held or the signature is invalid.
+  pub fn import_key_image(&mut self, global_index: u64, key_image: &SignedKeyImage) -> bool {
+    let valid = self
+      .outputs
+      .get(&global_index)
+      .map(|output| key_image.verify(output.output.key()))
+      .unwrap_or(false);
+    valid && self.set_key_image(global_index, key_image.key_image)
+  }
+
+  /// The outputs whose key images aren't known, and whose spends accordingly can't be detected.
+  pub fn missing_key_images(&self) -> Vec<&SpendableOutput> {
+    self
+      .outputs
+      .values()
+      .filter(|output| output.key_image.is_none())
+      .map(|output| &output.output)
+      .collect()
+  }
+
+  /// Every output held, including spent outputs, ordered by their global index.
+  pub fn outputs(&self) -> Vec<&TrackedOutput> {
+    self.outputs.values().collect()
+  }
+
+  /// The spendability of an output as of the latest block scanned, ignoring if it's been spent.
+  pub fn spendability(&self, output: &TrackedOutput) -> Spendability {
+    output.lock.spendability(self.height, self.time)
+  }
+
+  /// The state of an output as of the latest block scanned.
+  pub fn state(&self, output: &TrackedOutput) -> OutputState {
+    if let Some((_, tx)) = output.spent {
+      return OutputState::Spent(tx);
+    }
+
+    if self.spendability(output) == Spendability::Spendable {
+      OutputState::Unspent
+    } else {
+      OutputState::Locked
+    }
+  }
+
+  /// The unspent outputs which are spent by transactions in the mempool, with the hashes of those
+  /// transactions.
+  ///
+  /// If an output is spent by a transaction other than the one expected, or by multiple
+  /// transactions, it's been double spent by another holder of its key.
+  pub fn mempool_spends<'a>(
+    &'a self,
+    mempool: &'a Mempool,
+  ) -> Vec<(&'a TrackedOutput, &'a [[u8; 32]])> {
+    self
+      .outputs
+      .values()
+      .filter(|output| output.spent.is_none())
+      .filter_map(|output| {
+        let spenders = mempool.spent_by(&output.key_image?);
+        Some((output, spenders)).filter(|_| !spenders.is_empty())
+      })
+      .collect()
+  }
+
+  /// The outputs which can currently be spent.
+  pub fn spendable(&self) -> Vec<&SpendableOutput> {
+    self
+      .outputs
+      .values()
+      .filter(|output| self.state(output) == OutputState::Unspent)
+      .map(|output| &output.output)
+      .collect()
+  }
+
+  /// The balance as of the latest block scanned.
+  pub fn balance(&self) -> Balance {
+    let mut balance = Balance::default();
+    for output in self.outputs.values() {
+      let amount = output.output.commitment().amount;
+      match self.state(output) {
+        OutputState::Locked => balance.locked += amount,
+        OutputState::Unspent => balance.unspent += amount,
+        OutputState::Spent(_) => balance.spent += amount,
+      }
+    }
+    balance
+  }
+}
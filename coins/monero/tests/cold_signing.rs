@@ -0,0 +1,65 @@
+use core::ops::Deref;
+use std::collections::HashSet;
+
+use zeroize::Zeroizing;
+
+use curve25519_dalek::constants::ED25519_BASEPOINT_TABLE;
+
+use monero_serai::{
+  random_scalar,
+  transaction::Transaction,
+  verify::verify_transactions,
+  wallet::{
+    ViewPair, Scanner, Change, SignableTransactionBuilder, TransactionError, UnsignedTransaction,
+  },
+};
+
+mod runner;
+use runner::TestRng;
+
+async_sequential!(
+  async fn cold_signing() {
+    let rpc = runner::rpc().await;
+
+    // The online machine only has the view key
+    let spend = Zeroizing::new(random_scalar(&mut TestRng));
+    let view = ViewPair::new(
+      spend.deref() * &ED25519_BASEPOINT_TABLE,
+      Zeroizing::new(random_scalar(&mut TestRng)),
+    );
+    let output = runner::get_miner_tx_output(&rpc, &view).await;
+
+    let (_, recipient, address) = runner::random_address();
+    let mut builder = SignableTransactionBuilder::new(
+      rpc.get_protocol().await.unwrap(),
+      rpc.get_fee().await.unwrap(),
+      Some(Change::new(&view, false)),
+    );
+    builder.add_input(output);
+    builder.add_payment(address, 5);
+    let unsigned = builder.build().unwrap().unsigned(&mut TestRng, &rpc).await.unwrap();
+    let eventuality = unsigned.eventuality();
+
+    // Transfer it to the offline machine, which signs it
+    let unsigned = UnsignedTransaction::read::<&[u8]>(&mut unsigned.serialize().as_ref()).unwrap();
+    assert!(unsigned.payments().contains(&(address, 5)));
+    assert_eq!(
+      unsigned.sign(&mut TestRng, &Zeroizing::new(random_scalar(&mut TestRng))),
+      Err(TransactionError::WrongPrivateKey)
+    );
+    let signed = unsigned.sign(&mut TestRng, &spend).unwrap().serialize();
+
+    // Transfer the signed transaction back to the online machine, which publishes it
+    let signed = Transaction::read::<&[u8]>(&mut signed.as_ref()).unwrap();
+    rpc.publish_transaction(&signed).await.unwrap();
+    runner::mine_until_unlocked(&rpc, &runner::random_address().2.to_string(), signed.hash()).await;
+
+    let tx = rpc.get_transaction(signed.hash()).await.unwrap();
+    verify_transactions(&mut TestRng, &rpc, &[tx.clone()]).await.unwrap();
+    assert!(eventuality.matches(&tx));
+    assert_eq!(tx.rct_signatures.base.fee, unsigned.fee());
+
+    let mut scanner = Scanner::from_view(recipient, Some(HashSet::new()));
+    assert_eq!(scanner.scan_transaction(&tx).not_locked().swap_remove(0).commitment().amount, 5);
+  }
+);
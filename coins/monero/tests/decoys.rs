@@ -0,0 +1,74 @@
+use rand_core::RngCore;
+
+use monero_serai::wallet::{Decoys, OutputDistribution};
+
+mod runner;
+use runner::TestRng;
+
+async_sequential!(
+  async fn deterministic_decoys() {
+    let rpc = runner::rpc().await;
+    let view = runner::random_address().1;
+    let inputs = [runner::get_miner_tx_output(&rpc, &view).await];
+
+    let ring_len = rpc.get_protocol().await.unwrap().ring_len();
+    let height = rpc.get_height().await.unwrap() - 1;
+
+    // One participant takes a snapshot of the distribution, which is shared with the others
+    let mut distribution = OutputDistribution::new();
+    distribution.refresh(&rpc, height).await.unwrap();
+    let snapshot = distribution.snapshot(height).unwrap().serialize();
+    let snapshot = OutputDistribution::read::<&[u8]>(&mut snapshot.as_ref()).unwrap();
+
+    let mut seed = [0; 32];
+    TestRng.fill_bytes(&mut seed);
+
+    // Participants with their own connections select the same rings
+    let other = runner::rpc().await;
+    let decoys =
+      Decoys::select_deterministic(seed, &rpc, &snapshot, ring_len, height, &inputs).await.unwrap();
+    assert_eq!(
+      Decoys::select_deterministic(seed, &other, &snapshot, ring_len, height, &inputs)
+        .await
+        .unwrap(),
+      decoys
+    );
+    assert!(Decoys::verify_deterministic(
+      seed, &other, &snapshot, ring_len, height, &inputs, &decoys
+    )
+    .await
+    .unwrap());
+
+    assert_eq!(decoys.len(), 1);
+    assert_eq!(decoys[0].len(), ring_len);
+    let real = usize::from(decoys[0].i);
+    assert_eq!(decoys[0].ring[real], [inputs[0].key(), inputs[0].commitment().calculate()]);
+
+    // A ring with a different member, or different offsets, is rejected
+    let decoy = (real + 1) % ring_len;
+    let mut tampered = decoys.clone();
+    tampered[0].ring[decoy] = tampered[0].ring[real];
+    assert!(!Decoys::verify_deterministic(
+      seed, &other, &snapshot, ring_len, height, &inputs, &tampered
+    )
+    .await
+    .unwrap());
+
+    let mut tampered = decoys.clone();
+    tampered[0].offsets[decoy] += 1;
+    assert!(!Decoys::verify_deterministic(
+      seed, &other, &snapshot, ring_len, height, &inputs, &tampered
+    )
+    .await
+    .unwrap());
+
+    // The rings are bound to the seed
+    let mut other_seed = seed;
+    other_seed[0] ^= 1;
+    assert!(!Decoys::verify_deterministic(
+      other_seed, &other, &snapshot, ring_len, height, &inputs, &decoys
+    )
+    .await
+    .unwrap());
+  }
+);
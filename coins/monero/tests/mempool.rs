@@ -0,0 +1,67 @@
+use core::ops::Deref;
+use std::collections::HashSet;
+
+use zeroize::Zeroizing;
+
+use curve25519_dalek::constants::ED25519_BASEPOINT_TABLE;
+
+use monero_serai::{
+  random_scalar,
+  wallet::{ViewPair, Scanner, Mempool, Change, SignableTransactionBuilder},
+};
+
+mod runner;
+use runner::TestRng;
+
+async_sequential!(
+  async fn mempool() {
+    let rpc = runner::rpc().await;
+
+    let spend = Zeroizing::new(random_scalar(&mut TestRng));
+    let view = ViewPair::new(
+      spend.deref() * &ED25519_BASEPOINT_TABLE,
+      Zeroizing::new(random_scalar(&mut TestRng)),
+    );
+    let output = runner::get_miner_tx_output(&rpc, &view).await;
+
+    let (_, recipient, address) = runner::random_address();
+    let protocol = rpc.get_protocol().await.unwrap();
+    let fee = rpc.get_fee().await.unwrap();
+    let transfer = |amount| {
+      let mut builder =
+        SignableTransactionBuilder::new(protocol, fee, Some(Change::new(&view, false)));
+      builder.add_input(output.clone());
+      builder.add_payment(address, amount);
+      builder.build().unwrap()
+    };
+
+    let tx = transfer(5).sign(&mut TestRng, &rpc, &spend).await.unwrap();
+    rpc.publish_transaction(&tx).await.unwrap();
+
+    // The transaction should be in the mempool, as it has yet to be mined
+    assert!(rpc.get_transaction_pool_hashes().await.unwrap().contains(&tx.hash()));
+    let mempool = Mempool::fetch(&rpc).await.unwrap();
+    assert!(mempool.contains(tx.hash()));
+    assert!(mempool.double_spends(&tx).is_empty());
+
+    // The recipient should see the payment as unconfirmed
+    let mut scanner = Scanner::from_view(recipient, Some(HashSet::new()));
+    let unconfirmed = scanner.scan_mempool(&mempool);
+    assert_eq!(unconfirmed.len(), 1);
+    let received = unconfirmed[0].not_locked().swap_remove(0);
+    assert_eq!(received.output.absolute.tx, tx.hash());
+    assert_eq!(received.output.commitment().amount, 5);
+    assert!(!received.double_spent);
+
+    // A transaction spending the same output is rejected, and detected as a double spend
+    let conflicting = transfer(6).sign(&mut TestRng, &rpc, &spend).await.unwrap();
+    assert!(rpc.publish_transaction(&conflicting).await.is_err());
+    assert_eq!(mempool.double_spends(&conflicting), vec![tx.hash()]);
+
+    // Once mined, the transaction leaves the mempool and its output is found in its block
+    runner::mine_until_unlocked(&rpc, &runner::random_address().2.to_string(), tx.hash()).await;
+    assert!(!rpc.get_transaction_pool_hashes().await.unwrap().contains(&tx.hash()));
+    let tx = rpc.get_transaction(tx.hash()).await.unwrap();
+    assert_eq!(scanner.scan_transaction(&tx).not_locked(), vec![received.output.clone()]);
+  }
+);
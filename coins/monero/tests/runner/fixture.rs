@@ -0,0 +1,193 @@
+// Integration tests are run against a regtest monerod at 127.0.0.1:18081, and a monero-wallet-rpc
+// at 127.0.0.1:6061, by default.
+//
+// Setting MONERO_SERAI_FIXTURES=record additionally writes every exchange with them to
+// tests/fixtures/<binary>/<test>.json, which MONERO_SERAI_FIXTURES=replay then serves, in order,
+// without any node. Fixtures must be re-recorded whenever a test changes the requests it makes.
+
+use std::{path::PathBuf, sync::Mutex};
+
+use lazy_static::lazy_static;
+
+use rand_core::{RngCore, CryptoRng, SeedableRng, OsRng};
+use rand_chacha::ChaCha20Rng;
+
+use serde::{Serialize, Deserialize};
+
+use async_trait::async_trait;
+
+use monero_serai::{
+  hash_to_scalar,
+  rpc::{RpcError, RpcConnection, HttpRpc, Rpc},
+};
+
+// The environment variable used to select the mode tests are run in
+const MODE: &str = "MONERO_SERAI_FIXTURES";
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Mode {
+  // Run against a live node
+  Live,
+  // Run against a live node, recording every exchange with it
+  Record,
+  // Run against previously recorded exchanges, without a node
+  Replay,
+}
+
+pub fn mode() -> Mode {
+  match std::env::var(MODE).as_deref() {
+    Err(_) | Ok("") | Ok("live") => Mode::Live,
+    Ok("record") => Mode::Record,
+    Ok("replay") => Mode::Replay,
+    Ok(mode) => panic!("unknown {} mode: {}", MODE, mode),
+  }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Exchange {
+  route: String,
+  // Bodies are hex encoded as binary routes are also recorded
+  request: String,
+  response: String,
+}
+
+#[derive(Debug)]
+struct Fixture {
+  mode: Mode,
+  path: PathBuf,
+  exchanges: Vec<Exchange>,
+  // The next exchange to serve, when replaying
+  next: usize,
+}
+
+lazy_static! {
+  static ref FIXTURE: Mutex<Option<Fixture>> = Mutex::new(None);
+  static ref RNG: Mutex<ChaCha20Rng> = Mutex::new(ChaCha20Rng::from_seed([0; 32]));
+}
+
+fn path(binary: &str, test: &str) -> PathBuf {
+  PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+    .join("tests")
+    .join("fixtures")
+    .join(binary)
+    .join(test.to_string() + ".json")
+}
+
+// Prepare to run the specified test
+pub fn start(binary: &str, test: &str) {
+  let mode = mode();
+
+  // When recording or replaying, seed the RNG from the test's name so it makes the same requests
+  // every time it's run
+  *RNG.lock().unwrap() = if mode == Mode::Live {
+    ChaCha20Rng::from_rng(OsRng).unwrap()
+  } else {
+    ChaCha20Rng::from_seed(hash_to_scalar(format!("{}::{}", binary, test).as_bytes()).to_bytes())
+  };
+
+  let path = path(binary, test);
+  *FIXTURE.lock().unwrap() = match mode {
+    Mode::Live => None,
+    Mode::Record => Some(Fixture { mode, path, exchanges: vec![], next: 0 }),
+    Mode::Replay => {
+      let exchanges = serde_json::from_slice(&std::fs::read(&path).unwrap_or_else(|_| {
+        panic!("no fixture at {}, record it with {}=record", path.display(), MODE)
+      }))
+      .unwrap();
+      Some(Fixture { mode, path, exchanges, next: 0 })
+    }
+  };
+}
+
+// Finish the current test, writing out its recording or checking its replay was exhausted
+pub fn finish() {
+  if let Some(fixture) = FIXTURE.lock().unwrap().take() {
+    match fixture.mode {
+      Mode::Live => {}
+      Mode::Record => {
+        std::fs::create_dir_all(fixture.path.parent().unwrap()).unwrap();
+        std::fs::write(&fixture.path, serde_json::to_vec_pretty(&fixture.exchanges).unwrap())
+          .unwrap();
+      }
+      Mode::Replay => assert_eq!(
+        fixture.next,
+        fixture.exchanges.len(),
+        "{} has exchanges which weren't replayed",
+        fixture.path.display()
+      ),
+    }
+  }
+}
+
+/// A RNG which is deterministically seeded per-test when recording or replaying.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct TestRng;
+
+impl RngCore for TestRng {
+  fn next_u32(&mut self) -> u32 {
+    RNG.lock().unwrap().next_u32()
+  }
+  fn next_u64(&mut self) -> u64 {
+    RNG.lock().unwrap().next_u64()
+  }
+  fn fill_bytes(&mut self, dest: &mut [u8]) {
+    RNG.lock().unwrap().fill_bytes(dest)
+  }
+  fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+    RNG.lock().unwrap().try_fill_bytes(dest)
+  }
+}
+
+impl CryptoRng for TestRng {}
+
+/// A connection which records its exchanges with the node, or replays them without a node, as
+/// selected by the MONERO_SERAI_FIXTURES environment variable.
+#[derive(Clone, Debug)]
+pub struct FixtureRpc(Option<HttpRpc>);
+
+impl FixtureRpc {
+  pub fn new(url: String) -> Rpc<FixtureRpc> {
+    Rpc::new(FixtureRpc(if mode() == Mode::Replay {
+      None
+    } else {
+      Some(HttpRpc::new(url).unwrap().connection().clone())
+    }))
+  }
+}
+
+#[async_trait]
+impl RpcConnection for FixtureRpc {
+  async fn post(&self, route: &str, body: Vec<u8>) -> Result<Vec<u8>, RpcError> {
+    let request = hex::encode(&body);
+
+    let node = if let Some(node) = self.0.as_ref() {
+      node
+    } else {
+      let mut fixture = FIXTURE.lock().unwrap();
+      let fixture = fixture.as_mut().expect("replaying an exchange outside of a test");
+      let exchange = fixture
+        .exchanges
+        .get(fixture.next)
+        .unwrap_or_else(|| panic!("{} ran out of exchanges, re-record it", fixture.path.display()));
+      assert!(
+        (exchange.route == route) && (exchange.request == request),
+        "exchange {} didn't match {}, re-record it",
+        fixture.next,
+        fixture.path.display()
+      );
+      let response = hex::decode(&exchange.response).unwrap();
+      fixture.next += 1;
+      return Ok(response);
+    };
+
+    let response = node.post(route, body).await?;
+    if let Some(fixture) = FIXTURE.lock().unwrap().as_mut() {
+      fixture.exchanges.push(Exchange {
+        route: route.to_string(),
+        request,
+        response: hex::encode(&response),
+      });
+    }
+    Ok(response)
+  }
+}
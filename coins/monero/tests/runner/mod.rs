@@ -4,9 +4,11 @@ use std::collections::HashSet;
 use lazy_static::lazy_static;
 
 use zeroize::Zeroizing;
-use rand_core::OsRng;
 
-use curve25519_dalek::{constants::ED25519_BASEPOINT_TABLE, scalar::Scalar};
+use curve25519_dalek::{
+  constants::{ED25519_BASEPOINT_POINT, ED25519_BASEPOINT_TABLE},
+  scalar::Scalar,
+};
 
 use tokio::sync::Mutex;
 
@@ -20,10 +22,13 @@ use monero_serai::{
   },
 };
 
+pub mod fixture;
+pub use fixture::{TestRng, FixtureRpc};
+
 pub fn random_address() -> (Scalar, ViewPair, MoneroAddress) {
-  let spend = random_scalar(&mut OsRng);
+  let spend = random_scalar(&mut TestRng);
   let spend_pub = &spend * &ED25519_BASEPOINT_TABLE;
-  let view = Zeroizing::new(random_scalar(&mut OsRng));
+  let view = Zeroizing::new(random_scalar(&mut TestRng));
   (
     spend,
     ViewPair::new(spend_pub, view.clone()),
@@ -38,7 +43,8 @@ pub fn random_address() -> (Scalar, ViewPair, MoneroAddress) {
 // TODO: Support transactions already on-chain
 // TODO: Don't have a side effect of mining blocks more blocks than needed under race conditions
 // TODO: mine as much as needed instead of default 10 blocks
-pub async fn mine_until_unlocked(rpc: &Rpc, addr: &str, tx_hash: [u8; 32]) {
+#[allow(dead_code)]
+pub async fn mine_until_unlocked(rpc: &Rpc<FixtureRpc>, addr: &str, tx_hash: [u8; 32]) {
   // mine until tx is in a block
   let mut height = rpc.get_height().await.unwrap();
   let mut found = false;
@@ -60,7 +66,7 @@ pub async fn mine_until_unlocked(rpc: &Rpc, addr: &str, tx_hash: [u8; 32]) {
 
 // Mines 60 blocks and returns an unlocked miner TX output.
 #[allow(dead_code)]
-pub async fn get_miner_tx_output(rpc: &Rpc, view: &ViewPair) -> SpendableOutput {
+pub async fn get_miner_tx_output(rpc: &Rpc<FixtureRpc>, view: &ViewPair) -> SpendableOutput {
   let mut scanner = Scanner::from_view(view.clone(), Some(HashSet::new()));
 
   // Mine 60 blocks to unlock a miner TX
@@ -74,23 +80,24 @@ pub async fn get_miner_tx_output(rpc: &Rpc, view: &ViewPair) -> SpendableOutput
   scanner.scan(rpc, &block).await.unwrap().swap_remove(0).ignore_timelock().swap_remove(0)
 }
 
-pub async fn rpc() -> Rpc {
-  let rpc = Rpc::new("http://127.0.0.1:18081".to_string()).unwrap();
-
-  // Only run once
-  if rpc.get_height().await.unwrap() != 1 {
-    return rpc;
-  }
-
-  let addr = MoneroAddress {
-    meta: AddressMeta::new(Network::Mainnet, AddressType::Standard),
-    spend: &random_scalar(&mut OsRng) * &ED25519_BASEPOINT_TABLE,
-    view: &random_scalar(&mut OsRng) * &ED25519_BASEPOINT_TABLE,
-  }
-  .to_string();
+pub async fn rpc() -> Rpc<FixtureRpc> {
+  let rpc = FixtureRpc::new("http://127.0.0.1:18081".to_string());
 
   // Mine 40 blocks to ensure decoy availability
-  rpc.generate_blocks(&addr, 40).await.unwrap();
+  // This is checked by every test, instead of only by whichever test happened to run first, so
+  // each test's exchanges don't depend on which tests ran before it
+  // The miner is fixed so this doesn't consume any of the test's randomness
+  const BLOCKS: usize = 40;
+  let height = rpc.get_height().await.unwrap();
+  if height <= BLOCKS {
+    let addr = MoneroAddress {
+      meta: AddressMeta::new(Network::Mainnet, AddressType::Standard),
+      spend: ED25519_BASEPOINT_POINT,
+      view: ED25519_BASEPOINT_POINT,
+    }
+    .to_string();
+    rpc.generate_blocks(&addr, (BLOCKS + 1) - height).await.unwrap();
+  }
 
   // Make sure we recognize the protocol
   rpc.get_protocol().await.unwrap();
@@ -109,6 +116,7 @@ macro_rules! async_sequential {
       #[tokio::test]
       async fn $name() {
         let guard = runner::SEQUENTIAL.lock().await;
+        runner::fixture::start(module_path!(), stringify!($name));
         let local = tokio::task::LocalSet::new();
         local.run_until(async move {
           if let Err(err) = tokio::task::spawn_local(async move { $body }).await {
@@ -116,6 +124,7 @@ macro_rules! async_sequential {
             Err(err).unwrap()
           }
         }).await;
+        runner::fixture::finish();
       }
     )*
   }
@@ -142,7 +151,6 @@ macro_rules! test {
         use std::collections::HashMap;
 
         use zeroize::Zeroizing;
-        use rand_core::OsRng;
 
         use curve25519_dalek::constants::ED25519_BASEPOINT_TABLE;
 
@@ -157,13 +165,14 @@ macro_rules! test {
 
         use monero_serai::{
           random_scalar,
+          verify::verify_transactions,
           wallet::{
             address::{Network, AddressSpec}, ViewPair, Scanner, Change, SignableTransaction,
             SignableTransactionBuilder,
           },
         };
 
-        use runner::{random_address, rpc, mine_until_unlocked, get_miner_tx_output};
+        use runner::{TestRng, random_address, rpc, mine_until_unlocked, get_miner_tx_output};
 
         type Builder = SignableTransactionBuilder;
 
@@ -176,9 +185,9 @@ macro_rules! test {
             continue;
           }
 
-          let spend = Zeroizing::new(random_scalar(&mut OsRng));
+          let spend = Zeroizing::new(random_scalar(&mut TestRng));
           #[cfg(feature = "multisig")]
-          let keys = key_gen::<_, Ed25519>(&mut OsRng);
+          let keys = key_gen::<_, Ed25519>(&mut TestRng);
 
           let spend_pub = if !multisig {
             spend.deref() * &ED25519_BASEPOINT_TABLE
@@ -191,7 +200,7 @@ macro_rules! test {
 
           let rpc = rpc().await;
 
-          let view = ViewPair::new(spend_pub, Zeroizing::new(random_scalar(&mut OsRng)));
+          let view = ViewPair::new(spend_pub, Zeroizing::new(random_scalar(&mut TestRng)));
           let addr = view.address(Network::Mainnet, AddressSpec::Standard);
 
           let miner_tx = get_miner_tx_output(&rpc, &view).await;
@@ -201,8 +210,8 @@ macro_rules! test {
             rpc.get_fee().await.unwrap(),
             Some(Change::new(
               &ViewPair::new(
-                &random_scalar(&mut OsRng) * &ED25519_BASEPOINT_TABLE,
-                Zeroizing::new(random_scalar(&mut OsRng))
+                &random_scalar(&mut TestRng) * &ED25519_BASEPOINT_TABLE,
+                Zeroizing::new(random_scalar(&mut TestRng))
               ),
               false
             )),
@@ -215,7 +224,7 @@ macro_rules! test {
             let keys = keys.clone();
             async move {
               if !multisig {
-                tx.sign(&mut OsRng, &rpc, &spend).await.unwrap()
+                tx.sign(&mut TestRng, &rpc, &spend).await.unwrap()
               } else {
                 #[cfg(not(feature = "multisig"))]
                 panic!("Multisig branch called without the multisig feature");
@@ -238,7 +247,7 @@ macro_rules! test {
                     );
                   }
 
-                  frost::tests::sign_without_caching(&mut OsRng, machines, &[])
+                  frost::tests::sign_without_caching(&mut TestRng, machines, &[])
                 }
               }
             }
@@ -252,10 +261,14 @@ macro_rules! test {
             builder.add_input(miner_tx);
             let (tx, state) = ($first_tx)(rpc.clone(), builder, next_addr).await;
 
+            // The fee was calculated for this weight, which the signed transaction can't exceed
+            let weight = tx.weight();
             let signed = sign(tx).await;
+            assert!(signed.weight() <= weight);
             rpc.publish_transaction(&signed).await.unwrap();
             mine_until_unlocked(&rpc, &random_address().2.to_string(), signed.hash()).await;
             let tx = rpc.get_transaction(signed.hash()).await.unwrap();
+            verify_transactions(&mut TestRng, &rpc, &[tx.clone()]).await.unwrap();
             let scanner =
               Scanner::from_view(view.clone(), Some(HashSet::new()));
             ($first_checks)(rpc.clone(), tx, scanner, state).await
@@ -271,10 +284,13 @@ macro_rules! test {
               *carried_state.downcast().unwrap()
             ).await;
 
+            let weight = tx.weight();
             let signed = sign(tx).await;
+            assert!(signed.weight() <= weight);
             rpc.publish_transaction(&signed).await.unwrap();
             mine_until_unlocked(&rpc, &random_address().2.to_string(), signed.hash()).await;
             let tx = rpc.get_transaction(signed.hash()).await.unwrap();
+            verify_transactions(&mut TestRng, &rpc, &[tx.clone()]).await.unwrap();
             #[allow(unused_assignments)]
             {
               let scanner =
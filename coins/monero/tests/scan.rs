@@ -49,7 +49,7 @@ test!(
       let scanner = Scanner::from_view(view.clone(), Some(HashSet::new()));
 
       let mut payment_id = [0u8; 8];
-      OsRng.fill_bytes(&mut payment_id);
+      TestRng.fill_bytes(&mut payment_id);
 
       builder.add_payment(view.address(Network::Mainnet, AddressSpec::Integrated(payment_id)), 5);
       (builder.build().unwrap(), (scanner, payment_id))
@@ -122,7 +122,7 @@ test!(
       let view = runner::random_address().1;
       let scanner = Scanner::from_view(view.clone(), Some(HashSet::new()));
       let mut payment_id = [0u8; 8];
-      OsRng.fill_bytes(&mut payment_id);
+      TestRng.fill_bytes(&mut payment_id);
 
       builder.add_payment(
         view.address(
@@ -156,7 +156,7 @@ test!(
       scanner.register_subaddress(subaddress);
 
       let mut payment_id = [0u8; 8];
-      OsRng.fill_bytes(&mut payment_id);
+      TestRng.fill_bytes(&mut payment_id);
 
       builder.add_payment(
         view.address(
@@ -241,7 +241,7 @@ test!(
       let view = runner::random_address().1;
       let scanner = Scanner::from_view(view.clone(), None);
       let mut payment_id = [0u8; 8];
-      OsRng.fill_bytes(&mut payment_id);
+      TestRng.fill_bytes(&mut payment_id);
 
       builder.add_payment(
         view.address(
@@ -275,7 +275,7 @@ test!(
       scanner.register_subaddress(subaddress);
 
       let mut payment_id = [0u8; 8];
-      OsRng.fill_bytes(&mut payment_id);
+      TestRng.fill_bytes(&mut payment_id);
 
       builder.add_payment(
         view.address(
@@ -1,6 +1,8 @@
 use monero_serai::{
   transaction::Transaction,
-  wallet::{extra::Extra, address::SubaddressIndex, ReceivedOutput, SpendableOutput},
+  wallet::{
+    extra::Extra, address::SubaddressIndex, ReceivedOutput, SpendableOutput, TransactionError,
+  },
   rpc::Rpc,
 };
 
@@ -20,6 +22,31 @@ test!(
   ),
 );
 
+test!(
+  sweep_miner_output,
+  (
+    |_, builder: Builder, addr| async move {
+      // The miner output is worth far more than this threshold
+      assert_eq!(builder.clone().sweep_below(addr, 1), Err(TransactionError::NoInputs));
+
+      let mut with_payment = builder.clone();
+      with_payment.add_payment(addr, 5);
+      assert_eq!(with_payment.sweep(addr), Err(TransactionError::PaymentsInSweep));
+
+      let mut txs = builder.sweep(addr).unwrap();
+      assert_eq!(txs.len(), 1);
+      let tx = txs.swap_remove(0);
+      let swept = tx.payments()[0].1;
+      (tx, swept)
+    },
+    |_, tx: Transaction, mut scanner: Scanner, swept: u64| async move {
+      assert_eq!(tx.prefix.outputs.len(), 2);
+      let output = scanner.scan_transaction(&tx).not_locked().swap_remove(0);
+      assert_eq!(output.commitment().amount, swept);
+    },
+  ),
+);
+
 test!(
   spend_multiple_outputs,
   (
@@ -69,10 +96,10 @@ test!(
     },
   ),
   (
-    |rpc: Rpc, _, _, mut outputs: Vec<ReceivedOutput>| async move {
+    |rpc: Rpc<runner::FixtureRpc>, _, _, mut outputs: Vec<ReceivedOutput>| async move {
       let change_view = ViewPair::new(
-        &random_scalar(&mut OsRng) * &ED25519_BASEPOINT_TABLE,
-        Zeroizing::new(random_scalar(&mut OsRng)),
+        &random_scalar(&mut TestRng) * &ED25519_BASEPOINT_TABLE,
+        Zeroizing::new(random_scalar(&mut TestRng)),
       );
 
       let mut builder = SignableTransactionBuilder::new(
@@ -84,8 +111,8 @@ test!(
 
       // Send to a subaddress
       let sub_view = ViewPair::new(
-        &random_scalar(&mut OsRng) * &ED25519_BASEPOINT_TABLE,
-        Zeroizing::new(random_scalar(&mut OsRng)),
+        &random_scalar(&mut TestRng) * &ED25519_BASEPOINT_TABLE,
+        Zeroizing::new(random_scalar(&mut TestRng)),
       );
       builder.add_payment(
         sub_view
@@ -1,33 +1,74 @@
-use std::{
-  collections::{HashSet, HashMap},
-  str::FromStr,
-};
+use core::ops::Deref;
+use std::{collections::HashSet, str::FromStr};
+
+use rand_core::RngCore;
+
+use zeroize::Zeroizing;
 
-use rand_core::{OsRng, RngCore};
+use curve25519_dalek::{constants::ED25519_BASEPOINT_TABLE, scalar::Scalar};
 
 use serde::Deserialize;
 use serde_json::json;
 
-use monero_rpc::{
-  monero::{
-    Amount, Address,
-    cryptonote::{hash::Hash, subaddress::Index},
-    util::address::PaymentId,
-  },
-  TransferOptions, WalletClient,
-};
-
 use monero_serai::{
   transaction::Transaction,
   rpc::Rpc,
   wallet::{
     address::{Network, AddressSpec, SubaddressIndex, MoneroAddress},
     extra::{MAX_TX_EXTRA_NONCE_SIZE, Extra},
-    Scanner,
+    ViewPair, SubaddressLookahead, Scanner, SignedKeyImage, ExportedOutputs,
+    proofs::{TxProof, SpendProof, ReserveProof},
   },
 };
 
 mod runner;
+use runner::{TestRng, FixtureRpc};
+
+#[derive(Deserialize, Debug)]
+struct EmptyResponse {}
+
+#[derive(Deserialize, Debug)]
+struct AddressResponse {
+  address: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct SubaddressIndexResponse {
+  major: u32,
+  minor: u32,
+}
+
+#[derive(Deserialize, Debug)]
+struct TransferEntry {
+  amount: u64,
+  subaddr_index: SubaddressIndexResponse,
+  payment_id: String,
+}
+
+// The wallet RPC, whose exchanges are recorded and replayed alongside the node's
+fn wallet_rpc() -> Rpc<FixtureRpc> {
+  FixtureRpc::new("http://127.0.0.1:6061".to_string())
+}
+
+async fn refresh(wallet_rpc: &Rpc<FixtureRpc>) {
+  wallet_rpc.json_rpc_call::<EmptyResponse>("refresh", None).await.unwrap();
+}
+
+async fn get_transfer(wallet_rpc: &Rpc<FixtureRpc>, tx: &Transaction) -> TransferEntry {
+  #[derive(Deserialize, Debug)]
+  struct GetTransferResponse {
+    transfer: TransferEntry,
+  }
+
+  wallet_rpc
+    .json_rpc_call::<GetTransferResponse>(
+      "get_transfer_by_txid",
+      Some(json!({ "txid": hex::encode(tx.hash()) })),
+    )
+    .await
+    .unwrap()
+    .transfer
+}
 
 async fn make_integrated_address(payment_id: [u8; 8]) -> String {
   #[derive(Deserialize, Debug)]
@@ -35,8 +76,7 @@ async fn make_integrated_address(payment_id: [u8; 8]) -> String {
     integrated_address: String,
   }
 
-  let rpc = Rpc::new("http://127.0.0.1:6061".to_string()).unwrap();
-  let res = rpc
+  let res = wallet_rpc()
     .json_rpc_call::<IntegratedAddressResponse>(
       "make_integrated_address",
       Some(json!({ "payment_id": hex::encode(payment_id) })),
@@ -47,45 +87,81 @@ async fn make_integrated_address(payment_id: [u8; 8]) -> String {
   res.integrated_address
 }
 
-async fn initialize_rpcs() -> (WalletClient, Rpc, monero_rpc::monero::Address) {
-  let wallet_rpc =
-    monero_rpc::RpcClientBuilder::new().build("http://127.0.0.1:6061").unwrap().wallet();
+async fn query_key(key_type: &str) -> Zeroizing<Scalar> {
+  #[derive(Deserialize, Debug)]
+  struct QueryKeyResponse {
+    key: String,
+  }
+
+  let res = wallet_rpc()
+    .json_rpc_call::<QueryKeyResponse>("query_key", Some(json!({ "key_type": key_type })))
+    .await
+    .unwrap();
+
+  Zeroizing::new(
+    Scalar::from_canonical_bytes(hex::decode(res.key).unwrap().try_into().unwrap()).unwrap(),
+  )
+}
+
+async fn initialize_rpcs() -> (Rpc<FixtureRpc>, Rpc<FixtureRpc>, String) {
+  let wallet_rpc = wallet_rpc();
   let daemon_rpc = runner::rpc().await;
 
-  let address_resp = wallet_rpc.get_address(0, None).await;
-  let wallet_rpc_addr = if address_resp.is_ok() {
-    address_resp.unwrap().address
+  let get_address = || async {
+    wallet_rpc
+      .json_rpc_call::<AddressResponse>("get_address", Some(json!({ "account_index": 0 })))
+      .await
+      .map(|res| res.address)
+  };
+  let wallet_rpc_addr = if let Ok(addr) = get_address().await {
+    addr
   } else {
-    wallet_rpc.create_wallet("wallet".to_string(), None, "English".to_string()).await.unwrap();
-    let addr = wallet_rpc.get_address(0, None).await.unwrap().address;
-    daemon_rpc.generate_blocks(&addr.to_string(), 70).await.unwrap();
+    wallet_rpc
+      .json_rpc_call::<EmptyResponse>(
+        "create_wallet",
+        Some(json!({ "filename": "wallet", "language": "English" })),
+      )
+      .await
+      .unwrap();
+    let addr = get_address().await.unwrap();
+    daemon_rpc.generate_blocks(&addr, 70).await.unwrap();
     addr
   };
   (wallet_rpc, daemon_rpc, wallet_rpc_addr)
 }
 
-async fn from_wallet_rpc_to_self(spec: AddressSpec) {
+async fn transfer_from_wallet_rpc(address: MoneroAddress) -> Transaction {
+  #[derive(Deserialize, Debug)]
+  struct TransferResponse {
+    tx_hash: String,
+  }
+
   // initialize rpc
   let (wallet_rpc, daemon_rpc, wallet_rpc_addr) = initialize_rpcs().await;
 
-  // make an addr
-  let (_, view_pair, _) = runner::random_address();
-  let addr = Address::from_str(&view_pair.address(Network::Mainnet, spec).to_string()[..]).unwrap();
-
   // refresh & make a tx
-  wallet_rpc.refresh(None).await.unwrap();
+  refresh(&wallet_rpc).await;
   let tx = wallet_rpc
-    .transfer(
-      HashMap::from([(addr, Amount::ONE_XMR)]),
-      monero_rpc::TransferPriority::Default,
-      TransferOptions::default(),
+    .json_rpc_call::<TransferResponse>(
+      "transfer",
+      Some(json!({
+        "destinations": [{ "address": address.to_string(), "amount": 1000000000000u64 }],
+      })),
     )
     .await
     .unwrap();
-  let tx_hash: [u8; 32] = tx.tx_hash.0.try_into().unwrap();
+  let tx_hash: [u8; 32] = hex::decode(tx.tx_hash).unwrap().try_into().unwrap();
 
   // unlock it
-  runner::mine_until_unlocked(&daemon_rpc, &wallet_rpc_addr.to_string(), tx_hash).await;
+  runner::mine_until_unlocked(&daemon_rpc, &wallet_rpc_addr, tx_hash).await;
+
+  daemon_rpc.get_transaction(tx_hash).await.unwrap()
+}
+
+async fn from_wallet_rpc_to_self(spec: AddressSpec) {
+  // make an addr
+  let (_, view_pair, _) = runner::random_address();
+  let tx = transfer_from_wallet_rpc(view_pair.address(Network::Mainnet, spec)).await;
 
   // create the scanner
   let mut scanner = Scanner::from_view(view_pair, Some(HashSet::new()));
@@ -93,8 +169,7 @@ async fn from_wallet_rpc_to_self(spec: AddressSpec) {
     scanner.register_subaddress(index);
   }
 
-  // retrieve it and confirm
-  let tx = daemon_rpc.get_transaction(tx_hash).await.unwrap();
+  // confirm receipt
   let output = scanner.scan_transaction(&tx).not_locked().swap_remove(0);
 
   match spec {
@@ -119,9 +194,264 @@ async_sequential!(
 
   async fn receipt_of_wallet_rpc_tx_integrated() {
     let mut payment_id = [0u8; 8];
-    OsRng.fill_bytes(&mut payment_id);
+    TestRng.fill_bytes(&mut payment_id);
     from_wallet_rpc_to_self(AddressSpec::Integrated(payment_id)).await;
   }
+
+  async fn receipt_of_wallet_rpc_tx_subaddress_lookahead() {
+    let (_, view_pair, _) = runner::random_address();
+    let mut scanner = Scanner::from_view(view_pair.clone(), Some(HashSet::new()));
+    scanner.set_subaddress_lookahead(SubaddressLookahead::default());
+
+    // With wallet2's default lookahead, (0, 199) and (49, 0) are the furthest subaddresses found
+    // by a new wallet. Receiving to them extends the lookahead to (0, 398) and (98, 0)
+    for (account, address) in [(0, 199), (0, 398), (49, 0), (98, 0)] {
+      let index = SubaddressIndex::new(account, address).unwrap();
+      let tx = transfer_from_wallet_rpc(
+        view_pair.address(Network::Mainnet, AddressSpec::Subaddress(index)),
+      )
+      .await;
+      let output = scanner.scan_transaction(&tx).not_locked().swap_remove(0);
+      assert_eq!(output.metadata.subaddress, Some(index));
+      assert_eq!(output.commitment().amount, 1000000000000);
+    }
+    assert_eq!(scanner.accounts(), 99);
+    assert_eq!(scanner.addresses(0), 399);
+
+    // Out of the window
+    let index = SubaddressIndex::new(0, 597).unwrap();
+    let tx =
+      transfer_from_wallet_rpc(view_pair.address(Network::Mainnet, AddressSpec::Subaddress(index)))
+        .await;
+    assert!(scanner.scan_transaction(&tx).not_locked().is_empty());
+  }
+
+  async fn wallet_rpc_signed_key_images() {
+    #[derive(Deserialize, Debug)]
+    struct SignedKeyImageResponse {
+      key_image: String,
+      signature: String,
+    }
+
+    #[derive(Deserialize, Debug)]
+    struct ExportKeyImagesResponse {
+      offset: u32,
+      signed_key_images: Vec<SignedKeyImageResponse>,
+    }
+
+    #[derive(Deserialize, Debug)]
+    struct ImportKeyImagesResponse {
+      spent: u64,
+      unspent: u64,
+    }
+
+    let (rpc, _, wallet_rpc_addr) = initialize_rpcs().await;
+    let spend = query_key("spend_key").await;
+    let view_pair =
+      ViewPair::new(spend.deref() * &ED25519_BASEPOINT_TABLE, query_key("view_key").await);
+    let mut scanner = Scanner::from_view(view_pair, None);
+
+    // Have the wallet send to itself, creating outputs we can scan
+    let address = MoneroAddress::from_str(Network::Mainnet, &wallet_rpc_addr).unwrap();
+    let tx = transfer_from_wallet_rpc(address).await;
+    let outputs = scanner.scan_transaction(&tx).not_locked();
+    assert!(!outputs.is_empty());
+    refresh(&rpc).await;
+
+    let exported = rpc
+      .json_rpc_call::<ExportKeyImagesResponse>("export_key_images", Some(json!({ "all": true })))
+      .await
+      .unwrap();
+    assert_eq!(exported.offset, 0);
+
+    for output in outputs {
+      let ours = SignedKeyImage::new(&mut TestRng, &spend, &output);
+
+      // Find wallet2's signed key image for this output, and verify its signature
+      let (offset, theirs) = exported
+        .signed_key_images
+        .iter()
+        .map(|signed| {
+          let mut serialized = hex::decode(&signed.key_image).unwrap();
+          serialized.extend(hex::decode(&signed.signature).unwrap());
+          SignedKeyImage::read(&mut serialized.as_slice()).unwrap()
+        })
+        .enumerate()
+        .find(|(_, signed)| signed.key_image == ours.key_image)
+        .unwrap();
+      assert!(theirs.verify(output.key()));
+
+      // Have wallet2 verify our signature
+      let serialized = ours.serialize();
+      let imported = rpc
+        .json_rpc_call::<ImportKeyImagesResponse>(
+          "import_key_images",
+          Some(json!({
+            "offset": offset,
+            "signed_key_images": [{
+              "key_image": hex::encode(&serialized[.. 32]),
+              "signature": hex::encode(&serialized[32 ..]),
+            }],
+          })),
+        )
+        .await
+        .unwrap();
+      assert_eq!(imported.spent, 0);
+      assert_eq!(imported.unspent, output.commitment().amount);
+    }
+  }
+
+  async fn wallet_rpc_exported_outputs() {
+    #[derive(Deserialize, Debug)]
+    struct ExportOutputsResponse {
+      outputs_data_hex: String,
+    }
+
+    #[derive(Deserialize, Debug)]
+    struct ImportOutputsResponse {
+      num_imported: u64,
+    }
+
+    let (rpc, _, wallet_rpc_addr) = initialize_rpcs().await;
+    let spend = query_key("spend_key").await;
+    let view_pair =
+      ViewPair::new(spend.deref() * &ED25519_BASEPOINT_TABLE, query_key("view_key").await);
+    let mut scanner = Scanner::from_view(view_pair.clone(), None);
+
+    let address = MoneroAddress::from_str(Network::Mainnet, &wallet_rpc_addr).unwrap();
+    let tx = transfer_from_wallet_rpc(address).await;
+    let outputs = scanner.scan_transaction(&tx).not_locked();
+    assert!(!outputs.is_empty());
+    refresh(&rpc).await;
+
+    // Decrypt and read wallet2's export, which must include the outputs we scanned
+    let exported = rpc
+      .json_rpc_call::<ExportOutputsResponse>("export_outputs", Some(json!({ "all": true })))
+      .await
+      .unwrap();
+    let exported =
+      ExportedOutputs::import(&view_pair, &hex::decode(exported.outputs_data_hex).unwrap())
+        .unwrap();
+    assert_eq!(exported.offset, 0);
+    assert_eq!(exported.total, u64::try_from(exported.outputs.len()).unwrap());
+    for output in outputs {
+      let theirs = exported.outputs.iter().find(|theirs| theirs.key == output.key()).unwrap();
+      assert_eq!(theirs.amount, output.commitment().amount);
+      assert_eq!(theirs.key_offset(&view_pair), Some(output.key_offset()));
+    }
+
+    // Have wallet2 decrypt and import our export of them
+    let imported = rpc
+      .json_rpc_call::<ImportOutputsResponse>(
+        "import_outputs",
+        Some(json!({ "outputs_data_hex": hex::encode(exported.export(&mut TestRng, &view_pair)) })),
+      )
+      .await
+      .unwrap();
+    assert_eq!(imported.num_imported, exported.total);
+  }
+
+  async fn wallet_rpc_proofs() {
+    #[derive(Deserialize, Debug)]
+    struct SignatureResponse {
+      signature: String,
+    }
+
+    #[derive(Deserialize, Debug)]
+    struct CheckTxProofResponse {
+      good: bool,
+      received: u64,
+    }
+
+    #[derive(Deserialize, Debug)]
+    struct CheckReserveProofResponse {
+      good: bool,
+      total: u64,
+    }
+
+    let (rpc, daemon_rpc, wallet_rpc_addr) = initialize_rpcs().await;
+
+    // Have wallet2 prove it sent a transaction to us
+    let (_, view_pair, _) = runner::random_address();
+    let index = SubaddressIndex::new(0, 1).unwrap();
+    let address = view_pair.address(Network::Mainnet, AddressSpec::Subaddress(index));
+    let tx = transfer_from_wallet_rpc(address).await;
+    let txid = hex::encode(tx.hash());
+
+    let out_proof = rpc
+      .json_rpc_call::<SignatureResponse>(
+        "get_tx_proof",
+        Some(json!({ "txid": txid, "address": address.to_string(), "message": "message" })),
+      )
+      .await
+      .unwrap();
+    let out_proof = TxProof::from_str(&out_proof.signature).unwrap();
+    assert_eq!(out_proof.verify(&tx, &address, b"message"), Some(1000000000000));
+
+    // Have wallet2 check our proof we received it
+    let in_proof =
+      TxProof::in_proof(&mut TestRng, &tx, &view_pair, Some(index), b"message").unwrap();
+    let checked = rpc
+      .json_rpc_call::<CheckTxProofResponse>(
+        "check_tx_proof",
+        Some(json!({
+          "txid": txid,
+          "address": address.to_string(),
+          "message": "message",
+          "signature": in_proof.to_string(),
+        })),
+      )
+      .await
+      .unwrap();
+    assert!(checked.good);
+    assert_eq!(checked.received, 1000000000000);
+
+    // Have wallet2 prove it sent the transaction, by the key images it spent
+    let spend_proof = rpc
+      .json_rpc_call::<SignatureResponse>(
+        "get_spend_proof",
+        Some(json!({ "txid": txid, "message": "message" })),
+      )
+      .await
+      .unwrap();
+    let spend_proof = SpendProof::from_str(&spend_proof.signature).unwrap();
+    assert!(spend_proof.verify(&daemon_rpc, &tx, b"message").await.unwrap());
+
+    // Prove wallet2's own outputs are held, and have wallet2 check the proof
+    let spend = query_key("spend_key").await;
+    let view_pair =
+      ViewPair::new(spend.deref() * &ED25519_BASEPOINT_TABLE, query_key("view_key").await);
+    let mut scanner = Scanner::from_view(view_pair.clone(), None);
+    let address = MoneroAddress::from_str(Network::Mainnet, &wallet_rpc_addr).unwrap();
+    let tx = transfer_from_wallet_rpc(address).await;
+    let outputs = scanner.scan_transaction(&tx).not_locked();
+    let txs = [tx];
+
+    let reserve_proof =
+      ReserveProof::new(&mut TestRng, &spend, &view_pair, &txs, &outputs, b"message").unwrap();
+    let checked = rpc
+      .json_rpc_call::<CheckReserveProofResponse>(
+        "check_reserve_proof",
+        Some(json!({
+          "address": address.to_string(),
+          "message": "message",
+          "signature": reserve_proof.to_string(),
+        })),
+      )
+      .await
+      .unwrap();
+    assert!(checked.good);
+    assert_eq!(checked.total, outputs.iter().map(|output| output.commitment().amount).sum::<u64>());
+    assert_eq!(
+      reserve_proof
+        .verify(&address, b"message", &txs)
+        .unwrap()
+        .iter()
+        .map(|(_, amount)| amount)
+        .sum::<u64>(),
+      checked.total
+    );
+  }
 );
 
 test!(
@@ -132,19 +462,16 @@ test!(
       let (wallet_rpc, _, wallet_rpc_addr) = initialize_rpcs().await;
 
       // add destination
-      builder.add_payment(
-        MoneroAddress::from_str(Network::Mainnet, &wallet_rpc_addr.to_string()).unwrap(),
-        1000000,
-      );
+      builder
+        .add_payment(MoneroAddress::from_str(Network::Mainnet, &wallet_rpc_addr).unwrap(), 1000000);
       (builder.build().unwrap(), (wallet_rpc,))
     },
-    |_, tx: Transaction, _, data: (WalletClient,)| async move {
+    |_, tx: Transaction, _, data: (Rpc<FixtureRpc>,)| async move {
       // confirm receipt
-      data.0.refresh(None).await.unwrap();
-      let transfer =
-        data.0.get_transfer(Hash::from_slice(&tx.hash()), None).await.unwrap().unwrap();
-      assert_eq!(transfer.amount.as_pico(), 1000000);
-      assert_eq!(transfer.subaddr_index, Index { major: 0, minor: 0 });
+      refresh(&data.0).await;
+      let transfer = get_transfer(&data.0, &tx).await;
+      assert_eq!(transfer.amount, 1000000);
+      assert_eq!((transfer.subaddr_index.major, transfer.subaddr_index.minor), (0, 0));
     },
   ),
 );
@@ -157,21 +484,31 @@ test!(
       let (wallet_rpc, _, _) = initialize_rpcs().await;
 
       // make the addr
-      let (subaddress, index) = wallet_rpc.create_address(0, None).await.unwrap();
+      #[derive(Deserialize, Debug)]
+      struct CreateAddressResponse {
+        address: String,
+        address_index: u32,
+      }
+      let subaddress = wallet_rpc
+        .json_rpc_call::<CreateAddressResponse>(
+          "create_address",
+          Some(json!({ "account_index": 0 })),
+        )
+        .await
+        .unwrap();
 
       builder.add_payment(
-        MoneroAddress::from_str(Network::Mainnet, &subaddress.to_string()).unwrap(),
+        MoneroAddress::from_str(Network::Mainnet, &subaddress.address).unwrap(),
         1000000,
       );
-      (builder.build().unwrap(), (wallet_rpc, index))
+      (builder.build().unwrap(), (wallet_rpc, subaddress.address_index))
     },
-    |_, tx: Transaction, _, data: (WalletClient, u32)| async move {
+    |_, tx: Transaction, _, data: (Rpc<FixtureRpc>, u32)| async move {
       // confirm receipt
-      data.0.refresh(None).await.unwrap();
-      let transfer =
-        data.0.get_transfer(Hash::from_slice(&tx.hash()), None).await.unwrap().unwrap();
-      assert_eq!(transfer.amount.as_pico(), 1000000);
-      assert_eq!(transfer.subaddr_index, Index { major: 0, minor: data.1 });
+      refresh(&data.0).await;
+      let transfer = get_transfer(&data.0, &tx).await;
+      assert_eq!(transfer.amount, 1000000);
+      assert_eq!((transfer.subaddr_index.major, transfer.subaddr_index.minor), (0, data.1));
 
       // Make sure only one R was included in TX extra
       assert!(Extra::read::<&[u8]>(&mut tx.prefix.extra.as_ref())
@@ -193,20 +530,19 @@ test!(
 
       // make the addr
       let mut payment_id = [0u8; 8];
-      OsRng.fill_bytes(&mut payment_id);
+      TestRng.fill_bytes(&mut payment_id);
       let addr = make_integrated_address(payment_id).await;
 
       builder.add_payment(MoneroAddress::from_str(Network::Mainnet, &addr).unwrap(), 1000000);
       (builder.build().unwrap(), (wallet_rpc, payment_id))
     },
-    |_, tx: Transaction, _, data: (WalletClient, [u8; 8])| async move {
+    |_, tx: Transaction, _, data: (Rpc<FixtureRpc>, [u8; 8])| async move {
       // confirm receipt
-      data.0.refresh(None).await.unwrap();
-      let transfer =
-        data.0.get_transfer(Hash::from_slice(&tx.hash()), None).await.unwrap().unwrap();
-      assert_eq!(transfer.amount.as_pico(), 1000000);
-      assert_eq!(transfer.subaddr_index, Index { major: 0, minor: 0 });
-      assert_eq!(transfer.payment_id.0, PaymentId::from_slice(&data.1));
+      refresh(&data.0).await;
+      let transfer = get_transfer(&data.0, &tx).await;
+      assert_eq!(transfer.amount, 1000000);
+      assert_eq!((transfer.subaddr_index.major, transfer.subaddr_index.minor), (0, 0));
+      assert_eq!(transfer.payment_id, hex::encode(data.1));
     },
   ),
 );
@@ -219,10 +555,8 @@ test!(
       let (wallet_rpc, _, wallet_rpc_addr) = initialize_rpcs().await;
 
       // add destination
-      builder.add_payment(
-        MoneroAddress::from_str(Network::Mainnet, &wallet_rpc_addr.to_string()).unwrap(),
-        1000000,
-      );
+      builder
+        .add_payment(MoneroAddress::from_str(Network::Mainnet, &wallet_rpc_addr).unwrap(), 1000000);
 
       // Make 2 data that is the full 255 bytes
       for _ in 0 .. 2 {
@@ -233,13 +567,12 @@ test!(
 
       (builder.build().unwrap(), (wallet_rpc,))
     },
-    |_, tx: Transaction, _, data: (WalletClient,)| async move {
+    |_, tx: Transaction, _, data: (Rpc<FixtureRpc>,)| async move {
       // confirm receipt
-      data.0.refresh(None).await.unwrap();
-      let transfer =
-        data.0.get_transfer(Hash::from_slice(&tx.hash()), None).await.unwrap().unwrap();
-      assert_eq!(transfer.amount.as_pico(), 1000000);
-      assert_eq!(transfer.subaddr_index, Index { major: 0, minor: 0 });
+      refresh(&data.0).await;
+      let transfer = get_transfer(&data.0, &tx).await;
+      assert_eq!(transfer.amount, 1000000);
+      assert_eq!((transfer.subaddr_index.major, transfer.subaddr_index.minor), (0, 0));
     },
   ),
 );
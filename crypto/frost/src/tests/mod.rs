@@ -76,9 +76,13 @@ pub(crate) fn preprocess<
   mut machines: HashMap<Participant, M>,
   mut cache: F,
 ) -> (HashMap<Participant, M::SignMachine>, HashMap<Participant, M::Preprocess>) {
+  // Preprocess in order of participant, so a seeded RNG always yields each the same nonces
+  let mut ordered = machines.drain().collect::<Vec<_>>();
+  ordered.sort_by_key(|(i, _)| *i);
+
   let mut commitments = HashMap::new();
-  let mut machines = machines
-    .drain()
+  let mut machines = ordered
+    .drain(..)
     .map(|(i, machine)| {
       let (machine, preprocess) = machine.preprocess(rng);
       commitments.insert(i, {
@@ -128,6 +128,8 @@ pub mod substrate {
   #[derive(Clone, PartialEq, Eq, Debug, Zeroize, Serialize, Deserialize)]
   pub enum ProcessorMessage {
     Update { key: Vec<u8>, block: Vec<u8>, instructions: Vec<InInstructionWithBalance> },
+    // Burns which will never be fulfilled, as their data exceeds what the coin can include.
+    RejectedBurns { context: SubstrateContext, burns: Vec<OutInstructionWithBalance> },
   }
 }
 
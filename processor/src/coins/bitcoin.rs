@@ -28,7 +28,7 @@ use bitcoin_serai::{
   rpc::{RpcError, Rpc},
 };
 
-#[cfg(test)]
+#[cfg(any(test, feature = "tests"))]
 use bitcoin_serai::bitcoin::{
   secp256k1::{SECP256K1, SecretKey, Message},
   PrivateKey, PublicKey, EcdsaSighashType,
@@ -141,7 +141,7 @@ impl TransactionTrait<Bitcoin> for Transaction {
   fn serialize(&self) -> Vec<u8> {
     Serialize::serialize(self)
   }
-  #[cfg(test)]
+  #[cfg(any(test, feature = "tests"))]
   async fn fee(&self, coin: &Bitcoin) -> u64 {
     let mut value = 0;
     for input in &self.input {
@@ -236,6 +236,7 @@ fn scanner(
 #[derive(Clone, Debug)]
 pub struct Bitcoin {
   pub(crate) rpc: Rpc,
+  confirmations: usize,
 }
 // Shim required for testing/debugging purposes due to generic arguments also necessitating trait
 // bounds
@@ -247,11 +248,11 @@ impl PartialEq for Bitcoin {
 impl Eq for Bitcoin {}
 
 impl Bitcoin {
-  pub async fn new(url: String) -> Bitcoin {
-    Bitcoin { rpc: Rpc::new(url).await.expect("couldn't create a Bitcoin RPC") }
+  pub async fn new(url: String, confirmations: usize) -> Bitcoin {
+    Bitcoin { rpc: Rpc::new(url).await.expect("couldn't create a Bitcoin RPC"), confirmations }
   }
 
-  #[cfg(test)]
+  #[cfg(any(test, feature = "tests"))]
   pub async fn fresh_chain(&self) {
     if self.rpc.get_latest_block_number().await.unwrap() > 0 {
       self
@@ -305,6 +306,14 @@ impl Coin for Bitcoin {
   const MAX_INPUTS: usize = 520;
   const MAX_OUTPUTS: usize = 520;
 
+  // Bitcoin only relays a single OP_RETURN output, with a max of 80 bytes of data
+  const MAX_PAYMENT_DATA: usize = 80;
+  const MAX_DATA_PAYMENTS: usize = 1;
+
+  fn confirmations(&self) -> usize {
+    self.confirmations
+  }
+
   fn tweak_keys(keys: &mut ThresholdKeys<Self::Curve>) {
     *keys = tweak_keys(keys);
     // Also create a scanner to assert these keys, and all expected paths, are usable
@@ -458,7 +467,8 @@ impl Coin for Bitcoin {
           let (_, offsets, _) = scanner(key);
           Self::address(key + (ProjectivePoint::GENERATOR * offsets[&OutputType::Change])).0
         }),
-        None,
+        // The scheduler ensures there's at most one payment with data per plan
+        plan.payments.iter().find_map(|payment| payment.data.clone()),
         fee.0,
       ) {
         Ok(signable) => Some(signable),
@@ -467,7 +477,9 @@ impl Coin for Bitcoin {
         }
         // No outputs left and the change isn't worth enough
         Err(TransactionError::NoOutputs) => None,
-        Err(TransactionError::TooMuchData) => panic!("too much data despite not specifying data"),
+        Err(TransactionError::TooMuchData) => {
+          panic!("too much data despite the scheduler limiting data")
+        }
         Err(TransactionError::NotEnoughFunds) => {
           if tx_fee.is_none() {
             // Mot even enough funds to pay the fee
@@ -536,17 +548,17 @@ impl Coin for Bitcoin {
     eventuality == &tx.input[0].previous_output
   }
 
-  #[cfg(test)]
+  #[cfg(any(test, feature = "tests"))]
   async fn get_block_number(&self, id: &[u8; 32]) -> usize {
     self.rpc.get_block_number(id).await.unwrap()
   }
 
-  #[cfg(test)]
+  #[cfg(any(test, feature = "tests"))]
   async fn get_fee(&self) -> Self::Fee {
     Fee(1)
   }
 
-  #[cfg(test)]
+  #[cfg(any(test, feature = "tests"))]
   async fn mine_block(&self) {
     self
       .rpc
@@ -558,7 +570,7 @@ impl Coin for Bitcoin {
       .unwrap();
   }
 
-  #[cfg(test)]
+  #[cfg(any(test, feature = "tests"))]
   async fn test_send(&self, address: Self::Address) -> Block {
     let secret_key = SecretKey::new(&mut rand_core::OsRng);
     let private_key = PrivateKey::new(secret_key, Network::Regtest);
@@ -607,7 +619,7 @@ impl Coin for Bitcoin {
 
     let block = self.get_latest_block_number().await.unwrap() + 1;
     self.rpc.send_raw_transaction(&tx).await.unwrap();
-    for _ in 0 .. Self::CONFIRMATIONS {
+    for _ in 0 .. self.confirmations {
       self.mine_block().await;
     }
     self.get_block(block).await.unwrap()
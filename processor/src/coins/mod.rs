@@ -28,6 +28,8 @@ use crate::Plan;
 pub enum CoinError {
   #[error("failed to connect to coin daemon")]
   ConnectionError,
+  #[error("inputs weren't spendable yet")]
+  NotSpendable,
 }
 
 pub trait Id:
@@ -113,7 +115,7 @@ pub trait Transaction<C: Coin>: Send + Sync + Sized + Clone + Debug {
   fn id(&self) -> Self::Id;
   fn serialize(&self) -> Vec<u8>;
 
-  #[cfg(test)]
+  #[cfg(any(test, feature = "tests"))]
   async fn fee(&self, coin: &C) -> u64;
 }
 
@@ -163,11 +165,40 @@ pub trait Block<C: Coin>: Send + Sync + Sized + Clone + Debug {
 }
 
 // The post-fee value of an expected branch.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub struct PostFeeBranch {
   pub expected: u64,
   pub actual: Option<u64>,
 }
 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Network {
+  Mainnet,
+  Testnet,
+  Regtest,
+}
+
+impl TryFrom<&str> for Network {
+  type Error = ();
+  fn try_from(network: &str) -> Result<Network, ()> {
+    match network {
+      "mainnet" => Ok(Network::Mainnet),
+      "testnet" => Ok(Network::Testnet),
+      "regtest" => Ok(Network::Regtest),
+      _ => Err(()),
+    }
+  }
+}
+
+// Determine the amount of confirmations a deployment should use
+// Regtest may use as few as one confirmation, yet all other networks must use at least
+// C::CONFIRMATIONS. If no amount is configured, C::CONFIRMATIONS is used
+// Returns None if the configured amount is too low for the network
+pub fn confirmations<C: Coin>(network: Network, configured: Option<usize>) -> Option<usize> {
+  let minimum = if network == Network::Regtest { 1 } else { C::CONFIRMATIONS };
+  Some(configured.unwrap_or(C::CONFIRMATIONS)).filter(|confirmations| *confirmations >= minimum)
+}
+
 // Return the PostFeeBranches needed when dropping a transaction
 pub fn drop_branches<C: Coin>(plan: &Plan<C>) -> Vec<PostFeeBranch> {
   let mut branch_outputs = vec![];
@@ -251,7 +282,8 @@ pub trait Coin: 'static + Send + Sync + Clone + PartialEq + Eq + Debug {
 
   /// String ID for this coin.
   const ID: &'static str;
-  /// The amount of confirmations required to consider a block 'final'.
+  /// The minimum amount of confirmations required to consider a block 'final'.
+  /// Deployments may require more via `confirmations`.
   const CONFIRMATIONS: usize;
   /// The maximum amount of inputs which will fit in a TX.
   /// This should be equal to MAX_OUTPUTS unless one is specifically limited.
@@ -262,9 +294,18 @@ pub trait Coin: 'static + Send + Sync + Clone + PartialEq + Eq + Debug {
   /// A TX with MAX_INPUTS and MAX_OUTPUTS must not exceed the max size.
   const MAX_OUTPUTS: usize;
 
+  /// The maximum length of the data which may be attached to a single payment.
+  const MAX_PAYMENT_DATA: usize;
+  /// The maximum amount of payments with data which will fit in a TX.
+  /// Payments with data beyond this will be scheduled into their own transactions.
+  const MAX_DATA_PAYMENTS: usize;
+
   /// Minimum output value which will be handled.
   const DUST: u64;
 
+  /// The amount of confirmations this deployment requires to consider a block 'final'.
+  fn confirmations(&self) -> usize;
+
   /// Tweak keys for this coin.
   fn tweak_keys(key: &mut ThresholdKeys<Self::Curve>);
 
@@ -285,6 +326,20 @@ pub trait Coin: 'static + Send + Sync + Clone + PartialEq + Eq + Debug {
     key: <Self::Curve as Ciphersuite>::G,
   ) -> Result<Vec<Self::Output>, CoinError>;
 
+  /// Split outputs into those which can be used as of the specified block and those which are
+  /// still locked.
+  ///
+  /// The Scanner holds locked outputs, checking them again whenever it scans a block, and only
+  /// emits them once they've unlocked. This must solely depend on the block, not the current
+  /// state of the chain, so every processor releases an output with the same block.
+  async fn unlocked(
+    &self,
+    _: &Self::Block,
+    outputs: Vec<Self::Output>,
+  ) -> Result<(Vec<Self::Output>, Vec<Self::Output>), CoinError> {
+    Ok((outputs, vec![]))
+  }
+
   /// Get the registered eventualities completed within this block, and any prior blocks which
   /// registered eventualities may have been completed in.
   async fn get_eventuality_completions(
@@ -295,6 +350,8 @@ pub trait Coin: 'static + Send + Sync + Clone + PartialEq + Eq + Debug {
 
   /// Prepare a SignableTransaction for a transaction.
   /// Returns None for the transaction if the SignableTransaction was dropped due to lack of value.
+  /// Returns NotSpendable if the inputs can't be spent as of the specified block, which must not
+  /// depend on the current state of the chain.
   #[rustfmt::skip]
   async fn prepare_send(
     &self,
@@ -329,17 +386,17 @@ pub trait Coin: 'static + Send + Sync + Clone + PartialEq + Eq + Debug {
   fn confirm_completion(&self, eventuality: &Self::Eventuality, tx: &Self::Transaction) -> bool;
 
   /// Get a block's number by its ID.
-  #[cfg(test)]
+  #[cfg(any(test, feature = "tests"))]
   async fn get_block_number(&self, id: &<Self::Block as Block<Self>>::Id) -> usize;
 
-  #[cfg(test)]
+  #[cfg(any(test, feature = "tests"))]
   async fn get_fee(&self) -> Self::Fee;
 
-  #[cfg(test)]
+  #[cfg(any(test, feature = "tests"))]
   async fn mine_block(&self);
 
   /// Sends to the specified address.
   /// Additionally mines enough blocks so that the TX is past the confirmation depth.
-  #[cfg(test)]
+  #[cfg(any(test, feature = "tests"))]
   async fn test_send(&self, key: Self::Address) -> Self::Block;
 }
@@ -14,12 +14,13 @@ use monero_serai::{
   Protocol,
   transaction::Transaction,
   block::Block as MBlock,
-  rpc::{RpcError, Rpc},
+  rpc::{RpcError, HttpRpc, Rpc},
   wallet::{
     ViewPair, Scanner,
     address::{Network, SubaddressIndex, AddressSpec},
-    Fee, SpendableOutput, Change, TransactionError, SignableTransaction as MSignableTransaction,
-    Eventuality, TransactionMachine,
+    extra::MAX_ARBITRARY_DATA_SIZE,
+    Fee, SpendableOutput, Spendability, OutputLock, Change, TransactionError,
+    SignableTransaction as MSignableTransaction, Eventuality, TransactionMachine,
   },
 };
 
@@ -102,7 +103,7 @@ impl TransactionTrait<Monero> for Transaction {
   fn serialize(&self) -> Vec<u8> {
     self.serialize()
   }
-  #[cfg(test)]
+  #[cfg(any(test, feature = "tests"))]
   async fn fee(&self, _: &Monero) -> u64 {
     self.rct_signatures.base.fee
   }
@@ -150,7 +151,8 @@ impl BlockTrait<Monero> for Block {
 
 #[derive(Clone, Debug)]
 pub struct Monero {
-  rpc: Rpc,
+  rpc: Rpc<HttpRpc>,
+  confirmations: usize,
 }
 // Shim required for testing/debugging purposes due to generic arguments also necessitating trait
 // bounds
@@ -162,8 +164,8 @@ impl PartialEq for Monero {
 impl Eq for Monero {}
 
 impl Monero {
-  pub fn new(url: String) -> Monero {
-    Monero { rpc: Rpc::new(url).unwrap() }
+  pub fn new(url: String, confirmations: usize) -> Monero {
+    Monero { rpc: HttpRpc::new(url).unwrap(), confirmations }
   }
 
   fn view_pair(spend: EdwardsPoint) -> ViewPair {
@@ -186,17 +188,25 @@ impl Monero {
     scanner
   }
 
-  #[cfg(test)]
+  // If an output under this lock should be held until it unlocks, when the chain has the
+  // specified height and its latest block has the specified timestamp
+  // Outputs which are solely young, as possible with less than 10 confirmations, aren't held, as
+  // they'll be spendable within a few blocks and prepare_send defers spending them until then
+  pub(crate) fn held(lock: &OutputLock, height: usize, time: u64) -> bool {
+    !matches!(lock.spendability(height, time), Spendability::Spendable | Spendability::Young(_))
+  }
+
+  #[cfg(any(test, feature = "tests"))]
   fn test_view_pair() -> ViewPair {
     ViewPair::new(*EdwardsPoint::generator(), Zeroizing::new(Scalar::ONE.0))
   }
 
-  #[cfg(test)]
+  #[cfg(any(test, feature = "tests"))]
   fn test_scanner() -> Scanner {
     Scanner::from_view(Self::test_view_pair(), Some(std::collections::HashSet::new()))
   }
 
-  #[cfg(test)]
+  #[cfg(any(test, feature = "tests"))]
   fn test_address() -> Address {
     Address::new(Self::test_view_pair().address(Network::Mainnet, AddressSpec::Standard)).unwrap()
   }
@@ -222,14 +232,27 @@ impl Coin for Monero {
 
   // wallet2 will not create a transaction larger than 100kb, and Monero won't relay a transaction
   // larger than 150kb. This fits within the 100kb mark
-  // Technically, it can be ~124, yet a small bit of buffer is appreciated
-  // TODO: Test creating a TX this big
+  // With the maximum amount of outputs and data, it can be 121, yet a small bit of buffer is
+  // appreciated. monero-serai's tests create a TX this big
   const MAX_INPUTS: usize = 120;
   const MAX_OUTPUTS: usize = 16;
 
+  // TX extra is limited to 1060 bytes. With 16 outputs, it has up to 558 bytes of keys:
+  // - The TX key, with its tag, is 33 bytes
+  // - The 16 additional keys, with their tag and length, are 2 + (16 * 32) = 514 bytes
+  // - A payment ID, with its nonce tag, length, and tag, is 3 + 8 = 11 bytes
+  // A maximally sized data field, with its nonce tag, 2-byte length, and marker, is 4 + 254 = 258
+  // bytes. Only one fits in the remaining 502 bytes, as monero-serai's max_data test checks
+  const MAX_PAYMENT_DATA: usize = MAX_ARBITRARY_DATA_SIZE;
+  const MAX_DATA_PAYMENTS: usize = 1;
+
   // 0.01 XMR
   const DUST: u64 = 10000000000;
 
+  fn confirmations(&self) -> usize {
+    self.confirmations
+  }
+
   // Monero doesn't require/benefit from tweaking
   fn tweak_keys(_: &mut ThresholdKeys<Self::Curve>) {}
 
@@ -262,7 +285,9 @@ impl Coin for Monero {
       .await
       .map_err(|_| CoinError::ConnectionError)?
       .iter()
-      .filter_map(|outputs| Some(outputs.not_locked()).filter(|outputs| !outputs.is_empty()))
+      // Locked outputs are returned as well, with the Scanner holding them until unlocked says
+      // they've unlocked
+      .map(|timelocked| timelocked.ignore_timelock())
       .collect::<Vec<_>>();
 
     // This should be pointless as we shouldn't be able to scan for any other subaddress
@@ -293,6 +318,40 @@ impl Coin for Monero {
     Ok(outputs)
   }
 
+  async fn unlocked(
+    &self,
+    block: &Block,
+    outputs: Vec<Output>,
+  ) -> Result<(Vec<Output>, Vec<Output>), CoinError> {
+    if outputs.is_empty() {
+      return Ok((vec![], vec![]));
+    }
+
+    let locks = self
+      .rpc
+      .get_output_locks(&outputs.iter().map(|output| output.0.global_index).collect::<Vec<_>>())
+      .await
+      .map_err(|_| CoinError::ConnectionError)?;
+    // Whether the outputs could be spent by a transaction in the next block
+    let (height, time) = (block.1.number() + 1, block.1.header.timestamp);
+
+    let mut unlocked = vec![];
+    let mut locked = vec![];
+    for (output, lock) in outputs.into_iter().zip(locks) {
+      if Self::held(&lock, height, time) {
+        log::debug!(
+          "holding output {} until it unlocks: {:?}",
+          hex::encode(output.id()),
+          lock.spendability(height, time),
+        );
+        locked.push(output);
+      } else {
+        unlocked.push(output);
+      }
+    }
+    Ok((unlocked, locked))
+  }
+
   async fn get_eventuality_completions(
     &self,
     eventualities: &mut EventualitiesTracker<Eventuality>,
@@ -389,6 +448,29 @@ impl Coin for Monero {
     // Check a fork hasn't occurred which this processor hasn't been updated for
     assert_eq!(protocol, self.rpc.get_protocol().await.map_err(|_| CoinError::ConnectionError)?);
 
+    // Defer this send until every input can be spent, instead of failing when it's published
+    {
+      let locks = self
+        .rpc
+        .get_output_locks(&plan.inputs.iter().map(|input| input.0.global_index).collect::<Vec<_>>())
+        .await
+        .map_err(|_| CoinError::ConnectionError)?;
+      // This is evaluated against the specified block, not the current tip, so every processor
+      // defers the same plans
+      let height = block_number + 1;
+      let time = self.get_block(block_number).await?.1.header.timestamp;
+      for lock in locks {
+        let spendability = lock.spendability(height, time);
+        if spendability != Spendability::Spendable {
+          log::info!(
+            "plan {} has an input which isn't spendable yet: {spendability:?}",
+            hex::encode(plan.id())
+          );
+          Err(CoinError::NotSpendable)?;
+        }
+      }
+    }
+
     let signable = |plan: &mut Plan<Self>, tx_fee: Option<_>| {
       // Monero requires at least two outputs
       // If we only have one output planned, add a dummy payment
@@ -428,7 +510,7 @@ impl Coin for Monero {
         plan.change.map(|key| {
           Change::fingerprintable(Self::address_internal(key, CHANGE_SUBADDRESS).into())
         }),
-        vec![],
+        plan.payments.iter().filter_map(|payment| payment.data.clone()).collect(),
         fee,
       ) {
         Ok(signable) => Ok(Some(signable)),
@@ -439,9 +521,10 @@ impl Coin for Monero {
           TransactionError::NoInputs |
           TransactionError::NoOutputs |
           TransactionError::NoChange |
+          TransactionError::PaymentsInSweep |
           TransactionError::TooManyOutputs |
           TransactionError::TooMuchData |
-          TransactionError::TooLargeTransaction |
+          TransactionError::TooLargeTransaction(_) |
           TransactionError::WrongPrivateKey => {
             panic!("created an Monero invalid transaction: {e}");
           }
@@ -520,17 +603,17 @@ impl Coin for Monero {
     eventuality.matches(tx)
   }
 
-  #[cfg(test)]
+  #[cfg(any(test, feature = "tests"))]
   async fn get_block_number(&self, id: &[u8; 32]) -> usize {
     self.rpc.get_block(*id).await.unwrap().number()
   }
 
-  #[cfg(test)]
+  #[cfg(any(test, feature = "tests"))]
   async fn get_fee(&self) -> Self::Fee {
     self.rpc.get_fee().await.unwrap()
   }
 
-  #[cfg(test)]
+  #[cfg(any(test, feature = "tests"))]
   async fn mine_block(&self) {
     // https://github.com/serai-dex/serai/issues/198
     sleep(std::time::Duration::from_millis(100)).await;
@@ -553,7 +636,7 @@ impl Coin for Monero {
       .unwrap();
   }
 
-  #[cfg(test)]
+  #[cfg(any(test, feature = "tests"))]
   async fn test_send(&self, address: Self::Address) -> Block {
     use zeroize::Zeroizing;
     use rand_core::OsRng;
@@ -590,7 +673,7 @@ impl Coin for Monero {
 
     let block = self.get_latest_block_number().await.unwrap() + 1;
     self.rpc.publish_transaction(&tx).await.unwrap();
-    for _ in 0 .. 10 {
+    for _ in 0 .. self.confirmations() {
       self.mine_block().await;
     }
     self.get_block(block).await.unwrap()
@@ -22,6 +22,8 @@ pub trait Db: 'static + Send + Sync + Clone + Debug {
   }
   fn txn(&mut self) -> Self::Transaction;
   fn get(&self, key: impl AsRef<[u8]>) -> Option<Vec<u8>>;
+  /// Every entry in the DB, as can be decoded by `inspect::inspect`.
+  fn entries(&self) -> Vec<(Vec<u8>, Vec<u8>)>;
 }
 
 // TODO: Replace this with RocksDB
@@ -55,6 +57,9 @@ impl Db for MemDb {
   fn get(&self, key: impl AsRef<[u8]>) -> Option<Vec<u8>> {
     self.0.read().unwrap().get(key.as_ref()).cloned()
   }
+  fn entries(&self) -> Vec<(Vec<u8>, Vec<u8>)> {
+    self.0.read().unwrap().iter().map(|(key, value)| (key.clone(), value.clone())).collect()
+  }
 }
 
 #[derive(Debug)]
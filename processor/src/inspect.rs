@@ -0,0 +1,249 @@
+use std::collections::HashMap;
+
+use zeroize::Zeroizing;
+
+use group::GroupEncoding;
+use frost::{
+  curve::{Ciphersuite, Ristretto},
+  dkg::{Participant, ThresholdParams, ThresholdCore},
+};
+
+use serde_json::{Value, json};
+
+use serai_client::validator_sets::primitives::ValidatorSet;
+use messages::{key_gen::KeyGenId, sign::SignId};
+
+use crate::{
+  Db, KeyStore, Plan,
+  coins::{Output, Transaction, Eventuality, Block, Coin},
+};
+
+/// Split a DB key, as created by `Db::key`, into its DB DST, item DST, and the key itself.
+fn split_key(key: &[u8]) -> Option<(&[u8], &[u8], &[u8])> {
+  let (db_len, key) = key.split_first()?;
+  let db_len = usize::from(*db_len);
+  if key.len() < db_len {
+    return None;
+  }
+  let (db_dst, key) = key.split_at(db_len);
+
+  let (dst_len, key) = key.split_first()?;
+  let dst_len = usize::from(*dst_len);
+  if key.len() < dst_len {
+    return None;
+  }
+  let (item_dst, key) = key.split_at(dst_len);
+
+  Some((db_dst, item_dst, key))
+}
+
+fn u64_value(value: &[u8]) -> Option<Value> {
+  Some(json!(u64::from_le_bytes(value.try_into().ok()?)))
+}
+
+fn chunks(value: &[u8], len: usize) -> Option<Value> {
+  if (len == 0) || ((value.len() % len) != 0) {
+    return None;
+  }
+  Some(value.chunks(len).map(hex::encode).collect::<Vec<_>>().into())
+}
+
+fn output<C: Coin>(output: &C::Output) -> Value {
+  json!({
+    "id": hex::encode(output.id()),
+    "kind": format!("{:?}", output.kind()),
+    "amount": output.amount(),
+    "data": hex::encode(output.data()),
+  })
+}
+
+fn outputs<C: Coin>(mut value: &[u8]) -> Option<Value> {
+  let mut outputs = vec![];
+  while !value.is_empty() {
+    outputs.push(output::<C>(&C::Output::read(&mut value).ok()?));
+  }
+  Some(outputs.into())
+}
+
+fn plan<C: Coin>(value: &[u8]) -> Option<Value> {
+  if value.len() < 16 {
+    return None;
+  }
+  let block_number = u64::from_le_bytes(value[.. 8].try_into().unwrap());
+  let time = u64::from_le_bytes(value[8 .. 16].try_into().unwrap());
+  let plan = Plan::<C>::read::<&[u8]>(&mut &value[16 ..]).ok()?;
+
+  Some(json!({
+    "block_number": block_number,
+    "time": time,
+    "id": hex::encode(plan.id()),
+    "key": hex::encode(plan.key.to_bytes()),
+    "inputs": plan.inputs.iter().map(output::<C>).collect::<Vec<_>>(),
+    "payments": plan.payments.iter().map(|payment| json!({
+      "address": payment.address.to_string(),
+      "data": payment.data.as_ref().map(hex::encode),
+      "amount": payment.amount,
+    })).collect::<Vec<_>>(),
+    "change": plan.change.map(|change| hex::encode(change.to_bytes())),
+  }))
+}
+
+fn params(params: ThresholdParams) -> Value {
+  json!({ "t": params.t(), "n": params.n(), "i": u16::from(params.i()) })
+}
+
+// Solely reports the public components of the keys, never the secret shares
+fn keys<C: Coin, D: Db>(
+  keystore: Option<&KeyStore<D>>,
+  db_key: &[u8],
+  value: &[u8],
+) -> Option<Value> {
+  let Some(keystore) = keystore else {
+    return Some(json!({ "encrypted": value.len() }));
+  };
+  let value = keystore.decrypt(db_key, value).ok()?;
+  let mut value = value.as_slice();
+  let substrate = Zeroizing::new(ThresholdCore::<Ristretto>::read(&mut value).ok()?);
+  let coin = Zeroizing::new(ThresholdCore::<C::Curve>::read(&mut value).ok()?);
+  Some(json!({
+    "substrate": {
+      "params": params(substrate.params()),
+      "group_key": hex::encode(substrate.group_key().to_bytes()),
+    },
+    "coin": {
+      "params": params(coin.params()),
+      "group_key": hex::encode(coin.group_key().to_bytes()),
+    },
+  }))
+}
+
+/// Decode a single DB entry into JSON, returning None if it isn't recognized.
+fn decode<C: Coin, D: Db>(
+  keystore: Option<&KeyStore<D>>,
+  db_key: &[u8],
+  value: &[u8],
+) -> Option<Value> {
+  let (db_dst, item_dst, key) = split_key(db_key)?;
+
+  let tx_id_len = <C::Transaction as Transaction<C>>::Id::default().as_ref().len();
+  let block_id_len = <C::Block as Block<C>>::Id::default().as_ref().len();
+  let g_len = <C::Curve as Ciphersuite>::generator().to_bytes().as_ref().len();
+
+  Some(match (db_dst, item_dst) {
+    (b"MAIN", b"plan") => json!({ "id": hex::encode(key), "plan": plan::<C>(value)? }),
+    (b"MAIN", b"signing") => json!({ "key": hex::encode(key), "plans": chunks(value, 32)? }),
+
+    (b"SCANNER", b"block_id") => json!({
+      "number": u64::from_le_bytes(key.try_into().ok()?),
+      "id": hex::encode(value),
+    }),
+    (b"SCANNER", b"block_number") if key.len() == block_id_len => {
+      json!({ "id": hex::encode(key), "number": u64_value(value)? })
+    }
+    (b"SCANNER", b"active_keys") => json!({ "keys": chunks(value, g_len)? }),
+    (b"SCANNER", b"seen") => json!({ "output": hex::encode(key) }),
+    (b"SCANNER", b"outputs") => {
+      let (output_key, block) = bincode::deserialize::<(Vec<u8>, Vec<u8>)>(key).ok()?;
+      json!({
+        "key": hex::encode(output_key),
+        "block": hex::encode(block),
+        "outputs": outputs::<C>(value)?,
+      })
+    }
+    (b"SCANNER", b"locked_outputs") => {
+      json!({ "key": hex::encode(key), "outputs": outputs::<C>(value)? })
+    }
+    (b"SCANNER", b"scanned_block") => {
+      json!({ "key": hex::encode(key), "block": u64_value(value)? })
+    }
+
+    (b"SIGNER", b"completed") => {
+      json!({ "plan": hex::encode(key), "transactions": chunks(value, tx_id_len)? })
+    }
+    (b"SIGNER", b"eventuality") => json!({
+      "plan": hex::encode(key),
+      "lookup": hex::encode(C::Eventuality::read::<&[u8]>(&mut value.as_ref()).ok()?.lookup()),
+    }),
+    (b"SIGNER", b"attempt") => {
+      let id = bincode::deserialize::<SignId>(key).ok()?;
+      json!({ "key": hex::encode(id.key), "id": hex::encode(id.id), "attempt": id.attempt })
+    }
+    (b"SIGNER", b"tx") => json!({ "id": hex::encode(key), "transaction": hex::encode(value) }),
+
+    (b"KEY_GEN", b"params") => json!({
+      "set": bincode::deserialize::<ValidatorSet>(key).ok()?,
+      "params": params(bincode::deserialize::<ThresholdParams>(value).ok()?),
+    }),
+    (b"KEY_GEN", b"commitments") => {
+      let commitments = bincode::deserialize::<HashMap<Participant, Vec<u8>>>(value).ok()?;
+      let mut commitments = commitments.into_iter().collect::<Vec<_>>();
+      commitments.sort_by_key(|(i, _)| *i);
+      json!({
+        "id": bincode::deserialize::<KeyGenId>(key).ok()?,
+        "commitments": commitments
+          .into_iter()
+          .map(|(i, commitments)| (i.to_string(), hex::encode(commitments).into()))
+          .collect::<serde_json::Map<_, _>>(),
+      })
+    }
+    (b"KEY_GEN", b"generated_keys") => json!({
+      "id": bincode::deserialize::<KeyGenId>(key).ok()?,
+      "keys": keys::<C, D>(keystore, db_key, value)?,
+    }),
+    (b"KEY_GEN", b"keys") => {
+      json!({ "key": hex::encode(key), "keys": keys::<C, D>(keystore, db_key, value)? })
+    }
+
+    (b"KEYSTORE", b"salt") | (b"KEYSTORE", b"check") => json!(hex::encode(value)),
+    (b"KEYSTORE", b"entries") => {
+      let mut value = value;
+      let mut entries = vec![];
+      while !value.is_empty() {
+        let len = usize::from(u16::from_le_bytes(value.get(.. 2)?.try_into().unwrap()));
+        entries.push(hex::encode(value.get(2 .. (2 + len))?));
+        value = &value[(2 + len) ..];
+      }
+      json!(entries)
+    }
+
+    _ => return None,
+  })
+}
+
+/// Decode every entry of a processor DB into JSON, grouped by keyspace.
+///
+/// Entries which can't be decoded are reported by their key and value length alone, as there's
+/// no guarantee they don't contain secret material. Encrypted keys are only decoded if the
+/// keystore is provided, and then only their public components are reported.
+pub fn inspect<C: Coin, D: Db>(db: &D, keystore: Option<&KeyStore<D>>) -> Value {
+  let mut entries = db.entries();
+  entries.sort();
+
+  let mut res = serde_json::Map::new();
+  let mut unknown = vec![];
+  for (key, value) in entries {
+    let Some((db_dst, item_dst, _)) = split_key(&key) else {
+      unknown.push(json!({ "key": hex::encode(&key), "value_len": value.len() }));
+      continue;
+    };
+
+    match decode::<C, D>(keystore, &key, &value) {
+      Some(decoded) => res
+        .entry(String::from_utf8_lossy(db_dst))
+        .or_insert_with(|| Value::Object(serde_json::Map::new()))
+        .as_object_mut()
+        .unwrap()
+        .entry(String::from_utf8_lossy(item_dst))
+        .or_insert_with(|| Value::Array(vec![]))
+        .as_array_mut()
+        .unwrap()
+        .push(decoded),
+      None => unknown.push(json!({ "key": hex::encode(&key), "value_len": value.len() })),
+    }
+  }
+
+  if !unknown.is_empty() {
+    res.insert("unknown".to_string(), unknown.into());
+  }
+  Value::Object(res)
+}
@@ -18,7 +18,7 @@ use log::info;
 use serai_client::validator_sets::primitives::ValidatorSet;
 use messages::key_gen::*;
 
-use crate::{DbTxn, Db, coins::Coin};
+use crate::{DbTxn, Db, KeyStore, coins::Coin};
 
 #[derive(Debug)]
 pub enum KeyGenEvent<C: Ciphersuite> {
@@ -31,7 +31,7 @@ pub enum KeyGenEvent<C: Ciphersuite> {
 }
 
 #[derive(Clone, Debug)]
-struct KeyGenDb<C: Coin, D: Db>(D, PhantomData<C>);
+struct KeyGenDb<C: Coin, D: Db>(D, KeyStore<D>, PhantomData<C>);
 impl<C: Coin, D: Db> KeyGenDb<C, D> {
   fn key_gen_key(dst: &'static [u8], key: impl AsRef<[u8]>) -> Vec<u8> {
     D::key(b"KEY_GEN", dst, key)
@@ -86,7 +86,7 @@ impl<C: Coin, D: Db> KeyGenDb<C, D> {
   ) {
     let mut keys = substrate_keys.serialize();
     keys.extend(coin_keys.serialize().iter());
-    txn.put(Self::generated_keys_key(id), keys);
+    self.1.put(txn, &Self::generated_keys_key(id), &keys).expect("couldn't save our keys");
   }
 
   fn keys_key(key: &<C::Curve as Ciphersuite>::G) -> Vec<u8> {
@@ -96,8 +96,8 @@ impl<C: Coin, D: Db> KeyGenDb<C, D> {
   fn read_keys(
     &self,
     key: &[u8],
-  ) -> (Vec<u8>, (ThresholdKeys<Ristretto>, ThresholdKeys<C::Curve>)) {
-    let keys_vec = self.0.get(key).unwrap();
+  ) -> (Zeroizing<Vec<u8>>, (ThresholdKeys<Ristretto>, ThresholdKeys<C::Curve>)) {
+    let keys_vec = self.1.get(key).unwrap().expect("couldn't decrypt our keys");
     let mut keys_ref: &[u8] = keys_vec.as_ref();
     let substrate_keys = ThresholdKeys::new(ThresholdCore::read(&mut keys_ref).unwrap());
     let mut coin_keys = ThresholdKeys::new(ThresholdCore::read(&mut keys_ref).unwrap());
@@ -110,7 +110,8 @@ impl<C: Coin, D: Db> KeyGenDb<C, D> {
     id: &KeyGenId,
   ) -> (ThresholdKeys<Ristretto>, ThresholdKeys<C::Curve>) {
     let (keys_vec, keys) = self.read_keys(&Self::generated_keys_key(id));
-    txn.put(Self::keys_key(&keys.1.group_key()), keys_vec);
+    let key = Self::keys_key(&keys.1.group_key());
+    self.1.put(txn, &key, &keys_vec).expect("couldn't save our keys");
     keys
   }
   fn keys(
@@ -136,9 +137,9 @@ pub struct KeyGen<C: Coin, D: Db> {
 
 impl<C: Coin, D: Db> KeyGen<C, D> {
   #[allow(clippy::new_ret_no_self)]
-  pub fn new(db: D, entropy: Zeroizing<[u8; 32]>) -> KeyGen<C, D> {
+  pub fn new(db: D, keystore: KeyStore<D>, entropy: Zeroizing<[u8; 32]>) -> KeyGen<C, D> {
     KeyGen {
-      db: KeyGenDb(db, PhantomData::<C>),
+      db: KeyGenDb(db, keystore, PhantomData::<C>),
       entropy,
 
       active_commit: HashMap::new(),
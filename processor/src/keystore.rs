@@ -0,0 +1,253 @@
+use std::{
+  sync::{Arc, RwLock},
+  path::PathBuf,
+  fs,
+};
+
+use thiserror::Error;
+
+use zeroize::Zeroizing;
+use rand_core::{RngCore, OsRng};
+
+use subtle::ConstantTimeEq;
+
+use chacha20poly1305::{
+  aead::{KeyInit, Aead, Payload},
+  Key, XNonce, XChaCha20Poly1305,
+};
+
+use transcript::{Transcript, RecommendedTranscript};
+
+use crate::{DbTxn, Db};
+
+/// The secret used to derive the key which wraps all secrets written to the DB.
+#[derive(Clone, Debug)]
+pub enum WrappingSecret {
+  Passphrase(Zeroizing<String>),
+  File(PathBuf),
+}
+
+impl WrappingSecret {
+  fn bytes(&self) -> Result<Zeroizing<Vec<u8>>, KeyStoreError> {
+    match self {
+      WrappingSecret::Passphrase(passphrase) => Ok(Zeroizing::new(passphrase.as_bytes().to_vec())),
+      WrappingSecret::File(path) => {
+        Ok(Zeroizing::new(fs::read(path).map_err(|_| KeyStoreError::KeyFile)?))
+      }
+    }
+  }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Error)]
+pub enum KeyStoreError {
+  #[error("couldn't read the key file")]
+  KeyFile,
+  #[error("couldn't derive a key from the wrapping secret")]
+  KeyDerivation,
+  #[error("the wrapping secret isn't the one this keystore was created with")]
+  IncorrectSecret,
+  #[error("the keystore's check of the wrapping secret is missing")]
+  MissingCheck,
+  #[error("an entry failed authentication")]
+  InvalidEntry,
+}
+
+const NONCE_LEN: usize = 24;
+
+fn salt_key<D: Db>() -> Vec<u8> {
+  D::key(b"KEYSTORE", b"salt", b"")
+}
+fn check_key<D: Db>() -> Vec<u8> {
+  D::key(b"KEYSTORE", b"check", b"")
+}
+fn entries_key<D: Db>() -> Vec<u8> {
+  D::key(b"KEYSTORE", b"entries", b"")
+}
+
+fn derive(secret: &WrappingSecret, salt: &[u8]) -> Result<Zeroizing<[u8; 32]>, KeyStoreError> {
+  // scrypt's recommended parameters take notable time/memory, which isn't needed for tests
+  #[cfg(not(test))]
+  let params = scrypt::Params::recommended();
+  #[cfg(test)]
+  let params = scrypt::Params::new(8, 8, 1).unwrap();
+
+  let mut key = Zeroizing::new([0; 32]);
+  scrypt::scrypt(secret.bytes()?.as_ref(), salt, &params, key.as_mut())
+    .map_err(|_| KeyStoreError::KeyDerivation)?;
+  Ok(key)
+}
+
+// A public value committing to the wrapping key, used to detect an incorrect secret before it's
+// used to write entries which could never be decrypted
+fn check(key: &[u8; 32]) -> [u8; 32] {
+  let mut transcript = RecommendedTranscript::new(b"Serai Processor KeyStore Check v0.1");
+  transcript.append_message(b"wrapping_key", key);
+  let mut res = [0; 32];
+  res.copy_from_slice(&transcript.challenge(b"check")[.. 32]);
+  res
+}
+
+// Entries are a random nonce followed by the ciphertext, with the DB key they're stored under
+// authenticated as associated data
+fn encrypt(key: &[u8; 32], context: &[u8], plaintext: &[u8]) -> Vec<u8> {
+  let mut nonce = [0; NONCE_LEN];
+  OsRng.fill_bytes(&mut nonce);
+
+  let mut res = nonce.to_vec();
+  res.extend(
+    XChaCha20Poly1305::new(Key::from_slice(key))
+      .encrypt(XNonce::from_slice(&nonce), Payload { msg: plaintext, aad: context })
+      .unwrap(),
+  );
+  res
+}
+
+fn decrypt(
+  key: &[u8; 32],
+  context: &[u8],
+  entry: &[u8],
+) -> Result<Zeroizing<Vec<u8>>, KeyStoreError> {
+  if entry.len() < NONCE_LEN {
+    Err(KeyStoreError::InvalidEntry)?;
+  }
+  let (nonce, ciphertext) = entry.split_at(NONCE_LEN);
+  Ok(Zeroizing::new(
+    XChaCha20Poly1305::new(Key::from_slice(key))
+      .decrypt(XNonce::from_slice(nonce), Payload { msg: ciphertext, aad: context })
+      .map_err(|_| KeyStoreError::InvalidEntry)?,
+  ))
+}
+
+/// Encrypted-at-rest storage for secrets, layered over a DB.
+///
+/// Entries are authenticated to the DB key they're stored under, preventing them from being
+/// swapped, and may be re-encrypted under a new wrapping secret via `rotate`.
+///
+/// Clones share the wrapping key, so a rotation performed via any clone applies to all of them.
+#[derive(Clone)]
+pub struct KeyStore<D: Db> {
+  db: D,
+  key: Arc<RwLock<Zeroizing<[u8; 32]>>>,
+}
+
+impl<D: Db> core::fmt::Debug for KeyStore<D> {
+  fn fmt(&self, fmt: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    fmt.debug_struct("KeyStore").field("db", &self.db).finish_non_exhaustive()
+  }
+}
+
+impl<D: Db> KeyStore<D> {
+  fn new_salt() -> [u8; 32] {
+    let mut salt = [0; 32];
+    OsRng.fill_bytes(&mut salt);
+    salt
+  }
+
+  /// Open the keystore within this DB, creating it if it doesn't already exist.
+  pub fn new(mut db: D, secret: &WrappingSecret) -> Result<Self, KeyStoreError> {
+    let Some(salt) = db.get(salt_key::<D>()) else {
+      let salt = Self::new_salt();
+      let key = derive(secret, &salt)?;
+
+      let mut txn = db.txn();
+      txn.put(salt_key::<D>(), salt);
+      txn.put(check_key::<D>(), check(&key));
+      txn.commit();
+
+      return Ok(KeyStore { db, key: Arc::new(RwLock::new(key)) });
+    };
+
+    let key = derive(secret, &salt)?;
+    let expected = db.get(check_key::<D>()).ok_or(KeyStoreError::MissingCheck)?;
+    if !bool::from(check(&key).ct_eq(&expected)) {
+      Err(KeyStoreError::IncorrectSecret)?;
+    }
+    Ok(KeyStore { db, key: Arc::new(RwLock::new(key)) })
+  }
+
+  fn entries(getter: impl Fn(Vec<u8>) -> Option<Vec<u8>>) -> Result<Vec<Vec<u8>>, KeyStoreError> {
+    let entries = getter(entries_key::<D>()).unwrap_or(vec![]);
+    let mut entries = entries.as_slice();
+    let mut res = vec![];
+    while !entries.is_empty() {
+      let len = entries.get(.. 2).ok_or(KeyStoreError::InvalidEntry)?;
+      let len = usize::from(u16::from_le_bytes(len.try_into().unwrap()));
+      res.push(entries.get(2 .. (2 + len)).ok_or(KeyStoreError::InvalidEntry)?.to_vec());
+      entries = &entries[(2 + len) ..];
+    }
+    Ok(res)
+  }
+
+  /// Encrypt and save a secret under the specified DB key, within the specified transaction.
+  ///
+  /// The secret is encrypted under the current wrapping key. The transaction must be committed
+  /// before the keystore is rotated, or the rotation won't re-encrypt this secret.
+  pub fn put(
+    &self,
+    txn: &mut D::Transaction,
+    key: &[u8],
+    secret: &[u8],
+  ) -> Result<(), KeyStoreError> {
+    // Track every entry so they can all be re-encrypted on rotation
+    let entries = Self::entries(|key| txn.get(key))?;
+    if !entries.iter().any(|entry| entry == key) {
+      let mut buf = txn.get(entries_key::<D>()).unwrap_or(vec![]);
+      buf.extend(u16::try_from(key.len()).unwrap().to_le_bytes());
+      buf.extend(key);
+      txn.put(entries_key::<D>(), buf);
+    }
+
+    txn.put(key, encrypt(&self.key.read().unwrap(), key, secret));
+    Ok(())
+  }
+
+  /// Get and decrypt the secret saved under the specified DB key.
+  pub fn get(&self, key: &[u8]) -> Option<Result<Zeroizing<Vec<u8>>, KeyStoreError>> {
+    // Hold the lock while reading the entry, so it isn't re-encrypted by a rotation before it's
+    // decrypted
+    let wrapping_key = self.key.read().unwrap();
+    let entry = self.db.get(key)?;
+    Some(decrypt(&wrapping_key, key, &entry))
+  }
+
+  /// Decrypt an entry which was already read from the DB.
+  pub(crate) fn decrypt(
+    &self,
+    key: &[u8],
+    entry: &[u8],
+  ) -> Result<Zeroizing<Vec<u8>>, KeyStoreError> {
+    decrypt(&self.key.read().unwrap(), key, entry)
+  }
+
+  /// Rotate the wrapping secret, re-encrypting every entry under the new secret.
+  ///
+  /// Only committed entries are re-encrypted. This must not be called while a transaction
+  /// containing a `put` is uncommitted.
+  pub fn rotate(&self, secret: &WrappingSecret) -> Result<(), KeyStoreError> {
+    let salt = Self::new_salt();
+    let new_key = derive(secret, &salt)?;
+
+    // Hold the lock for the entire rotation, so no entry is encrypted under the prior key once its
+    // entries have been read
+    let mut key = self.key.write().unwrap();
+
+    // Decrypt everything before writing anything, so a corrupt entry doesn't leave the keystore
+    // partially rotated
+    let mut decrypted = vec![];
+    for entry_key in Self::entries(|key| self.db.get(key))? {
+      let entry = self.db.get(&entry_key).ok_or(KeyStoreError::InvalidEntry)?;
+      decrypted.push((decrypt(&key, &entry_key, &entry)?, entry_key));
+    }
+
+    let mut txn = self.db.clone().txn();
+    for (secret, entry_key) in &decrypted {
+      txn.put(entry_key, encrypt(&new_key, entry_key, secret));
+    }
+    txn.put(salt_key::<D>(), salt);
+    txn.put(check_key::<D>(), check(&new_key));
+    txn.commit();
+
+    *key = new_key;
+    Ok(())
+  }
+}
@@ -0,0 +1,41 @@
+use frost::curve::Ciphersuite;
+
+mod plan;
+pub use plan::*;
+
+mod db;
+pub use db::*;
+
+mod keystore;
+pub use keystore::*;
+
+mod coordinator;
+pub use coordinator::*;
+
+pub mod coins;
+use coins::Coin;
+
+pub mod key_gen;
+
+pub mod signer;
+
+pub mod scanner;
+
+pub mod scheduler;
+
+pub mod inspect;
+
+/// Tests for the processor, including a conformance suite for Coin implementations.
+#[cfg(any(test, feature = "tests"))]
+pub mod tests;
+
+// Generate a static additional key for a given chain in a globally consistent manner
+// Doesn't consider the current group key to increase the simplicity of verifying Serai's status
+// Takes an index, k, to support protocols which use multiple secondary keys
+// Presumably a view key
+pub(crate) fn additional_key<C: Coin>(k: u64) -> <C::Curve as Ciphersuite>::F {
+  <C::Curve as Ciphersuite>::hash_to_F(
+    b"Serai DEX Additional Key",
+    &[C::ID.as_bytes(), &k.to_le_bytes()].concat(),
+  )
+}
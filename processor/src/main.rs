@@ -14,7 +14,10 @@ use group::GroupEncoding;
 use frost::curve::Ciphersuite;
 
 use log::{info, warn, error};
-use tokio::time::sleep;
+use tokio::{
+  time::sleep,
+  signal::unix::{SignalKind, signal},
+};
 
 use scale::Decode;
 
@@ -26,47 +29,19 @@ use serai_client::{
 
 use messages::{SubstrateContext, sign, substrate, CoordinatorMessage, ProcessorMessage};
 
-mod plan;
-pub use plan::*;
-
-mod db;
-pub use db::*;
-
-mod coordinator;
-pub use coordinator::*;
-
-mod coins;
-use coins::{OutputType, Output, PostFeeBranch, Block, Coin};
+use processor::{
+  Payment, Plan, Db, MainDb, MemDb, WrappingSecret, KeyStore, Coordinator, MemCoordinator,
+  coins::{CoinError, OutputType, Output, PostFeeBranch, Block, Coin, Network, confirmations},
+  key_gen::{KeyGenEvent, KeyGen},
+  inspect::inspect,
+  signer::{SignerEvent, Signer, SignerHandle},
+  scanner::{ScannerEvent, Scanner, ScannerHandle},
+  scheduler::Scheduler,
+};
 #[cfg(feature = "bitcoin")]
-use coins::Bitcoin;
+use processor::coins::Bitcoin;
 #[cfg(feature = "monero")]
-use coins::Monero;
-
-mod key_gen;
-use key_gen::{KeyGenEvent, KeyGen};
-
-mod signer;
-use signer::{SignerEvent, Signer, SignerHandle};
-
-mod scanner;
-use scanner::{ScannerEvent, Scanner, ScannerHandle};
-
-mod scheduler;
-use scheduler::Scheduler;
-
-#[cfg(test)]
-mod tests;
-
-// Generate a static additional key for a given chain in a globally consistent manner
-// Doesn't consider the current group key to increase the simplicity of verifying Serai's status
-// Takes an index, k, to support protocols which use multiple secondary keys
-// Presumably a view key
-pub(crate) fn additional_key<C: Coin>(k: u64) -> <C::Curve as Ciphersuite>::F {
-  <C::Curve as Ciphersuite>::hash_to_F(
-    b"Serai DEX Additional Key",
-    &[C::ID.as_bytes(), &k.to_le_bytes()].concat(),
-  )
-}
+use processor::coins::Monero;
 
 struct SignerMessageFuture<'a, C: Coin, D: Db>(&'a mut HashMap<Vec<u8>, SignerHandle<C, D>>);
 impl<'a, C: Coin, D: Db> Future for SignerMessageFuture<'a, C, D> {
@@ -99,38 +74,45 @@ async fn get_fee<C: Coin>(coin: &C, block_number: usize) -> C::Fee {
   }
 }
 
+// Returns None if the plan's inputs can't be spent yet, in which case it should be retried later
+#[allow(clippy::type_complexity)]
 async fn prepare_send<C: Coin, D: Db>(
   coin: &C,
   signer: &SignerHandle<C, D>,
   block_number: usize,
   fee: C::Fee,
   plan: Plan<C>,
-) -> (Option<(C::SignableTransaction, C::Eventuality)>, Vec<PostFeeBranch>) {
+) -> Option<(Option<(C::SignableTransaction, C::Eventuality)>, Vec<PostFeeBranch>)> {
   let keys = signer.keys().await;
   loop {
     match coin.prepare_send(keys.clone(), block_number, plan.clone(), fee).await {
       Ok(prepared) => {
-        return prepared;
+        return Some(prepared);
+      }
+      Err(CoinError::NotSpendable) => {
+        info!("plan {} has inputs which aren't spendable yet", hex::encode(plan.id()));
+        return None;
       }
       Err(e) => {
         error!("couldn't prepare a send for plan {}: {e}", hex::encode(plan.id()));
-        // The processor is either trying to create an invalid TX (fatal) or the node went
-        // offline
-        // The former requires a patch, the latter is a connection issue
-        // If the latter, this is an appropriate sleep. If the former, we should panic, yet
-        // this won't flood the console ad infinitum
+        // The processor is either trying to create an invalid TX (fatal) or the node went offline
+        // The former requires a patch and the latter is a connection issue
+        // If the latter, this is an appropriate sleep. If the former, we should panic, yet this
+        // won't flood the console ad infinitum
         sleep(Duration::from_secs(60)).await;
       }
     }
   }
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn sign_plans<C: Coin, D: Db>(
   db: &mut MainDb<C, D>,
   coin: &C,
   scanner: &ScannerHandle<C, D>,
   schedulers: &mut HashMap<Vec<u8>, Scheduler<C>>,
   signers: &HashMap<Vec<u8>, SignerHandle<C, D>>,
+  deferred: &mut Vec<Plan<C>>,
   context: SubstrateContext,
   plans: Vec<Plan<C>>,
 ) {
@@ -146,7 +128,13 @@ async fn sign_plans<C: Coin, D: Db>(
 
     let key = plan.key.to_bytes();
     db.save_signing(key.as_ref(), context.coin_latest_block_number, context.time, &plan);
-    let (tx, branches) = prepare_send(coin, &signers[key.as_ref()], block_number, fee, plan).await;
+    let Some((tx, branches)) =
+      prepare_send(coin, &signers[key.as_ref()], block_number, fee, plan.clone()).await
+    else {
+      // Retry this plan with the next Substrate context, continuing with the rest
+      deferred.push(plan);
+      continue;
+    };
 
     // TODO: If we reboot mid-sign_plans, for a DB-backed scheduler, these may be partially
     // executed
@@ -193,9 +181,38 @@ async fn run<C: Coin, D: Db, Co: Coordinator>(raw_db: D, coin: C, mut coordinato
     res
   };
 
+  let keystore = {
+    let secret = |file, passphrase| {
+      if let Ok(path) = env::var(file) {
+        Some(WrappingSecret::File(path.into()))
+      } else {
+        env::var(passphrase)
+          .ok()
+          .map(|passphrase| WrappingSecret::Passphrase(Zeroizing::new(passphrase)))
+      }
+    };
+
+    let keystore = KeyStore::new(
+      raw_db.clone(),
+      &secret("KEYSTORE_FILE", "KEYSTORE_PASSPHRASE")
+        .expect("neither a keystore file nor passphrase was provided as an env var"),
+    )
+    .expect("couldn't open the keystore");
+
+    // If a new secret was specified, re-encrypt the keystore under it
+    // Once this completes, the processor must be restarted with the new secret
+    if let Some(new_secret) = secret("KEYSTORE_ROTATE_FILE", "KEYSTORE_ROTATE_PASSPHRASE") {
+      keystore.rotate(&new_secret).expect("couldn't rotate the keystore");
+      info!("rotated the keystore's wrapping secret");
+    }
+
+    keystore
+  };
+
   // We don't need to re-issue GenerateKey orders because the coordinator is expected to
   // schedule/notify us of new attempts
-  let mut key_gen = KeyGen::<C, _>::new(raw_db.clone(), entropy(b"key-gen_entropy"));
+  let mut key_gen =
+    KeyGen::<C, _>::new(raw_db.clone(), keystore.clone(), entropy(b"key-gen_entropy"));
   // The scanner has no long-standing orders to re-issue
   let (mut scanner, active_keys) = Scanner::new(coin.clone(), raw_db.clone());
 
@@ -204,6 +221,9 @@ async fn run<C: Coin, D: Db, Co: Coordinator>(raw_db: D, coin: C, mut coordinato
 
   let mut main_db = MainDb::new(raw_db.clone());
 
+  // Plans whose inputs weren't spendable as of the block they were prepared with
+  let mut deferred = vec![];
+
   for key in &active_keys {
     // TODO: Load existing schedulers
 
@@ -212,19 +232,23 @@ async fn run<C: Coin, D: Db, Co: Coordinator>(raw_db: D, coin: C, mut coordinato
 
     // Load any TXs being actively signed
     let key = key.to_bytes();
-    for (block_number, start, plan) in main_db.signing(key.as_ref()) {
+    for (block_number, time, plan) in main_db.signing(key.as_ref()) {
       let block_number = block_number.try_into().unwrap();
-      let start = SystemTime::UNIX_EPOCH.checked_add(Duration::from_secs(start)).unwrap();
+      let start = SystemTime::UNIX_EPOCH.checked_add(Duration::from_secs(time)).unwrap();
 
       let fee = get_fee(&coin, block_number).await;
 
       let id = plan.id();
       info!("reloading plan {}: {:?}", hex::encode(id), plan);
 
-      let (Some((tx, eventuality)), _) =
-        prepare_send(&coin, &signer, block_number, fee, plan).await else {
-          panic!("previously created transaction is no longer being created")
-        };
+      let Some(prepared) = prepare_send(&coin, &signer, block_number, fee, plan.clone()).await
+      else {
+        deferred.push(plan);
+        continue;
+      };
+      let (Some((tx, eventuality)), _) = prepared else {
+        panic!("previously created transaction is no longer being created")
+      };
 
       scanner.register_eventuality(block_number, id, eventuality.clone()).await;
       // TODO: Reconsider if the Signer should have the eventuality, or if just the coin/scanner
@@ -235,6 +259,11 @@ async fn run<C: Coin, D: Db, Co: Coordinator>(raw_db: D, coin: C, mut coordinato
     signers.insert(key.as_ref().to_vec(), signer);
   }
 
+  // Dump the DB as JSON whenever we receive SIGUSR1, so a misbehaving processor can be inspected
+  // without stopping it
+  let mut inspect_signal =
+    signal(SignalKind::user_defined1()).expect("couldn't listen for SIGUSR1");
+
   // We can't load this from the DB as we can't guarantee atomic increments with the ack function
   let mut last_coordinator_msg = None;
 
@@ -267,14 +296,14 @@ async fn run<C: Coin, D: Db, Co: Coordinator>(raw_db: D, coin: C, mut coordinato
             };
 
             // Check our daemon has this block
-            // CONFIRMATIONS - 1 since any block's TXs have one confirmation (the block itself)
-            let confirmed = actual.saturating_sub(C::CONFIRMATIONS - 1);
+            // confirmations - 1 since any block's TXs have one confirmation (the block itself)
+            let confirmed = actual.saturating_sub(coin.confirmations() - 1);
             if needed > confirmed {
               // This may occur within some natural latency window
               warn!(
                 "node is desynced. need block {}, have {}",
                 // Print the block needed for the needed block to be confirmed
-                needed + (C::CONFIRMATIONS - 1),
+                needed + (coin.confirmations() - 1),
                 actual,
               );
               // Sleep for one second per needed block
@@ -357,6 +386,27 @@ async fn run<C: Coin, D: Db, Co: Coordinator>(raw_db: D, coin: C, mut coordinato
           }
 
           CoordinatorMessage::Substrate(msg) => {
+            // Retry any deferred plans against this message's context
+            // This is done upon Substrate messages, instead of on a timer, so every processor
+            // retries them at the same point, with the same context
+            let context = match &msg {
+              substrate::CoordinatorMessage::BlockAcknowledged { context, .. } |
+              substrate::CoordinatorMessage::Burns { context, .. } => *context,
+            };
+            if !deferred.is_empty() {
+              let plans = core::mem::take(&mut deferred);
+              sign_plans(
+                &mut main_db,
+                &coin,
+                &scanner,
+                &mut schedulers,
+                &signers,
+                &mut deferred,
+                context,
+                plans
+              ).await;
+            }
+
             match msg {
               substrate::CoordinatorMessage::BlockAcknowledged { context, key: key_vec, block } => {
                 let key =
@@ -374,6 +424,7 @@ async fn run<C: Coin, D: Db, Co: Coordinator>(raw_db: D, coin: C, mut coordinato
                   &scanner,
                   &mut schedulers,
                   &signers,
+                  &mut deferred,
                   context,
                   plans
                 ).await;
@@ -385,30 +436,50 @@ async fn run<C: Coin, D: Db, Co: Coordinator>(raw_db: D, coin: C, mut coordinato
                 let scheduler = schedulers.get_mut(schedule_key.to_bytes().as_ref()).unwrap();
 
                 let mut payments = vec![];
+                let mut rejected = vec![];
                 for out in burns.clone() {
                   let OutInstructionWithBalance {
                     instruction: OutInstruction { address, data },
                     balance,
-                  } = out;
+                  } = out.clone();
                   if let Ok(address) = C::Address::try_from(address.consume()) {
-                    payments.push(Payment {
-                      address,
-                      data: data.map(|data| data.consume()),
-                      amount: balance.amount.0,
-                    });
+                    let data = data.map(|data| data.consume());
+                    // If this data can never be included in a TX, don't attempt the payment
+                    // The coordinator is told so it can refund the burn
+                    if data.as_ref().map(Vec::len).unwrap_or(0) > C::MAX_PAYMENT_DATA {
+                      warn!(
+                        "burn had data exceeding {}'s MAX_PAYMENT_DATA ({})",
+                        C::ID,
+                        C::MAX_PAYMENT_DATA,
+                      );
+                      rejected.push(out);
+                      continue;
+                    }
+
+                    payments.push(Payment { address, data, amount: balance.amount.0 });
                   }
                 }
 
-                let plans = scheduler.schedule(payments);
-                sign_plans(
-                  &mut main_db,
-                  &coin,
-                  &scanner,
-                  &mut schedulers,
-                  &signers,
-                  context,
-                  plans
-                ).await;
+                if !rejected.is_empty() {
+                  coordinator.send(ProcessorMessage::Substrate(
+                    substrate::ProcessorMessage::RejectedBurns { context, burns: rejected }
+                  )).await;
+                }
+
+                // Every burn may have been rejected
+                if !payments.is_empty() {
+                  let plans = scheduler.schedule(payments);
+                  sign_plans(
+                    &mut main_db,
+                    &coin,
+                    &scanner,
+                    &mut schedulers,
+                    &signers,
+                    &mut deferred,
+                    context,
+                    plans
+                  ).await;
+                }
               }
             }
           }
@@ -480,6 +551,10 @@ async fn run<C: Coin, D: Db, Co: Coordinator>(raw_db: D, coin: C, mut coordinato
           },
         }
       },
+
+      _ = inspect_signal.recv() => {
+        info!("DB: {}", inspect::<C, D>(&raw_db, Some(&keystore)));
+      },
     }
   }
 }
@@ -489,11 +564,31 @@ async fn main() {
   let db = MemDb::new(); // TODO
   let coordinator = MemCoordinator::new(); // TODO
   let url = env::var("COIN_RPC").expect("coin rpc wasn't specified as an env var");
+
+  let network = Network::try_from(
+    env::var("NETWORK").expect("network wasn't specified as an env var").as_str(),
+  )
+  .expect("network wasn't mainnet, testnet, or regtest");
+  let configured = env::var("CONFIRMATIONS")
+    .ok()
+    .map(|confirmations| confirmations.parse().expect("confirmations weren't a number"));
+  fn confirmations_for<C: Coin>(network: Network, configured: Option<usize>) -> usize {
+    confirmations::<C>(network, configured).unwrap_or_else(|| {
+      panic!("configured confirmations were insufficient for {} on {network:?}", C::ID)
+    })
+  }
+
   match env::var("COIN").expect("coin wasn't specified as an env var").as_str() {
     #[cfg(feature = "bitcoin")]
-    "bitcoin" => run(db, Bitcoin::new(url).await, coordinator).await,
+    "bitcoin" => {
+      let confirmations = confirmations_for::<Bitcoin>(network, configured);
+      run(db, Bitcoin::new(url, confirmations).await, coordinator).await
+    }
     #[cfg(feature = "monero")]
-    "monero" => run(db, Monero::new(url), coordinator).await,
+    "monero" => {
+      let confirmations = confirmations_for::<Monero>(network, configured);
+      run(db, Monero::new(url, confirmations), coordinator).await
+    }
     _ => panic!("unrecognized coin"),
   }
 }
@@ -131,6 +131,32 @@ impl<C: Coin, D: Db> ScannerDb<C, D> {
     Some(res)
   }
 
+  fn locked_outputs_key(key: &<C::Curve as Ciphersuite>::G) -> Vec<u8> {
+    Self::scanner_key(b"locked_outputs", key.to_bytes())
+  }
+  fn save_locked_outputs(
+    &mut self,
+    txn: &mut D::Transaction,
+    key: &<C::Curve as Ciphersuite>::G,
+    outputs: &[C::Output],
+  ) {
+    let mut bytes = Vec::with_capacity(outputs.len() * 64);
+    for output in outputs {
+      output.write(&mut bytes).unwrap();
+    }
+    txn.put(Self::locked_outputs_key(key), bytes);
+  }
+  fn locked_outputs(&self, key: &<C::Curve as Ciphersuite>::G) -> Vec<C::Output> {
+    let bytes_vec = self.0.get(Self::locked_outputs_key(key)).unwrap_or(vec![]);
+    let mut bytes: &[u8] = bytes_vec.as_ref();
+
+    let mut res = vec![];
+    while !bytes.is_empty() {
+      res.push(C::Output::read(&mut bytes).unwrap());
+    }
+    res
+  }
+
   fn scanned_block_key(key: &<C::Curve as Ciphersuite>::G) -> Vec<u8> {
     Self::scanner_key(b"scanned_block", key.to_bytes())
   }
@@ -300,8 +326,8 @@ impl<C: Coin, D: Db> Scanner<C, D> {
         let latest = scanner.coin.get_latest_block_number().await;
         let latest = match latest {
           // Only scan confirmed blocks, which we consider effectively finalized
-          // CONFIRMATIONS - 1 as whatever's in the latest block already has 1 confirm
-          Ok(latest) => latest.saturating_sub(C::CONFIRMATIONS.saturating_sub(1)),
+          // confirmations - 1 as whatever's in the latest block already has 1 confirm
+          Ok(latest) => latest.saturating_sub(scanner.coin.confirmations().saturating_sub(1)),
           Err(_) => {
             warn!("Couldn't get {}'s latest block number", C::ID);
             sleep(Duration::from_secs(60)).await;
@@ -362,7 +388,7 @@ impl<C: Coin, D: Db> Scanner<C, D> {
               let _ = tx;
             }
 
-            let outputs = match scanner.coin.get_outputs(&block, key).await {
+            let mut outputs = match scanner.coin.get_outputs(&block, key).await {
               Ok(outputs) => outputs,
               Err(_) => {
                 warn!("Couldn't scan {} block {i:?}", C::ID);
@@ -370,6 +396,10 @@ impl<C: Coin, D: Db> Scanner<C, D> {
               }
             };
 
+            // Blocks without emitted outputs may be rescanned, so skip outputs already being held
+            let held = scanner.db.locked_outputs(&key);
+            outputs.retain(|output| !held.iter().any(|held| held.id() == output.id()));
+
             // Panic if we've already seen these outputs
             for output in &outputs {
               let id = output.id();
@@ -383,21 +413,43 @@ impl<C: Coin, D: Db> Scanner<C, D> {
               // On Bitcoin, the output ID should be unique for a given chain
               // On Monero, it's trivial to make an output sharing an ID with another
               // We should only scan outputs with valid IDs however, which will be unique
-              let seen = scanner.db.seen(&id);
-              let id = id.as_ref().to_vec();
-              if seen || scanner.ram_outputs.contains(&id) {
+              if scanner.db.seen(&id) || scanner.ram_outputs.contains(id.as_ref()) {
                 panic!("scanned an output multiple times");
               }
-              scanner.ram_outputs.insert(id);
+            }
+            let ids =
+              outputs.iter().map(|output| output.id().as_ref().to_vec()).collect::<Vec<_>>();
+
+            // Hold any outputs which are still locked as of this block, and release any held
+            // outputs which have since unlocked
+            // This is evaluated against the block itself so every processor releases an output
+            // with the same block, regardless of when it scans it
+            let held_len = held.len();
+            let new_len = outputs.len();
+            outputs.extend(held);
+            let (outputs, locked) = match scanner.coin.unlocked(&block, outputs).await {
+              Ok(outputs) => outputs,
+              Err(_) => {
+                warn!("Couldn't check if {} block {i:?}'s outputs are unlocked", C::ID);
+                break;
+              }
+            };
+            // Only mark these outputs as scanned once nothing can cause this block to be rescanned
+            // within this process's lifetime
+            scanner.ram_outputs.extend(ids);
+
+            let mut txn = scanner.db.0.txn();
+            if (new_len != 0) || (locked.len() != held_len) {
+              scanner.db.save_locked_outputs(&mut txn, &key, &locked);
             }
 
             // TODO: Still fire an empty Outputs event if we haven't had inputs in a while
             if outputs.is_empty() {
+              txn.commit();
               continue;
             }
 
             // Save the outputs to disk
-            let mut txn = scanner.db.0.txn();
             scanner.db.save_outputs(&mut txn, &key, &block_id, &outputs);
             txn.commit();
 
@@ -80,6 +80,32 @@ impl<C: Coin> Scheduler<C> {
       amount
     };
 
+    // If we have more payments with data than we can handle in a single TX, move the excess into
+    // their own branches
+    // The branch payments don't have data, and each branch will only have as many payments with
+    // data as a TX can handle, so this never increases the amount of outputs
+    let mut data_payments = 0;
+    let mut excess_data = vec![];
+    payments = payments
+      .drain(..)
+      .filter_map(|payment| {
+        if payment.data.is_none() {
+          return Some(payment);
+        }
+        data_payments += 1;
+        if data_payments <= C::MAX_DATA_PAYMENTS {
+          Some(payment)
+        } else {
+          excess_data.push(payment);
+          None
+        }
+      })
+      .collect();
+    for chunk in excess_data.chunks(C::MAX_DATA_PAYMENTS) {
+      let amount = add_plan(chunk.to_vec());
+      payments.insert(0, Payment { address: branch_address.clone(), data: None, amount });
+    }
+
     // If we have more payments than we can handle in a single TX, create plans for them
     // TODO2: This isn't perfect. For 258 outputs, and a MAX_OUTPUTS of 16, this will create:
     // 15 branches of 16 leaves
@@ -148,6 +174,12 @@ impl<C: Coin> Scheduler<C> {
   pub fn schedule(&mut self, payments: Vec<Payment<C>>) -> Vec<Plan<C>> {
     log::debug!("scheduling payments");
     assert!(!payments.is_empty(), "tried to schedule zero payments");
+    for payment in &payments {
+      assert!(
+        payment.data.as_ref().map(Vec::len).unwrap_or(0) <= C::MAX_PAYMENT_DATA,
+        "tried to schedule a payment with more data than the coin supports",
+      );
+    }
 
     // Add all new payments to the list of pending payments
     self.payments.extend(payments);
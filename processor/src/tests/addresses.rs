@@ -31,7 +31,7 @@ async fn spend<C: Coin, D: Db>(
         coin
           .prepare_send(
             keys.clone(),
-            coin.get_latest_block_number().await.unwrap() - C::CONFIRMATIONS,
+            coin.get_latest_block_number().await.unwrap() - coin.confirmations(),
             // Send to a change output
             Plan { key, inputs: outputs.clone(), payments: vec![], change: Some(key) },
             coin.get_fee().await,
@@ -45,7 +45,7 @@ async fn spend<C: Coin, D: Db>(
   }
   sign(coin.clone(), keys_txs).await;
 
-  for _ in 0 .. C::CONFIRMATIONS {
+  for _ in 0 .. coin.confirmations() {
     coin.mine_block().await;
   }
   match timeout(Duration::from_secs(30), scanner.events.recv()).await.unwrap().unwrap() {
@@ -67,7 +67,7 @@ pub async fn test_addresses<C: Coin>(coin: C) {
   let key = keys[&Participant::new(1).unwrap()].group_key();
 
   // Mine blocks so there's a confirmed block
-  for _ in 0 .. C::CONFIRMATIONS {
+  for _ in 0 .. coin.confirmations() {
     coin.mine_block().await;
   }
 
@@ -0,0 +1,27 @@
+use crate::coins::{Network, Coin, confirmations};
+
+pub fn test_confirmations<C: Coin>() {
+  for network in [Network::Mainnet, Network::Testnet, Network::Regtest] {
+    // Without configuration, the coin's default should be used
+    assert_eq!(confirmations::<C>(network, None), Some(C::CONFIRMATIONS));
+
+    // A configured amount above the minimum should be used
+    assert_eq!(confirmations::<C>(network, Some(C::CONFIRMATIONS + 5)), Some(C::CONFIRMATIONS + 5));
+
+    // Zero confirmations is never allowed
+    assert_eq!(confirmations::<C>(network, Some(0)), None);
+  }
+
+  // Amounts below the coin's minimum should be rejected for non-regtest networks
+  for network in [Network::Mainnet, Network::Testnet] {
+    assert_eq!(confirmations::<C>(network, Some(C::CONFIRMATIONS)), Some(C::CONFIRMATIONS));
+    assert_eq!(confirmations::<C>(network, Some(C::CONFIRMATIONS - 1)), None);
+  }
+
+  // Regtest allows as few as one confirmation
+  assert_eq!(confirmations::<C>(Network::Regtest, Some(1)), Some(1));
+  assert_eq!(
+    confirmations::<C>(Network::Regtest, Some(C::CONFIRMATIONS - 1)),
+    Some(C::CONFIRMATIONS - 1).filter(|confirmations| *confirmations != 0)
+  );
+}
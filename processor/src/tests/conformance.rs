@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+
+use rand_core::OsRng;
+
+use frost::{curve::Ciphersuite, Participant, dkg::tests::key_gen};
+
+use crate::{
+  Payment, Plan,
+  coins::{OutputType, Output, Transaction, Eventuality, Block, PostFeeBranch, Coin, amortize_fee},
+  tests::sign,
+};
+
+// Tests amortize_fee, which doesn't require a node
+fn test_amortize_fee<C: Coin>(key: <C::Curve as Ciphersuite>::G) {
+  let address = C::address(key);
+  let branch = C::branch_address(key);
+
+  let plan = |payments: Vec<(C::Address, u64)>| Plan::<C> {
+    key,
+    inputs: vec![],
+    payments: payments
+      .into_iter()
+      .map(|(address, amount)| Payment { address, data: None, amount })
+      .collect(),
+    change: Some(key),
+  };
+
+  // Without payments, there's nothing to amortize over
+  let mut empty = plan(vec![]);
+  assert!(amortize_fee(&mut empty, 1000).is_empty());
+  assert!(empty.payments.is_empty());
+
+  // The fee should be evenly split, rounding up, with branches reported
+  let mut plan_with_branch =
+    plan(vec![(address.clone(), 10 * C::DUST), (branch.clone(), 10 * C::DUST)]);
+  assert_eq!(
+    amortize_fee(&mut plan_with_branch, 3),
+    vec![PostFeeBranch { expected: 10 * C::DUST, actual: Some((10 * C::DUST) - 2) }]
+  );
+  assert_eq!(plan_with_branch.payments[0].amount, (10 * C::DUST) - 2);
+  assert_eq!(plan_with_branch.payments[1].amount, (10 * C::DUST) - 2);
+
+  // Payments which fall below dust, or can't cover their fee, should be dropped
+  let mut plan_with_dust =
+    plan(vec![(address.clone(), 10 * C::DUST), (branch, C::DUST), (address, 1)]);
+  assert_eq!(
+    amortize_fee(&mut plan_with_dust, 3),
+    vec![PostFeeBranch { expected: C::DUST, actual: None }]
+  );
+  assert_eq!(plan_with_dust.payments.len(), 1);
+  assert_eq!(plan_with_dust.payments[0].amount, (10 * C::DUST) - 1);
+}
+
+/// A conformance suite for Coin implementations.
+///
+/// This checks address derivation, the kinds of outputs returned by `get_outputs`, fee
+/// amortization, eventuality serialization, and `confirm_completion`. Any new Coin should pass
+/// this, which is done by instantiating it via `test_coin!`.
+pub async fn test_conformance<C: Coin>(coin: C) {
+  let mut keys = key_gen(&mut OsRng);
+  for (_, keys) in keys.iter_mut() {
+    C::tweak_keys(keys);
+  }
+  let key = keys[&Participant::new(1).unwrap()].group_key();
+
+  // Addresses should be deterministic, distinct, and serializable
+  let address = C::address(key);
+  let branch = C::branch_address(key);
+  assert_eq!(address, C::address(key));
+  assert_eq!(branch, C::branch_address(key));
+  assert!(address != branch);
+  for address in [&address, &branch] {
+    let serialized: Vec<u8> = address.clone().try_into().map_err(|_| ()).unwrap();
+    assert_eq!(&C::Address::try_from(serialized).map_err(|_| ()).unwrap(), address);
+  }
+
+  test_amortize_fee::<C>(key);
+
+  // Receive to the external and branch addresses, checking the outputs are properly identified
+  let external_block = coin.test_send(address.clone()).await;
+  let outputs = coin.get_outputs(&external_block, key).await.unwrap();
+  assert_eq!(outputs.len(), 1);
+  assert_eq!(outputs[0].kind(), OutputType::External);
+  let external = outputs[0].clone();
+
+  let branch_block = coin.test_send(branch).await;
+  let outputs = coin.get_outputs(&branch_block, key).await.unwrap();
+  assert_eq!(outputs.len(), 1);
+  assert_eq!(outputs[0].kind(), OutputType::Branch);
+  let branch = outputs[0].clone();
+
+  // Blocks should be retrievable by the number of their ID
+  assert_eq!(
+    coin.get_block(coin.get_block_number(&branch_block.id()).await).await.unwrap().id(),
+    branch_block.id()
+  );
+
+  // Outputs should round-trip
+  {
+    let mut buf = vec![];
+    external.write(&mut buf).unwrap();
+    assert_eq!(external, C::Output::read::<&[u8]>(&mut buf.as_ref()).unwrap());
+  }
+
+  // Create a plan for each output, both of which create change
+  let block_number = coin.get_block_number(&branch_block.id()).await;
+  let fee = coin.get_fee().await;
+  let plan = |input: C::Output| Plan::<C> {
+    key,
+    inputs: vec![input],
+    payments: vec![Payment { address: address.clone(), data: None, amount: 2 * C::DUST }],
+    change: Some(key),
+  };
+
+  let mut keys_txs = HashMap::new();
+  let mut eventualities = vec![];
+  for (i, keys) in keys.drain() {
+    let (signable, eventuality) = coin
+      .prepare_send(keys.clone(), block_number, plan(external.clone()), fee)
+      .await
+      .unwrap()
+      .0
+      .unwrap();
+
+    // Eventualities should round-trip
+    let serialized = eventuality.serialize();
+    let read = C::Eventuality::read::<&[u8]>(&mut serialized.as_ref()).unwrap();
+    assert_eq!(read.serialize(), serialized);
+    assert_eq!(read.lookup(), eventuality.lookup());
+
+    eventualities.push(read);
+    keys_txs.insert(i, (keys, (signable, eventuality)));
+  }
+  let first_keys = keys_txs[&Participant::new(1).unwrap()].0.clone();
+  let other_eventuality =
+    coin.prepare_send(first_keys, block_number, plan(branch), fee).await.unwrap().0.unwrap().1;
+
+  let txid = sign(coin.clone(), keys_txs).await;
+  let tx = coin.get_transaction(&txid).await.unwrap();
+  assert_eq!(tx.id(), txid);
+
+  // The TX should complete its own eventualities, yet not the other plan's
+  for eventuality in eventualities {
+    assert!(coin.confirm_completion(&eventuality, &tx));
+  }
+  assert!(!coin.confirm_completion(&other_eventuality, &tx));
+
+  // The created outputs should be a payment to the external address and change
+  coin.mine_block().await;
+  let block = coin.get_block(coin.get_latest_block_number().await.unwrap()).await.unwrap();
+  let mut kinds = coin
+    .get_outputs(&block, key)
+    .await
+    .unwrap()
+    .iter()
+    .map(|output| output.kind())
+    .collect::<Vec<_>>();
+  kinds.sort_by_key(|kind| *kind as u8);
+  assert_eq!(kinds, vec![OutputType::External, OutputType::Change]);
+}
@@ -4,7 +4,7 @@ use zeroize::Zeroizing;
 
 use rand_core::{RngCore, OsRng};
 
-use group::GroupEncoding;
+use group::{ff::PrimeField, GroupEncoding};
 use frost::{Participant, ThresholdParams, tests::clone_without};
 
 use serai_client::{
@@ -14,14 +14,21 @@ use serai_client::{
 
 use messages::{SubstrateContext, key_gen::*};
 use crate::{
+  Db, WrappingSecret, KeyStore,
   coins::Coin,
   key_gen::{KeyGenEvent, KeyGen},
+  inspect::inspect,
   tests::util::db::MemDb,
 };
 
 const ID: KeyGenId =
   KeyGenId { set: ValidatorSet { session: Session(1), network: MONERO_NET_ID }, attempt: 3 };
 
+fn keystore(db: &MemDb) -> KeyStore<MemDb> {
+  KeyStore::new(db.clone(), &WrappingSecret::Passphrase(Zeroizing::new("key gen".to_string())))
+    .unwrap()
+}
+
 pub async fn test_key_gen<C: Coin>() {
   let mut entropies = HashMap::new();
   let mut dbs = HashMap::new();
@@ -31,7 +38,8 @@ pub async fn test_key_gen<C: Coin>() {
     OsRng.fill_bytes(entropy.as_mut());
     entropies.insert(i, entropy);
     dbs.insert(i, MemDb::new());
-    key_gens.insert(i, KeyGen::<C, _>::new(dbs[&i].clone(), entropies[&i].clone()));
+    key_gens
+      .insert(i, KeyGen::<C, _>::new(dbs[&i].clone(), keystore(&dbs[&i]), entropies[&i].clone()));
   }
 
   let mut all_commitments = HashMap::new();
@@ -58,7 +66,8 @@ pub async fn test_key_gen<C: Coin>() {
   // 3 ... are rebuilt once, one at each of the following steps
   let rebuild = |key_gens: &mut HashMap<_, _>, i| {
     key_gens.remove(&i);
-    key_gens.insert(i, KeyGen::<C, _>::new(dbs[&i].clone(), entropies[&i].clone()));
+    key_gens
+      .insert(i, KeyGen::<C, _>::new(dbs[&i].clone(), keystore(&dbs[&i]), entropies[&i].clone()));
   };
   rebuild(&mut key_gens, 1);
   rebuild(&mut key_gens, 2);
@@ -138,6 +147,20 @@ pub async fn test_key_gen<C: Coin>() {
         ),
         res.as_ref().unwrap()
       );
+
+      // The keys shouldn't be stored in plaintext
+      let secret_share = hex::encode(substrate_keys.secret_share().to_repr());
+      let raw = dbs[&i].entries().iter().map(|(_, value)| hex::encode(value)).collect::<String>();
+      assert!(!raw.contains(&secret_share));
+
+      // The keys should be inspectable without revealing the secret shares
+      let inspected = inspect::<C, _>(&dbs[&i], Some(&keystore(&dbs[&i])));
+      assert_eq!(
+        inspected["KEY_GEN"]["keys"][0]["keys"]["substrate"]["group_key"],
+        hex::encode(substrate_keys.group_key().to_bytes())
+      );
+      assert_eq!(inspected["KEY_GEN"]["keys"][0]["keys"]["coin"]["params"]["i"], i);
+      assert!(!inspected.to_string().contains(&secret_share));
     } else {
       panic!("didn't get key back");
     }
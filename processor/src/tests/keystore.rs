@@ -0,0 +1,134 @@
+use std::thread;
+
+use zeroize::Zeroizing;
+
+use crate::{DbTxn, Db, WrappingSecret, KeyStoreError, KeyStore, tests::util::db::MemDb};
+
+fn passphrase(passphrase: &str) -> WrappingSecret {
+  WrappingSecret::Passphrase(Zeroizing::new(passphrase.to_string()))
+}
+
+#[test]
+fn keystore() {
+  let mut db = MemDb::new();
+  let keystore = KeyStore::new(db.clone(), &passphrase("original")).unwrap();
+
+  let key = MemDb::key(b"TEST", b"secret", b"a");
+  let other_key = MemDb::key(b"TEST", b"secret", b"b");
+  let secret = b"a secret which shouldn't be in the DB";
+
+  let mut txn = db.txn();
+  keystore.put(&mut txn, &key, secret).unwrap();
+  txn.commit();
+
+  // The secret shouldn't be present in plaintext
+  assert!(!Db::get(&db, &key).unwrap().windows(secret.len()).any(|window| window == secret));
+  assert_eq!(keystore.get(&key).unwrap().unwrap().as_slice(), secret);
+  assert!(keystore.get(&other_key).is_none());
+
+  // Re-opening with the same secret should work, yet a distinct secret should be rejected
+  assert_eq!(
+    KeyStore::new(db.clone(), &passphrase("original")).unwrap().get(&key).unwrap().unwrap(),
+    keystore.get(&key).unwrap().unwrap()
+  );
+  assert_eq!(
+    KeyStore::new(db.clone(), &passphrase("incorrect")).unwrap_err(),
+    KeyStoreError::IncorrectSecret
+  );
+
+  // Entries are bound to their key, so moving them should cause them to fail authentication
+  let mut txn = db.txn();
+  txn.put(&other_key, Db::get(&db, &key).unwrap());
+  txn.commit();
+  assert_eq!(keystore.get(&other_key).unwrap().unwrap_err(), KeyStoreError::InvalidEntry);
+
+  // As should modifying them
+  let mut modified = Db::get(&db, &key).unwrap();
+  *modified.last_mut().unwrap() ^= 1;
+  let mut txn = db.txn();
+  txn.put(&other_key, modified);
+  txn.commit();
+  assert_eq!(keystore.get(&other_key).unwrap().unwrap_err(), KeyStoreError::InvalidEntry);
+  let mut txn = db.txn();
+  txn.del(&other_key);
+  txn.commit();
+
+  // Rotate the wrapping secret
+  let clone = keystore.clone();
+  let encrypted = Db::get(&db, &key).unwrap();
+  keystore.rotate(&passphrase("rotated")).unwrap();
+  assert!(encrypted != Db::get(&db, &key).unwrap());
+  assert_eq!(keystore.get(&key).unwrap().unwrap().as_slice(), secret);
+
+  // Clones should also use the new wrapping key
+  assert_eq!(clone.get(&key).unwrap().unwrap().as_slice(), secret);
+  let mut txn = db.txn();
+  clone.put(&mut txn, &other_key, secret).unwrap();
+  txn.commit();
+  assert_eq!(
+    KeyStore::new(db.clone(), &passphrase("original")).unwrap_err(),
+    KeyStoreError::IncorrectSecret
+  );
+  let reopened = KeyStore::new(db.clone(), &passphrase("rotated")).unwrap();
+  assert_eq!(reopened.get(&key).unwrap().unwrap().as_slice(), secret);
+  assert_eq!(reopened.get(&other_key).unwrap().unwrap().as_slice(), secret);
+
+  // A truncated index of entries should error, not panic
+  let entries_key = MemDb::key(b"KEYSTORE", b"entries", b"");
+  let mut entries = Db::get(&db, &entries_key).unwrap();
+  entries.pop();
+  let mut txn = db.txn();
+  txn.put(&entries_key, entries);
+  txn.commit();
+  assert_eq!(reopened.rotate(&passphrase("truncated")).unwrap_err(), KeyStoreError::InvalidEntry);
+  assert_eq!(reopened.put(&mut db.txn(), &key, secret).unwrap_err(), KeyStoreError::InvalidEntry);
+
+  // As should a missing check
+  let mut txn = db.txn();
+  txn.del(MemDb::key(b"KEYSTORE", b"check", b""));
+  txn.commit();
+  assert_eq!(
+    KeyStore::new(db.clone(), &passphrase("rotated")).unwrap_err(),
+    KeyStoreError::MissingCheck
+  );
+}
+
+#[test]
+fn concurrent_rotation() {
+  let mut db = MemDb::new();
+  let keystore = KeyStore::new(db.clone(), &passphrase("0")).unwrap();
+
+  let mut txn = db.txn();
+  for i in 0u8 .. 100 {
+    keystore.put(&mut txn, &MemDb::key(b"TEST", b"secret", [i]), &[i]).unwrap();
+  }
+  txn.commit();
+
+  // Read entries while rotating, which should never read an entry under one wrapping key and
+  // decrypt it with another
+  let reader = {
+    let keystore = keystore.clone();
+    thread::spawn(move || {
+      for _ in 0 .. 10 {
+        for i in 0u8 .. 100 {
+          assert_eq!(
+            keystore.get(&MemDb::key(b"TEST", b"secret", [i])).unwrap().unwrap().as_slice(),
+            [i]
+          );
+        }
+      }
+    })
+  };
+  for i in 1 ..= 10 {
+    keystore.rotate(&passphrase(&i.to_string())).unwrap();
+  }
+  reader.join().unwrap();
+
+  let reopened = KeyStore::new(db, &passphrase("10")).unwrap();
+  for i in 0u8 .. 100 {
+    assert_eq!(
+      reopened.get(&MemDb::key(b"TEST", b"secret", [i])).unwrap().unwrap().as_slice(),
+      [i]
+    );
+  }
+}
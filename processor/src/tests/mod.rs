@@ -1,30 +1,52 @@
 pub(crate) mod util;
 
 mod key_gen;
-pub(crate) use key_gen::test_key_gen;
+pub use key_gen::test_key_gen;
+
+mod confirmations;
+pub use confirmations::test_confirmations;
 
 mod scanner;
-pub(crate) use scanner::test_scanner;
+pub use scanner::test_scanner;
 
 mod signer;
-pub(crate) use signer::{sign, test_signer};
+pub use signer::{sign, test_signer};
 
 mod wallet;
-pub(crate) use wallet::test_wallet;
+pub use wallet::test_wallet;
+
+mod scheduler;
+pub use scheduler::{test_scheduler, test_scheduler_excess_data};
 
 mod addresses;
-pub(crate) use addresses::test_addresses;
+pub use addresses::test_addresses;
+
+mod conformance;
+pub use conformance::test_conformance;
+
+#[cfg(test)]
+mod keystore;
+
+#[cfg(all(test, feature = "monero"))]
+mod monero;
+
+// Re-exported for the macros below, so they may be used by crates which don't depend on these
+#[doc(hidden)]
+pub use lazy_static;
+#[doc(hidden)]
+pub use tokio;
 
 // Effective Once
 lazy_static::lazy_static! {
-  static ref INIT_LOGGER: () = env_logger::init();
+  pub static ref INIT_LOGGER: () = env_logger::init();
 }
 
 #[macro_export]
 macro_rules! sequential {
   () => {
-    lazy_static::lazy_static! {
-      static ref SEQUENTIAL: tokio::sync::Mutex<()> = tokio::sync::Mutex::new(());
+    $crate::tests::lazy_static::lazy_static! {
+      static ref SEQUENTIAL: $crate::tests::tokio::sync::Mutex<()> =
+        $crate::tests::tokio::sync::Mutex::new(());
     }
   };
 }
@@ -33,17 +55,24 @@ macro_rules! sequential {
 macro_rules! async_sequential {
   ($(async fn $name: ident() $body: block)*) => {
     $(
-      #[tokio::test]
-      async fn $name() {
+      #[test]
+      fn $name() {
         *$crate::tests::INIT_LOGGER;
-        let guard = SEQUENTIAL.lock().await;
-        let local = tokio::task::LocalSet::new();
-        local.run_until(async move {
-          if let Err(err) = tokio::task::spawn_local(async move { $body }).await {
-            drop(guard);
-            Err(err).unwrap()
-          }
-        }).await;
+        $crate::tests::tokio::runtime::Builder::new_current_thread()
+          .enable_all()
+          .build()
+          .unwrap()
+          .block_on(async {
+            let guard = SEQUENTIAL.lock().await;
+            let local = $crate::tests::tokio::task::LocalSet::new();
+            local.run_until(async move {
+              let task = $crate::tests::tokio::task::spawn_local(async move { $body });
+              if let Err(err) = task.await {
+                drop(guard);
+                Err(err).unwrap()
+              }
+            }).await;
+          });
       }
     )*
   }
@@ -55,45 +84,77 @@ macro_rules! test_coin {
     $C: ident,
     $coin: ident,
     $key_gen: ident,
+    $confirmations: ident,
     $scanner: ident,
     $signer: ident,
     $wallet: ident,
+    $scheduler: ident,
+    $scheduler_excess_data: ident,
     $addresses: ident,
+    $conformance: ident,
   ) => {
-    use $crate::tests::{test_key_gen, test_scanner, test_signer, test_wallet, test_addresses};
+    use $crate::tests::{
+      test_key_gen, test_confirmations, test_scanner, test_signer, test_wallet, test_scheduler,
+      test_scheduler_excess_data, test_addresses, test_conformance,
+    };
 
     // This doesn't interact with a node and accordingly doesn't need to be run sequentially
-    #[tokio::test]
-    async fn $key_gen() {
-      test_key_gen::<$C>().await;
+    #[test]
+    fn $key_gen() {
+      $crate::tests::tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+        .block_on(test_key_gen::<$C>());
+    }
+
+    #[test]
+    fn $confirmations() {
+      test_confirmations::<$C>();
     }
 
-    sequential!();
+    #[test]
+    #[should_panic(expected = "tried to schedule a payment with more data than the coin supports")]
+    fn $scheduler_excess_data() {
+      test_scheduler_excess_data::<$C>();
+    }
 
-    async_sequential! {
+    $crate::sequential!();
+
+    $crate::async_sequential! {
       async fn $scanner() {
         test_scanner($coin().await).await;
       }
     }
 
-    async_sequential! {
+    $crate::async_sequential! {
       async fn $signer() {
         test_signer($coin().await).await;
       }
     }
 
-    async_sequential! {
+    $crate::async_sequential! {
       async fn $wallet() {
         test_wallet($coin().await).await;
       }
     }
 
-    async_sequential! {
+    $crate::async_sequential! {
+      async fn $scheduler() {
+        test_scheduler($coin().await).await;
+      }
+    }
+
+    $crate::async_sequential! {
       async fn $addresses() {
         test_addresses($coin().await).await;
       }
     }
+
+    $crate::async_sequential! {
+      async fn $conformance() {
+        test_conformance($coin().await).await;
+      }
+    }
   };
 }
-
-mod literal;
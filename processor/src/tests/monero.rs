@@ -0,0 +1,28 @@
+use monero_serai::{transaction::Timelock, wallet::OutputLock};
+
+use crate::coins::Monero;
+
+#[test]
+fn held_outputs() {
+  // Outputs which are solely young aren't held, as prepare_send defers spending them
+  let young = OutputLock { block: 100, coinbase: false, timelock: Timelock::None };
+  assert!(!Monero::held(&young, 101, 0));
+  assert!(!Monero::held(&young, 110, 0));
+
+  // Miner outputs are held until they mature
+  let coinbase = OutputLock { block: 100, coinbase: true, timelock: Timelock::Block(160) };
+  assert!(Monero::held(&coinbase, 101, 0));
+  assert!(Monero::held(&coinbase, 159, 0));
+  assert!(!Monero::held(&coinbase, 160, 0));
+
+  // Timelocked outputs are held until their timelock expires, which isn't when they're scanned
+  let block = OutputLock { block: 100, coinbase: false, timelock: Timelock::Block(1000) };
+  assert!(Monero::held(&block, 110, 0));
+  assert!(Monero::held(&block, 999, 0));
+  assert!(!Monero::held(&block, 1000, 0));
+
+  // Time-based timelocks are judged against the timestamp of the block they're evaluated at
+  let time = OutputLock { block: 100, coinbase: false, timelock: Timelock::Time(1_700_000_000) };
+  assert!(Monero::held(&time, 1000, 1_699_999_999));
+  assert!(!Monero::held(&time, 1000, 1_700_000_000));
+}
@@ -19,7 +19,7 @@ pub async fn test_scanner<C: Coin>(coin: C) {
   C::tweak_keys(&mut keys);
 
   // Mine blocks so there's a confirmed block
-  for _ in 0 .. C::CONFIRMATIONS {
+  for _ in 0 .. coin.confirmations() {
     coin.mine_block().await;
   }
 
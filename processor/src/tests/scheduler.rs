@@ -0,0 +1,80 @@
+use rand_core::OsRng;
+
+use frost::{Participant, dkg::tests::key_gen};
+
+use crate::{
+  Payment,
+  coins::{Output, Coin},
+  scheduler::Scheduler,
+};
+
+// Tests the Scheduler's handling of payments with data
+pub async fn test_scheduler<C: Coin>(coin: C) {
+  let mut keys = key_gen(&mut OsRng);
+  for (_, keys) in keys.iter_mut() {
+    C::tweak_keys(keys);
+  }
+  let key = keys[&Participant::new(1).unwrap()].group_key();
+
+  let block = coin.test_send(C::address(key)).await;
+  let outputs = coin.get_outputs(&block, key).await.unwrap();
+  assert_eq!(outputs.len(), 1);
+
+  let mut scheduler = Scheduler::new(key);
+  assert!(scheduler.add_outputs(outputs.clone()).is_empty());
+
+  // Schedule more payments with data than fit in a single TX
+  let data_payments = C::MAX_DATA_PAYMENTS * 3;
+  let payment =
+    |i: usize| Payment { address: C::address(key), data: Some(vec![1; i + 1]), amount: C::DUST };
+  let payments = (0 .. data_payments).map(payment).collect::<Vec<_>>();
+  let plans = scheduler.schedule(payments.clone());
+  assert_eq!(plans.len(), 1);
+  let plan = &plans[0];
+  assert_eq!(plan.inputs, outputs);
+
+  // Only MAX_DATA_PAYMENTS should remain in this TX, with the rest moved into branches
+  let (with_data, branches): (Vec<_>, Vec<_>) =
+    plan.payments.iter().partition(|payment| payment.data.is_some());
+  assert_eq!(with_data.len(), C::MAX_DATA_PAYMENTS);
+  assert_eq!(with_data.into_iter().cloned().collect::<Vec<_>>(), payments[.. C::MAX_DATA_PAYMENTS]);
+
+  // Each branch handles at most MAX_DATA_PAYMENTS payments with data
+  assert_eq!(branches.len(), 2);
+  for branch in branches {
+    assert_eq!(branch.address, C::branch_address(key));
+    assert_eq!(branch.amount, C::DUST * u64::try_from(C::MAX_DATA_PAYMENTS).unwrap());
+  }
+  assert_eq!(
+    plan.payments.iter().map(|payment| payment.amount).sum::<u64>(),
+    C::DUST * u64::try_from(data_payments).unwrap(),
+  );
+  assert!(outputs[0].amount() > C::DUST * u64::try_from(data_payments).unwrap());
+  assert_eq!(plan.change, Some(key));
+
+  // Data up to the limit is fine, with test_scheduler_excess_data covering data past it
+  let mut scheduler = Scheduler::<C>::new(key);
+  assert!(scheduler.add_outputs(outputs.clone()).is_empty());
+  let plans = scheduler.schedule(vec![Payment {
+    address: C::address(key),
+    data: Some(vec![1; C::MAX_PAYMENT_DATA]),
+    amount: C::DUST,
+  }]);
+  assert_eq!(plans.len(), 1);
+  assert_eq!(plans[0].payments[0].data.as_ref().unwrap().len(), C::MAX_PAYMENT_DATA);
+}
+
+// Payments with more data than the coin supports must never be scheduled, which panics
+pub fn test_scheduler_excess_data<C: Coin>() {
+  let mut keys = key_gen::<_, C::Curve>(&mut OsRng);
+  for (_, keys) in keys.iter_mut() {
+    C::tweak_keys(keys);
+  }
+  let key = keys[&Participant::new(1).unwrap()].group_key();
+
+  Scheduler::<C>::new(key).schedule(vec![Payment {
+    address: C::address(key),
+    data: Some(vec![1; C::MAX_PAYMENT_DATA + 1]),
+    amount: C::DUST,
+  }]);
+}
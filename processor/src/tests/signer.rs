@@ -135,7 +135,7 @@ pub async fn test_signer<C: Coin>(coin: C) {
   let key = keys[&Participant::new(1).unwrap()].group_key();
 
   let outputs = coin.get_outputs(&coin.test_send(C::address(key)).await, key).await.unwrap();
-  let sync_block = coin.get_latest_block_number().await.unwrap() - C::CONFIRMATIONS;
+  let sync_block = coin.get_latest_block_number().await.unwrap() - coin.confirmations();
   let fee = coin.get_fee().await;
 
   let amount = 2 * C::DUST;
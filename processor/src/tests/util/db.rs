@@ -39,4 +39,7 @@ impl Db for MemDb {
   fn get(&self, key: impl AsRef<[u8]>) -> Option<Vec<u8>> {
     self.0.read().unwrap().get(key.as_ref()).cloned()
   }
+  fn entries(&self) -> Vec<(Vec<u8>, Vec<u8>)> {
+    self.0.read().unwrap().iter().map(|(key, value)| (key.clone(), value.clone())).collect()
+  }
 }
@@ -91,7 +91,7 @@ pub async fn test_wallet<C: Coin>(coin: C) {
     assert!(coin.confirm_completion(&eventuality, &tx));
   }
 
-  for _ in 1 .. C::CONFIRMATIONS {
+  for _ in 1 .. coin.confirmations() {
     coin.mine_block().await;
   }
 